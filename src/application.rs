@@ -0,0 +1,566 @@
+//! Mozilla application descriptors
+//!
+//! Firefox forks and other Gecko-based applications (Thunderbird, LibreWolf,
+//! Waterfox, Floorp, Zen, Tor Browser, Mullvad Browser, SeaMonkey) store
+//! preferences in the same prefs.js format, but keep their profiles and
+//! installations in different places. An [`Application`] bundles these
+//! per-application locations so the profile and installation lookup code can
+//! work with any of them.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ffcv::Application;
+//!
+//! let app = Application::from_id("thunderbird").unwrap();
+//! assert_eq!(app.name, "Thunderbird");
+//! assert!(app.app_dir.is_none());
+//!
+//! assert_eq!(Application::FIREFOX.app_dir, Some("browser"));
+//! ```
+
+use crate::error::{Error, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// System-wide Flatpak installation directory
+#[cfg(target_os = "linux")]
+const FLATPAK_SYSTEM_DIR: &str = "/var/lib/flatpak";
+
+/// Per-user Flatpak installation directory, relative to the home directory
+#[cfg(target_os = "linux")]
+const FLATPAK_USER_DIR: &str = ".local/share/flatpak";
+
+/// Directory where snaps are mounted
+#[cfg(target_os = "linux")]
+const SNAP_DIR: &str = "/snap";
+
+/// Per-platform lists of paths
+///
+/// Profile roots are relative to the home directory on Linux and macOS, and
+/// relative to `%APPDATA%` on Windows. Installation search paths are
+/// absolute, except for entries starting with `~/` which are relative to the
+/// home directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PlatformPaths {
+    /// Paths used on Linux and other Unix-like systems
+    pub linux: &'static [&'static str],
+    /// Paths used on macOS
+    pub macos: &'static [&'static str],
+    /// Paths used on Windows
+    pub windows: &'static [&'static str],
+}
+
+impl PlatformPaths {
+    /// Paths for the platform ffcv was compiled for
+    pub fn current(&self) -> &'static [&'static str] {
+        #[cfg(target_os = "macos")]
+        {
+            self.macos
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            self.windows
+        }
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        {
+            self.linux
+        }
+    }
+}
+
+/// Description of a Gecko-based application and where it keeps its files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Application {
+    /// Identifier used on the command line (e.g., "firefox", "thunderbird")
+    pub id: &'static str,
+    /// Human-readable application name
+    pub name: &'static str,
+    /// Candidate profile root directories (containing profiles.ini)
+    pub profile_roots: PlatformPaths,
+    /// Candidate installation directories
+    pub search_paths: PlatformPaths,
+    /// Executable names used to recognise the application binary
+    pub binary_names: &'static [&'static str],
+    /// Subdirectory of the installation that holds the application omni.ja
+    /// (`browser` for Firefox-based browsers, `None` when the application
+    /// files live in the root omni.ja as in Thunderbird)
+    pub app_dir: Option<&'static str>,
+    /// Name of the profile registry file
+    pub profiles_ini: &'static str,
+    /// Name of the install-to-default-profile mapping file
+    pub installs_ini: &'static str,
+    /// Flatpak application ID, if the application is distributed on Flathub
+    pub flatpak_id: Option<&'static str>,
+    /// Snap package name, if the application is distributed as a snap
+    pub snap_name: Option<&'static str>,
+}
+
+impl Application {
+    /// Mozilla Firefox
+    pub const FIREFOX: Application = Application {
+        id: "firefox",
+        name: "Firefox",
+        profile_roots: PlatformPaths {
+            linux: &[".mozilla/firefox"],
+            macos: &["Library/Application Support/Firefox"],
+            windows: &["Mozilla/Firefox"],
+        },
+        search_paths: PlatformPaths {
+            linux: &[
+                "/usr/lib/firefox",
+                "/usr/lib64/firefox",
+                "/opt/firefox",
+                "/usr/local/firefox",
+                "/opt/firefox-beta",
+                "/opt/firefox-esr",
+            ],
+            macos: &[
+                "/Applications/Firefox.app/Contents/Resources",
+                "/Applications/Firefox Beta.app/Contents/Resources",
+                "/Applications/Firefox Developer Edition.app/Contents/Resources",
+                "/Applications/Firefox ESR.app/Contents/Resources",
+            ],
+            windows: &[
+                r"C:\Program Files\Mozilla Firefox",
+                r"C:\Program Files\Firefox Beta",
+                r"C:\Program Files\Firefox ESR",
+                r"C:\Program Files\Mozilla Firefox ESR",
+                r"C:\Program Files (x86)\Mozilla Firefox",
+                r"C:\Program Files (x86)\Firefox Beta",
+                r"C:\Program Files (x86)\Firefox ESR",
+                r"C:\Program Files (x86)\Mozilla Firefox ESR",
+                r"C:\Program Files\Mozilla Firefox Developer Edition",
+            ],
+        },
+        binary_names: &["firefox", "firefox-bin", "firefox.exe"],
+        app_dir: Some("browser"),
+        profiles_ini: "profiles.ini",
+        installs_ini: "installs.ini",
+        flatpak_id: Some("org.mozilla.firefox"),
+        snap_name: Some("firefox"),
+    };
+
+    /// Mozilla Thunderbird
+    pub const THUNDERBIRD: Application = Application {
+        id: "thunderbird",
+        name: "Thunderbird",
+        profile_roots: PlatformPaths {
+            linux: &[".thunderbird"],
+            macos: &["Library/Thunderbird"],
+            windows: &["Thunderbird"],
+        },
+        search_paths: PlatformPaths {
+            linux: &[
+                "/usr/lib/thunderbird",
+                "/usr/lib64/thunderbird",
+                "/opt/thunderbird",
+                "/usr/local/thunderbird",
+            ],
+            macos: &[
+                "/Applications/Thunderbird.app/Contents/Resources",
+                "/Applications/Thunderbird Beta.app/Contents/Resources",
+            ],
+            windows: &[
+                r"C:\Program Files\Mozilla Thunderbird",
+                r"C:\Program Files (x86)\Mozilla Thunderbird",
+            ],
+        },
+        binary_names: &["thunderbird", "thunderbird-bin", "thunderbird.exe"],
+        app_dir: None,
+        profiles_ini: "profiles.ini",
+        installs_ini: "installs.ini",
+        flatpak_id: Some("org.mozilla.Thunderbird"),
+        snap_name: Some("thunderbird"),
+    };
+
+    /// LibreWolf
+    pub const LIBREWOLF: Application = Application {
+        id: "librewolf",
+        name: "LibreWolf",
+        profile_roots: PlatformPaths {
+            linux: &[".librewolf"],
+            macos: &["Library/Application Support/librewolf"],
+            windows: &["librewolf"],
+        },
+        search_paths: PlatformPaths {
+            linux: &[
+                "/usr/lib/librewolf",
+                "/usr/lib64/librewolf",
+                "/opt/librewolf",
+            ],
+            macos: &["/Applications/LibreWolf.app/Contents/Resources"],
+            windows: &[r"C:\Program Files\LibreWolf"],
+        },
+        binary_names: &["librewolf", "librewolf-bin", "librewolf.exe"],
+        app_dir: Some("browser"),
+        profiles_ini: "profiles.ini",
+        installs_ini: "installs.ini",
+        flatpak_id: Some("io.gitlab.librewolf-community"),
+        snap_name: None,
+    };
+
+    /// Waterfox
+    pub const WATERFOX: Application = Application {
+        id: "waterfox",
+        name: "Waterfox",
+        profile_roots: PlatformPaths {
+            linux: &[".waterfox"],
+            macos: &["Library/Application Support/Waterfox"],
+            windows: &["Waterfox"],
+        },
+        search_paths: PlatformPaths {
+            linux: &["/usr/lib/waterfox", "/opt/waterfox"],
+            macos: &["/Applications/Waterfox.app/Contents/Resources"],
+            windows: &[r"C:\Program Files\Waterfox"],
+        },
+        binary_names: &["waterfox", "waterfox-bin", "waterfox.exe"],
+        app_dir: Some("browser"),
+        profiles_ini: "profiles.ini",
+        installs_ini: "installs.ini",
+        flatpak_id: Some("net.waterfox.waterfox"),
+        snap_name: None,
+    };
+
+    /// Floorp
+    pub const FLOORP: Application = Application {
+        id: "floorp",
+        name: "Floorp",
+        profile_roots: PlatformPaths {
+            linux: &[".floorp"],
+            macos: &["Library/Application Support/Floorp"],
+            windows: &["Floorp"],
+        },
+        search_paths: PlatformPaths {
+            linux: &["/usr/lib/floorp", "/opt/floorp"],
+            macos: &["/Applications/Floorp.app/Contents/Resources"],
+            windows: &[r"C:\Program Files\Ablaze Floorp"],
+        },
+        binary_names: &["floorp", "floorp-bin", "floorp.exe"],
+        app_dir: Some("browser"),
+        profiles_ini: "profiles.ini",
+        installs_ini: "installs.ini",
+        flatpak_id: Some("one.ablaze.floorp"),
+        snap_name: Some("floorp"),
+    };
+
+    /// Zen Browser
+    pub const ZEN: Application = Application {
+        id: "zen",
+        name: "Zen Browser",
+        profile_roots: PlatformPaths {
+            linux: &[".zen"],
+            macos: &["Library/Application Support/zen"],
+            windows: &["zen"],
+        },
+        search_paths: PlatformPaths {
+            linux: &["/opt/zen", "/opt/zen-browser-bin", "/usr/lib/zen-browser"],
+            macos: &["/Applications/Zen.app/Contents/Resources"],
+            windows: &[r"C:\Program Files\Zen Browser"],
+        },
+        binary_names: &["zen", "zen-bin", "zen.exe"],
+        app_dir: Some("browser"),
+        profiles_ini: "profiles.ini",
+        installs_ini: "installs.ini",
+        flatpak_id: Some("app.zen_browser.zen"),
+        snap_name: None,
+    };
+
+    /// Tor Browser (portable layout, profiles live inside the bundle)
+    pub const TOR_BROWSER: Application = Application {
+        id: "tor-browser",
+        name: "Tor Browser",
+        profile_roots: PlatformPaths {
+            linux: &[
+                ".local/share/torbrowser/tbb/x86_64/tor-browser/Browser/TorBrowser/Data/Browser",
+                "tor-browser/Browser/TorBrowser/Data/Browser",
+            ],
+            macos: &["Library/Application Support/TorBrowser-Data/Browser"],
+            windows: &[],
+        },
+        search_paths: PlatformPaths {
+            linux: &[
+                "~/.local/share/torbrowser/tbb/x86_64/tor-browser/Browser",
+                "~/tor-browser/Browser",
+            ],
+            macos: &["/Applications/Tor Browser.app/Contents/Resources"],
+            windows: &[],
+        },
+        binary_names: &["firefox", "firefox.real", "firefox.exe"],
+        app_dir: Some("browser"),
+        profiles_ini: "profiles.ini",
+        installs_ini: "installs.ini",
+        flatpak_id: None,
+        snap_name: None,
+    };
+
+    /// Mullvad Browser
+    pub const MULLVAD_BROWSER: Application = Application {
+        id: "mullvad-browser",
+        name: "Mullvad Browser",
+        profile_roots: PlatformPaths {
+            linux: &[
+                ".mullvad-browser",
+                "mullvad-browser/Browser/TorBrowser/Data/Browser",
+            ],
+            macos: &["Library/Application Support/MullvadBrowser-Data/Browser"],
+            windows: &["Mullvad/MullvadBrowser"],
+        },
+        search_paths: PlatformPaths {
+            linux: &["/usr/lib/mullvad-browser", "~/mullvad-browser/Browser"],
+            macos: &["/Applications/Mullvad Browser.app/Contents/Resources"],
+            windows: &[r"C:\Program Files\Mullvad Browser"],
+        },
+        binary_names: &["mullvad-browser", "firefox.real", "mullvadbrowser.exe"],
+        app_dir: Some("browser"),
+        profiles_ini: "profiles.ini",
+        installs_ini: "installs.ini",
+        flatpak_id: None,
+        snap_name: None,
+    };
+
+    /// SeaMonkey
+    pub const SEAMONKEY: Application = Application {
+        id: "seamonkey",
+        name: "SeaMonkey",
+        profile_roots: PlatformPaths {
+            linux: &[".mozilla/seamonkey"],
+            macos: &["Library/Application Support/SeaMonkey"],
+            windows: &["Mozilla/SeaMonkey"],
+        },
+        search_paths: PlatformPaths {
+            linux: &["/usr/lib/seamonkey", "/opt/seamonkey"],
+            macos: &["/Applications/SeaMonkey.app/Contents/Resources"],
+            windows: &[r"C:\Program Files\SeaMonkey"],
+        },
+        binary_names: &["seamonkey", "seamonkey-bin", "seamonkey.exe"],
+        app_dir: None,
+        profiles_ini: "profiles.ini",
+        installs_ini: "installs.ini",
+        flatpak_id: None,
+        snap_name: None,
+    };
+
+    /// All known applications, in auto-detection order
+    pub const ALL: &'static [Application] = &[
+        Application::FIREFOX,
+        Application::THUNDERBIRD,
+        Application::LIBREWOLF,
+        Application::WATERFOX,
+        Application::FLOORP,
+        Application::ZEN,
+        Application::TOR_BROWSER,
+        Application::MULLVAD_BROWSER,
+        Application::SEAMONKEY,
+    ];
+
+    /// Look up an application by its identifier (case-insensitive)
+    pub fn from_id(id: &str) -> Option<Application> {
+        Application::ALL
+            .iter()
+            .find(|app| app.id.eq_ignore_ascii_case(id))
+            .copied()
+    }
+
+    /// Candidate profile root directories for the current platform
+    ///
+    /// On Linux the native roots come first, followed by the Flatpak
+    /// (`~/.var/app/<id>/...`) and Snap (`~/snap/<name>/common/...`) roots.
+    ///
+    /// Returns an error if the base directory (HOME or APPDATA) is unknown.
+    pub fn profile_root_candidates(&self) -> Result<Vec<PathBuf>> {
+        let base = profile_base_directory()?;
+        let mut candidates: Vec<PathBuf> = self
+            .profile_roots
+            .current()
+            .iter()
+            .map(|root| base.join(root))
+            .collect();
+
+        #[cfg(target_os = "linux")]
+        for root in self.profile_roots.linux {
+            if let Some(flatpak_id) = self.flatpak_id {
+                candidates.push(base.join(".var/app").join(flatpak_id).join(root));
+            }
+            if let Some(snap_name) = self.snap_name {
+                candidates.push(base.join("snap").join(snap_name).join("common").join(root));
+            }
+        }
+
+        Ok(candidates)
+    }
+
+    /// Installation search paths for the current platform, with `~/` expanded
+    ///
+    /// On Linux this includes the system-wide and per-user Flatpak
+    /// installations and the Snap installation of the application.
+    pub fn installation_search_paths(&self) -> Vec<PathBuf> {
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+
+        #[allow(unused_mut)]
+        let mut paths: Vec<PathBuf> = self
+            .search_paths
+            .current()
+            .iter()
+            .filter_map(|path| match path.strip_prefix("~/") {
+                Some(rest) => home.as_ref().map(|h| h.join(rest)),
+                None => Some(PathBuf::from(path)),
+            })
+            .collect();
+
+        #[cfg(target_os = "linux")]
+        {
+            let lib_name = self.binary_names.first().copied().unwrap_or(self.id);
+
+            if let Some(flatpak_id) = self.flatpak_id {
+                let mut flatpak_roots = vec![PathBuf::from(FLATPAK_SYSTEM_DIR)];
+                if let Some(home) = &home {
+                    flatpak_roots.push(home.join(FLATPAK_USER_DIR));
+                }
+                for root in flatpak_roots {
+                    paths.push(
+                        root.join("app")
+                            .join(flatpak_id)
+                            .join("current/active/files/lib")
+                            .join(lib_name),
+                    );
+                }
+            }
+
+            if let Some(snap_name) = self.snap_name {
+                paths.push(
+                    PathBuf::from(SNAP_DIR)
+                        .join(snap_name)
+                        .join("current/usr/lib")
+                        .join(lib_name),
+                );
+            }
+        }
+
+        paths
+    }
+
+    /// Candidate omni.ja locations within an installation, application omni.ja first
+    pub fn omni_ja_paths(&self, install_path: &Path) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        if let Some(app_dir) = self.app_dir {
+            paths.push(install_path.join(app_dir).join("omni.ja"));
+        }
+        paths.push(install_path.join("omni.ja"));
+        paths
+    }
+
+    /// Whether a file name is one of this application's executables
+    pub fn is_binary_name(&self, file_name: &str) -> bool {
+        self.binary_names.contains(&file_name)
+    }
+}
+
+impl Default for Application {
+    fn default() -> Self {
+        Application::FIREFOX
+    }
+}
+
+impl std::fmt::Display for Application {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+impl std::str::FromStr for Application {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Application::from_id(s).ok_or_else(|| {
+            let known: Vec<&str> = Application::ALL.iter().map(|app| app.id).collect();
+            Error::UnknownApplication(format!("'{}'. Valid values: {}", s, known.join(", ")))
+        })
+    }
+}
+
+/// Base directory that profile roots are relative to
+fn profile_base_directory() -> Result<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        let appdata = std::env::var("APPDATA").map_err(|_| {
+            Error::ProfilesIniParse("APPDATA environment variable not set".to_string())
+        })?;
+        Ok(PathBuf::from(appdata))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let home = std::env::var("HOME").map_err(|_| {
+            Error::ProfilesIniParse("HOME environment variable not set".to_string())
+        })?;
+        Ok(PathBuf::from(home))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_id() {
+        assert_eq!(Application::from_id("firefox"), Some(Application::FIREFOX));
+        assert_eq!(
+            Application::from_id("Thunderbird"),
+            Some(Application::THUNDERBIRD)
+        );
+        assert_eq!(Application::from_id("netscape"), None);
+    }
+
+    #[test]
+    fn test_from_str_error_lists_known_ids() {
+        let err = "netscape".parse::<Application>().unwrap_err().to_string();
+        assert!(err.contains("librewolf"));
+        assert!(err.contains("seamonkey"));
+    }
+
+    #[test]
+    fn test_ids_are_unique() {
+        let mut ids: Vec<&str> = Application::ALL.iter().map(|app| app.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), Application::ALL.len());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_sandboxed_layouts() {
+        let paths = Application::FIREFOX.installation_search_paths();
+        assert!(paths.contains(&PathBuf::from(
+            "/var/lib/flatpak/app/org.mozilla.firefox/current/active/files/lib/firefox"
+        )));
+        assert!(paths.contains(&PathBuf::from("/snap/firefox/current/usr/lib/firefox")));
+
+        let roots = Application::FIREFOX.profile_root_candidates().unwrap();
+        assert!(roots[0].ends_with(".mozilla/firefox"));
+        assert!(roots
+            .iter()
+            .any(|r| r.ends_with(".var/app/org.mozilla.firefox/.mozilla/firefox")));
+        assert!(roots
+            .iter()
+            .any(|r| r.ends_with("snap/firefox/common/.mozilla/firefox")));
+    }
+
+    #[test]
+    fn test_omni_ja_paths() {
+        let install = Path::new("/usr/lib/firefox");
+        assert_eq!(
+            Application::FIREFOX.omni_ja_paths(install),
+            vec![
+                PathBuf::from("/usr/lib/firefox/browser/omni.ja"),
+                PathBuf::from("/usr/lib/firefox/omni.ja"),
+            ]
+        );
+        assert_eq!(
+            Application::THUNDERBIRD.omni_ja_paths(Path::new("/usr/lib/thunderbird")),
+            vec![PathBuf::from("/usr/lib/thunderbird/omni.ja")]
+        );
+    }
+}
@@ -0,0 +1,209 @@
+//! CityHash64 as used by Firefox
+//!
+//! Firefox derives install section names in profiles.ini from CityHash64 of
+//! the installation directory. It bundles Google's CityHash 1.0 release,
+//! whose 64-bit variant gives different results from CityHash 1.1 (what most
+//! crates implement) at every input length. This module is a direct port of
+//! the 1.0 algorithm so the hashes match the ones Firefox writes.
+
+const K0: u64 = 0xc3a5c85c97cb3127;
+const K1: u64 = 0xb492b66fbe98f273;
+const K2: u64 = 0x9ae16a3b2f90404f;
+const K3: u64 = 0xc949d7c7509e6557;
+
+/// Compute the CityHash64 (version 1.0) of a byte slice
+pub(crate) fn city_hash64(s: &[u8]) -> u64 {
+    let len = s.len();
+
+    if len <= 16 {
+        return hash_len_0_to_16(s);
+    }
+    if len <= 32 {
+        return hash_len_17_to_32(s);
+    }
+    if len <= 64 {
+        return hash_len_33_to_64(s);
+    }
+
+    // For strings over 64 bytes we hash the end first, and then as we
+    // loop we keep 56 bytes of state: v, w, x, y, and z.
+    let mut x = fetch64(s, 0);
+    let mut y = fetch64(s, len - 16) ^ K1;
+    let mut z = fetch64(s, len - 56) ^ K0;
+    let mut v = weak_hash_len_32_with_seeds(s, len - 64, len as u64, y);
+    let mut w = weak_hash_len_32_with_seeds(s, len - 32, (len as u64).wrapping_mul(K1), K0);
+    z = z.wrapping_add(shift_mix(v.1).wrapping_mul(K1));
+    x = rotate(z.wrapping_add(x), 39).wrapping_mul(K1);
+    y = rotate(y, 33).wrapping_mul(K1);
+
+    // Operate on 64-byte chunks
+    let mut remaining = (len - 1) & !63;
+    let mut offset = 0;
+    loop {
+        x = rotate(
+            x.wrapping_add(y)
+                .wrapping_add(v.0)
+                .wrapping_add(fetch64(s, offset + 16)),
+            37,
+        )
+        .wrapping_mul(K1);
+        y = rotate(
+            y.wrapping_add(v.1).wrapping_add(fetch64(s, offset + 48)),
+            42,
+        )
+        .wrapping_mul(K1);
+        x ^= w.1;
+        y ^= v.0;
+        z = rotate(z ^ w.0, 33);
+        v = weak_hash_len_32_with_seeds(s, offset, v.1.wrapping_mul(K1), x.wrapping_add(w.0));
+        w = weak_hash_len_32_with_seeds(s, offset + 32, z.wrapping_add(w.1), y);
+        std::mem::swap(&mut z, &mut x);
+        offset += 64;
+        remaining -= 64;
+        if remaining == 0 {
+            break;
+        }
+    }
+
+    hash_len_16(
+        hash_len_16(v.0, w.0)
+            .wrapping_add(shift_mix(y).wrapping_mul(K1))
+            .wrapping_add(z),
+        hash_len_16(v.1, w.1).wrapping_add(x),
+    )
+}
+
+fn fetch64(s: &[u8], offset: usize) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&s[offset..offset + 8]);
+    u64::from_le_bytes(buf)
+}
+
+fn fetch32(s: &[u8], offset: usize) -> u64 {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(&s[offset..offset + 4]);
+    u32::from_le_bytes(buf) as u64
+}
+
+fn rotate(val: u64, shift: u32) -> u64 {
+    if shift == 0 {
+        val
+    } else {
+        val.rotate_right(shift)
+    }
+}
+
+fn shift_mix(val: u64) -> u64 {
+    val ^ (val >> 47)
+}
+
+/// Hash 128 input bits down to 64 bits of output (Murmur-inspired)
+fn hash_len_16(u: u64, v: u64) -> u64 {
+    const K_MUL: u64 = 0x9ddfea08eb382d69;
+    let mut a = (u ^ v).wrapping_mul(K_MUL);
+    a ^= a >> 47;
+    let mut b = (v ^ a).wrapping_mul(K_MUL);
+    b ^= b >> 47;
+    b.wrapping_mul(K_MUL)
+}
+
+fn hash_len_0_to_16(s: &[u8]) -> u64 {
+    let len = s.len();
+
+    if len > 8 {
+        let a = fetch64(s, 0);
+        let b = fetch64(s, len - 8);
+        // RotateByAtLeast1 in the reference implementation
+        return hash_len_16(a, b.wrapping_add(len as u64).rotate_right(len as u32)) ^ b;
+    }
+    if len >= 4 {
+        let a = fetch32(s, 0);
+        return hash_len_16((len as u64).wrapping_add(a << 3), fetch32(s, len - 4));
+    }
+    if len > 0 {
+        let a = s[0] as u32;
+        let b = s[len >> 1] as u32;
+        let c = s[len - 1] as u32;
+        let y = a.wrapping_add(b << 8);
+        let z = (len as u32).wrapping_add(c << 2);
+        return shift_mix((y as u64).wrapping_mul(K2) ^ (z as u64).wrapping_mul(K3))
+            .wrapping_mul(K2);
+    }
+    K2
+}
+
+fn hash_len_17_to_32(s: &[u8]) -> u64 {
+    let len = s.len();
+    let a = fetch64(s, 0).wrapping_mul(K1);
+    let b = fetch64(s, 8);
+    let c = fetch64(s, len - 8).wrapping_mul(K2);
+    let d = fetch64(s, len - 16).wrapping_mul(K0);
+    hash_len_16(
+        rotate(a.wrapping_sub(b), 43)
+            .wrapping_add(rotate(c, 30))
+            .wrapping_add(d),
+        a.wrapping_add(rotate(b ^ K3, 20))
+            .wrapping_sub(c)
+            .wrapping_add(len as u64),
+    )
+}
+
+fn hash_len_33_to_64(s: &[u8]) -> u64 {
+    let len = s.len();
+    let mut z = fetch64(s, 24);
+    let mut a = fetch64(s, 0).wrapping_add(
+        (len as u64)
+            .wrapping_add(fetch64(s, len - 16))
+            .wrapping_mul(K0),
+    );
+    let mut b = rotate(a.wrapping_add(z), 52);
+    let mut c = rotate(a, 37);
+    a = a.wrapping_add(fetch64(s, 8));
+    c = c.wrapping_add(rotate(a, 7));
+    a = a.wrapping_add(fetch64(s, 16));
+    let vf = a.wrapping_add(z);
+    let vs = b.wrapping_add(rotate(a, 31)).wrapping_add(c);
+
+    a = fetch64(s, 16).wrapping_add(fetch64(s, len - 32));
+    z = fetch64(s, len - 8);
+    b = rotate(a.wrapping_add(z), 52);
+    c = rotate(a, 37);
+    a = a.wrapping_add(fetch64(s, len - 24));
+    c = c.wrapping_add(rotate(a, 7));
+    a = a.wrapping_add(fetch64(s, len - 16));
+    let wf = a.wrapping_add(z);
+    let ws = b.wrapping_add(rotate(a, 31)).wrapping_add(c);
+
+    let r = shift_mix(
+        vf.wrapping_add(ws)
+            .wrapping_mul(K2)
+            .wrapping_add(wf.wrapping_add(vs).wrapping_mul(K0)),
+    );
+    shift_mix(r.wrapping_mul(K0).wrapping_add(vs)).wrapping_mul(K2)
+}
+
+/// Return a 16-byte hash for 48 bytes ("weak" but fast)
+fn weak_hash_len_32_with_seeds(s: &[u8], offset: usize, mut a: u64, mut b: u64) -> (u64, u64) {
+    let w = fetch64(s, offset);
+    let x = fetch64(s, offset + 8);
+    let y = fetch64(s, offset + 16);
+    let z = fetch64(s, offset + 24);
+
+    a = a.wrapping_add(w);
+    b = rotate(b.wrapping_add(a).wrapping_add(z), 21);
+    let c = a;
+    a = a.wrapping_add(x);
+    a = a.wrapping_add(y);
+    b = b.wrapping_add(rotate(a, 44));
+    (a.wrapping_add(z), b.wrapping_add(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_input() {
+        assert_eq!(city_hash64(b""), K2);
+    }
+}
@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use ffcv::Application;
 use std::str::FromStr;
 
 /// View Firefox configuration from the command line
@@ -10,6 +11,11 @@ use std::str::FromStr;
 from the command line. Use subcommands to list profiles or inspect configuration."
 )]
 pub struct Cli {
+    /// Application to inspect (firefox, thunderbird, librewolf, waterfox, floorp,
+    /// zen, tor-browser, mullvad-browser, seamonkey) or "all" to auto-detect
+    #[arg(long, global = true, default_value = "firefox")]
+    pub app: AppSelection,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -21,6 +27,24 @@ pub enum Commands {
         /// Path to Firefox profiles directory (overrides auto-detection)
         #[arg(short = 'd', long = "profiles-dir")]
         profiles_dir: Option<std::path::PathBuf>,
+
+        /// Analyse a directory tree, mounted image or .zip/.tar.gz archive
+        /// instead of this machine's profiles
+        #[arg(long, conflicts_with = "profiles_dir")]
+        root: Option<std::path::PathBuf>,
+
+        /// Include times.json, compatibility.ini, size statistics and the installation
+        /// that owns each profile
+        #[arg(long)]
+        details: bool,
+
+        /// Link each profile to the installation that owns it
+        #[arg(long)]
+        with_install: bool,
+
+        /// List profile groups and their member profiles instead of individual profiles
+        #[arg(long, conflicts_with_all = ["details", "with_install"])]
+        groups: bool,
     },
 
     /// List Firefox installations
@@ -32,39 +56,54 @@ pub enum Commands {
         /// List all Firefox installations (default: first found)
         #[arg(long)]
         all: bool,
+
+        /// List profiles.ini install sections matched to detected installations
+        #[arg(long, conflicts_with = "all")]
+        sections: bool,
     },
 
     /// View Firefox configuration for a profile
     Config {
-        /// Firefox profile name (default: "default")
-        #[arg(short = 'p', long, default_value = "default")]
-        profile: String,
+        #[command(flatten)]
+        source: PrefSourceArgs,
 
-        /// Read preferences from stdin instead of profile directory
-        #[arg(long, conflicts_with = "profile")]
-        stdin: bool,
+        /// Query preferences by glob pattern (e.g., "network.*", "browser.*.enabled");
+        /// append "#/json/pointer" to select a value inside JSON-valued prefs
+        /// (e.g., "browser.uiCustomization.state#/placements/nav-bar")
+        #[arg(long, conflicts_with = "get")]
+        query: Vec<String>,
 
-        /// Path to Firefox profiles directory (overrides auto-detection)
-        #[arg(short = 'd', long = "profiles-dir")]
-        profiles_dir: Option<std::path::PathBuf>,
+        /// Treat --query patterns as regular expressions matched anywhere in the key
+        /// (e.g., "^network\\.(proxy|trr)\\.")
+        #[arg(long, requires = "query", conflicts_with = "search")]
+        regex: bool,
 
-        /// Path to Firefox installation directory (overrides auto-detection)
-        #[arg(long = "install-dir")]
-        install_dir: Option<std::path::PathBuf>,
+        /// Treat --query patterns as search terms; every term must occur in the key
+        /// (e.g., "proxy port")
+        #[arg(long, requires = "query")]
+        search: bool,
 
-        /// Maximum file size in bytes (default: 100MB for omni.ja, 10MB for prefs.js)
-        #[arg(long = "max-file-size", default_value = "104857600")]
-        max_file_size: usize,
+        /// Match --query patterns case-insensitively
+        #[arg(short = 'i', long = "ignore-case", requires = "query")]
+        ignore_case: bool,
 
-        /// Query preferences by glob pattern (e.g., "network.*", "browser.*.enabled")
-        #[arg(long, conflicts_with = "get")]
-        query: Vec<String>,
+        /// Filter preferences with an expression, e.g. "value > 100 AND source == user"
+        /// (fields: key, value, type, pref_type, source, source_file, explanation;
+        /// flags: locked, sticky, user, modified, explanation)
+        #[arg(long = "where", conflicts_with = "get")]
+        where_expr: Option<String>,
 
-        /// Get a single preference by exact key name (raw output)
+        /// Get a single preference by exact key name (raw output); accepts
+        /// "key#/json/pointer" for a value inside a JSON-valued pref
         #[arg(long, conflicts_with = "query")]
         get: Option<String>,
 
-        /// Output format type (default: json-object)
+        /// Decode JSON-valued string preferences and pretty-print them
+        #[arg(long = "decode-json")]
+        decode_json: bool,
+
+        /// Output format type: json-object, json-array, text, table, csv, tsv,
+        /// yaml, toml, ndjson or prefs-js (default: json-object)
         #[arg(
             long = "output-type",
             default_value = "json-object",
@@ -76,14 +115,258 @@ pub enum Commands {
         #[arg(long = "show-only-modified", conflicts_with = "all")]
         show_only_modified: bool,
 
-        /// Show all preferences including built-in defaults
-        #[arg(long)]
-        all: bool,
-
         /// Show only preferences without explanations (hidden flag)
         #[arg(long = "unexplained-only", hide = true)]
         unexplained_only: bool,
+
+        /// Fail when a user value's type differs from its default's (Firefox
+        /// ignores such values, e.g. user_pref("network.trr.mode", "2")), or
+        /// when no defaults could be loaded to check against
+        #[arg(long = "strict-types", conflicts_with = "stdin")]
+        strict_types: bool,
+    },
+
+    /// Show preference namespaces as a tree with per-branch counts
+    Tree {
+        /// Namespace to show (e.g., "browser.newtabpage"; default: all preferences)
+        prefix: Option<String>,
+
+        /// Number of namespace levels to expand below the prefix
+        #[arg(long)]
+        depth: Option<usize>,
+
+        /// Output format type: text or json (default: text)
+        #[arg(long = "output-type", default_value = "text")]
+        output_type: ReportOutputType,
+
+        #[command(flatten)]
+        source: PrefSourceArgs,
     },
+
+    /// Export preferences for deployment to other machines
+    Export {
+        /// Export format: policies-json (enterprise policies.json), mozilla-cfg
+        /// (autoconfig), distribution-ini or nix
+        #[arg(long, default_value = "policies-json")]
+        format: ExportFormat,
+
+        /// Export only preferences matching these glob patterns (e.g., "network.*")
+        #[arg(long)]
+        query: Vec<String>,
+
+        /// policies-json and nix --nix-module nixos: status for every preference:
+        /// default, user, locked or clear (default: from how each was set; lock_pref
+        /// is locked, pref is default; nixos uses the least restrictive)
+        #[arg(long)]
+        status: Option<ffcv::PolicyStatus>,
+
+        /// policies-json: map known preferences onto dedicated policies
+        /// (DNSOverHTTPS, Cookies, Homepage, DisableTelemetry, ...)
+        #[arg(long = "map-policies")]
+        map_policies: bool,
+
+        /// mozilla-cfg: obscure the file by shifting every byte by 13
+        /// (general.config.obscure_value)
+        #[arg(long)]
+        obscure: bool,
+
+        /// mozilla-cfg: also write the autoconfig.js that loads mozilla.cfg to this file
+        #[arg(long = "autoconfig")]
+        autoconfig: Option<std::path::PathBuf>,
+
+        /// distribution-ini: distribution id
+        #[arg(long = "distribution-id", default_value = "ffcv")]
+        distribution_id: String,
+
+        /// distribution-ini: distribution version
+        #[arg(long = "distribution-version", default_value = "1.0")]
+        distribution_version: String,
+
+        /// distribution-ini: description shown in the About dialog
+        #[arg(long = "distribution-about")]
+        distribution_about: Option<String>,
+
+        /// nix: wrap the attribute set in a module: none, home-manager or nixos
+        #[arg(long = "nix-module", default_value = "none")]
+        nix_module: NixModule,
+
+        /// nix: home-manager profile name (default: the --profile name)
+        #[arg(long = "nix-profile")]
+        nix_profile: Option<String>,
+
+        #[command(flatten)]
+        source: PrefSourceArgs,
+    },
+
+    /// Compare two preference sets
+    ///
+    /// Each side is a profile name, a prefs.js file, a profile directory, an
+    /// installation directory (its defaults) or an omni.ja. Prefix a side with
+    /// "profile:", "file:", "dir:", "install:" or "omni:" to force its kind.
+    Diff {
+        /// First preference set (e.g., "default-release", "./prefs.js")
+        a: String,
+
+        /// Second preference set (e.g., "install:/usr/lib/firefox")
+        b: String,
+
+        /// Output format: text (unified diff), json or prefs-js
+        /// (user.js lines that turn A into B) (default: text)
+        #[arg(long = "output-type", default_value = "text")]
+        output_type: DiffOutputType,
+
+        /// Compare only preferences matching these glob patterns (e.g., "network.*")
+        #[arg(long)]
+        query: Vec<String>,
+
+        /// Merge built-in defaults into profiles before comparing
+        #[arg(long)]
+        all: bool,
+
+        /// Path to Firefox profiles directory (overrides auto-detection)
+        #[arg(short = 'd', long = "profiles-dir")]
+        profiles_dir: Option<std::path::PathBuf>,
+
+        /// Maximum omni.ja size in bytes
+        #[arg(long = "max-file-size", default_value = "104857600")]
+        max_file_size: usize,
+    },
+
+    /// Compare the default preferences of two installations or omni.ja files
+    ///
+    /// Reports added, removed and changed built-in and global defaults; with
+    /// --profile, also the profile's modified preferences that equal the new
+    /// default or refer to a removed one.
+    DefaultsDiff {
+        /// Old installation directory (e.g., an ESR 115 install)
+        #[arg(long = "install-a", required_unless_present = "omni_a")]
+        install_a: Option<std::path::PathBuf>,
+
+        /// New installation directory
+        #[arg(long = "install-b", required_unless_present = "omni_b")]
+        install_b: Option<std::path::PathBuf>,
+
+        /// Old omni.ja (instead of --install-a)
+        #[arg(long = "omni-a", conflicts_with = "install_a")]
+        omni_a: Option<std::path::PathBuf>,
+
+        /// New omni.ja (instead of --install-b)
+        #[arg(long = "omni-b", conflicts_with = "install_b")]
+        omni_b: Option<std::path::PathBuf>,
+
+        /// Check this profile's modified preferences against the new defaults
+        /// (a profile name, prefs.js file or profile directory)
+        #[arg(short = 'p', long)]
+        profile: Option<String>,
+
+        /// Path to Firefox profiles directory (overrides auto-detection)
+        #[arg(short = 'd', long = "profiles-dir")]
+        profiles_dir: Option<std::path::PathBuf>,
+
+        /// Compare only preferences matching these glob patterns (e.g., "network.*")
+        #[arg(long)]
+        query: Vec<String>,
+
+        /// Output format type: text or json (default: text)
+        #[arg(long = "output-type", default_value = "text")]
+        output_type: ReportOutputType,
+    },
+
+    /// Find user preferences without a default in the installation
+    ///
+    /// Each one is classified as likely obsolete, dynamically created at
+    /// runtime or a likely typo, with close default keys suggested. Defaults
+    /// come from --install-dir, --omni-ja, the installation that owns the
+    /// profile or the detected installation.
+    Lint {
+        /// Lint only preferences matching these glob patterns (e.g., "network.*")
+        #[arg(long)]
+        query: Vec<String>,
+
+        /// Output format type: text or json (default: text)
+        #[arg(long = "output-type", default_value = "text")]
+        output_type: ReportOutputType,
+
+        #[command(flatten)]
+        source: PrefSourceArgs,
+    },
+}
+
+/// Where to read preferences from (shared by commands that load a profile)
+#[derive(Args, Debug)]
+pub struct PrefSourceArgs {
+    /// Firefox profile name (default: "default")
+    #[arg(short = 'p', long, default_value = "default")]
+    pub profile: String,
+
+    /// Read preferences from stdin instead of profile directory
+    #[arg(long, conflicts_with = "profile")]
+    pub stdin: bool,
+
+    /// Path to Firefox profiles directory (overrides auto-detection)
+    #[arg(short = 'd', long = "profiles-dir")]
+    pub profiles_dir: Option<std::path::PathBuf>,
+
+    /// Path to Firefox installation directory (overrides auto-detection)
+    #[arg(long = "install-dir")]
+    pub install_dir: Option<std::path::PathBuf>,
+
+    /// Path to an omni.ja to read built-in defaults from (overrides the installation's)
+    #[arg(long = "omni-ja")]
+    pub omni_ja: Option<std::path::PathBuf>,
+
+    /// Analyse a directory tree, mounted image or .zip/.tar.gz archive
+    /// instead of this machine's profiles; defaults only come from
+    /// --install-dir or --omni-ja
+    #[arg(long, conflicts_with_all = ["profiles_dir", "stdin"])]
+    pub root: Option<std::path::PathBuf>,
+
+    /// Maximum file size in bytes (default: 100MB for omni.ja, 10MB for prefs.js)
+    #[arg(long = "max-file-size", default_value = "104857600")]
+    pub max_file_size: usize,
+
+    /// Show all preferences including built-in defaults
+    #[arg(long)]
+    pub all: bool,
+}
+
+impl PrefSourceArgs {
+    /// Borrow these arguments as command parameters
+    pub fn params(&self, app: AppSelection) -> crate::commands::PrefSourceParams<'_> {
+        crate::commands::PrefSourceParams {
+            app,
+            stdin: self.stdin,
+            profile_name: &self.profile,
+            profiles_dir_opt: self.profiles_dir.as_deref(),
+            install_dir_opt: self.install_dir.as_deref(),
+            omni_ja_opt: self.omni_ja.as_deref(),
+            root_opt: self.root.as_deref(),
+            max_file_size: self.max_file_size,
+            all: self.all,
+        }
+    }
+}
+
+/// Output format type for the tree, defaults diff and lint reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportOutputType {
+    Text,
+    Json,
+}
+
+impl FromStr for ReportOutputType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(ReportOutputType::Text),
+            "json" => Ok(ReportOutputType::Json),
+            _ => Err(format!(
+                "Invalid output type: '{}'. Valid values: text, json",
+                s
+            )),
+        }
+    }
 }
 
 /// Output format type for configuration
@@ -91,28 +374,207 @@ pub enum Commands {
 pub enum OutputType {
     JsonObject,
     JsonArray,
+    /// `key = value` lines, highlighting --query matches on a terminal
+    Text,
+    /// Aligned columns with value type and source
+    Table,
+    Csv,
+    Tsv,
+    Yaml,
+    /// Tables nested by dot path
+    Toml,
+    /// One JSON entry per line
+    Ndjson,
+    /// `user_pref(...)` lines, usable as a user.js
+    PrefsJs,
+}
+
+impl OutputType {
+    /// All output types, in the order they are listed in help and errors
+    pub const ALL: [OutputType; 10] = [
+        OutputType::JsonObject,
+        OutputType::JsonArray,
+        OutputType::Text,
+        OutputType::Table,
+        OutputType::Csv,
+        OutputType::Tsv,
+        OutputType::Yaml,
+        OutputType::Toml,
+        OutputType::Ndjson,
+        OutputType::PrefsJs,
+    ];
 }
 
 impl FromStr for OutputType {
     type Err = String;
 
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_lowercase();
+        OutputType::ALL
+            .into_iter()
+            .find(|output_type| output_type.to_string() == name)
+            .ok_or_else(|| {
+                let valid: Vec<String> = OutputType::ALL.iter().map(|t| t.to_string()).collect();
+                format!(
+                    "Invalid output type: '{}'. Valid values: {}",
+                    s,
+                    valid.join(", ")
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for OutputType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputType::JsonObject => write!(f, "json-object"),
+            OutputType::JsonArray => write!(f, "json-array"),
+            OutputType::Text => write!(f, "text"),
+            OutputType::Table => write!(f, "table"),
+            OutputType::Csv => write!(f, "csv"),
+            OutputType::Tsv => write!(f, "tsv"),
+            OutputType::Yaml => write!(f, "yaml"),
+            OutputType::Toml => write!(f, "toml"),
+            OutputType::Ndjson => write!(f, "ndjson"),
+            OutputType::PrefsJs => write!(f, "prefs-js"),
+        }
+    }
+}
+
+/// Export format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Firefox enterprise policies.json
+    PoliciesJson,
+    /// Autoconfig file with lockPref/defaultPref/pref calls
+    MozillaCfg,
+    /// distribution.ini with a [Preferences] section
+    DistributionIni,
+    /// Nix attribute set
+    Nix,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
-            "json-object" => Ok(OutputType::JsonObject),
-            "json-array" => Ok(OutputType::JsonArray),
+            "policies-json" => Ok(ExportFormat::PoliciesJson),
+            "mozilla-cfg" => Ok(ExportFormat::MozillaCfg),
+            "distribution-ini" => Ok(ExportFormat::DistributionIni),
+            "nix" => Ok(ExportFormat::Nix),
             _ => Err(format!(
-                "Invalid output type: '{}'. Valid values: json-object, json-array",
+                "Invalid export format: '{}'. Valid values: policies-json, mozilla-cfg, distribution-ini, nix",
                 s
             )),
         }
     }
 }
 
-impl std::fmt::Display for OutputType {
+impl std::fmt::Display for ExportFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            OutputType::JsonObject => write!(f, "json-object"),
-            OutputType::JsonArray => write!(f, "json-array"),
+            ExportFormat::PoliciesJson => write!(f, "policies-json"),
+            ExportFormat::MozillaCfg => write!(f, "mozilla-cfg"),
+            ExportFormat::DistributionIni => write!(f, "distribution-ini"),
+            ExportFormat::Nix => write!(f, "nix"),
+        }
+    }
+}
+
+/// Output format for preference diffs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffOutputType {
+    Text,
+    Json,
+    PrefsJs,
+}
+
+impl FromStr for DiffOutputType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(DiffOutputType::Text),
+            "json" => Ok(DiffOutputType::Json),
+            "prefs-js" => Ok(DiffOutputType::PrefsJs),
+            _ => Err(format!(
+                "Invalid output type: '{}'. Valid values: text, json, prefs-js",
+                s
+            )),
+        }
+    }
+}
+
+/// Module skeleton for Nix export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NixModule {
+    /// Bare attribute set
+    None,
+    /// home-manager programs.firefox.profiles.<name>.settings
+    HomeManager,
+    /// NixOS programs.firefox.preferences
+    NixOS,
+}
+
+impl FromStr for NixModule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(NixModule::None),
+            "home-manager" => Ok(NixModule::HomeManager),
+            "nixos" => Ok(NixModule::NixOS),
+            _ => Err(format!(
+                "Invalid Nix module: '{}'. Valid values: none, home-manager, nixos",
+                s
+            )),
+        }
+    }
+}
+
+/// Application selected with `--app`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppSelection {
+    /// A single application
+    One(&'static Application),
+    /// Every known application that is present on the system
+    All,
+}
+
+impl AppSelection {
+    /// Applications covered by this selection, in auto-detection order
+    pub fn applications(&self) -> &[Application] {
+        match self {
+            AppSelection::One(app) => std::slice::from_ref(app),
+            AppSelection::All => Application::ALL,
+        }
+    }
+}
+
+impl FromStr for AppSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("all") {
+            return Ok(AppSelection::All);
+        }
+        let app = s
+            .parse::<Application>()
+            .map_err(|e| format!("{}, all", e))?;
+        Application::ALL
+            .iter()
+            .find(|known| known.id == app.id)
+            .map(AppSelection::One)
+            .ok_or_else(|| format!("Unknown application '{}'", s))
+    }
+}
+
+impl std::fmt::Display for AppSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppSelection::One(app) => write!(f, "{}", app),
+            AppSelection::All => write!(f, "all"),
         }
     }
 }
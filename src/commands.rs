@@ -1,66 +1,312 @@
-use crate::cli;
+use crate::cli::{self, AppSelection};
 use ffcv::PrefValue;
-use ffcv::PrefValueExt;
 use ffcv::{
-    find_all_firefox_installations, find_firefox_installation, find_profile_path,
-    list_profiles as list_profiles_impl, merge_all_preferences, query_preferences, MergeConfig,
-    PrefSource,
+    encode_json_pref, find_all_installations_for_app, find_installation_for_app,
+    find_profile_installation, find_profile_path_for_app, list_install_sections_for_app,
+    list_profile_groups_for_app, list_profiles_for_app, Application, DelimitedFormatter,
+    JsonArrayFormatter, JsonObjectFormatter, JsonPrefPath, KeyQuery, MergeConfig, NdjsonFormatter,
+    OfflineOptions, OfflineSource, PrefFormatter, PrefMerger, PrefQuery, PrefSet, PrefSource,
+    PrefsJsFormatter, ProfileListConfig, QueryOptions, TableFormatter, TextFormatter,
+    TomlFormatter, YamlFormatter,
 };
+use serde::Serialize;
+use std::io::Write;
 
-/// Configuration parameters for viewing Firefox configuration
-pub struct ViewConfigParams<'a> {
+/// Where to load preferences from
+pub struct PrefSourceParams<'a> {
+    pub app: AppSelection,
     pub stdin: bool,
     pub profile_name: &'a str,
     pub profiles_dir_opt: Option<&'a std::path::Path>,
     pub install_dir_opt: Option<&'a std::path::Path>,
+    pub omni_ja_opt: Option<&'a std::path::Path>,
+    pub root_opt: Option<&'a std::path::Path>,
     pub max_file_size: usize,
+    pub all: bool,
+}
+
+/// Configuration parameters for viewing Firefox configuration
+pub struct ViewConfigParams<'a> {
+    pub source: PrefSourceParams<'a>,
     pub query_patterns: &'a [&'a str],
+    pub query_options: QueryOptions,
+    pub where_expr: Option<&'a str>,
+    pub decode_json: bool,
     pub get: Option<String>,
     pub output_type: cli::OutputType,
     pub show_only_modified: bool,
-    pub all: bool,
     pub unexplained_only: bool,
+    pub strict_types: bool,
+}
+
+/// Parameters for the namespace tree view
+pub struct TreeParams<'a> {
+    pub source: PrefSourceParams<'a>,
+    pub prefix: Option<&'a str>,
+    pub depth: Option<usize>,
+    pub output_type: cli::ReportOutputType,
+}
+
+/// Parameters for exporting preferences
+pub struct ExportParams<'a> {
+    pub source: PrefSourceParams<'a>,
+    pub format: cli::ExportFormat,
+    pub query_patterns: &'a [&'a str],
+    pub policy_options: ffcv::PolicyOptions,
+    pub mozilla_cfg: ffcv::MozillaCfgFormatter,
+    pub autoconfig_path: Option<&'a std::path::Path>,
+    pub distribution: ffcv::DistributionIniFormatter,
+    pub nix: ffcv::NixFormatter,
+}
+
+/// Parameters for comparing two preference sets
+pub struct DiffParams<'a> {
+    pub app: AppSelection,
+    pub a: &'a str,
+    pub b: &'a str,
+    pub output_type: cli::DiffOutputType,
+    pub query_patterns: &'a [&'a str],
+    pub all: bool,
+    pub profiles_dir_opt: Option<&'a std::path::Path>,
+    pub max_file_size: usize,
+}
+
+/// Parameters for comparing the defaults of two installations
+pub struct DefaultsDiffParams<'a> {
+    pub app: AppSelection,
+    pub install_a: Option<&'a std::path::Path>,
+    pub install_b: Option<&'a std::path::Path>,
+    pub omni_a: Option<&'a std::path::Path>,
+    pub omni_b: Option<&'a std::path::Path>,
+    pub profile: Option<&'a str>,
+    pub profiles_dir_opt: Option<&'a std::path::Path>,
+    pub query_patterns: &'a [&'a str],
+    pub output_type: cli::ReportOutputType,
+}
+
+/// Parameters for linting user preferences against the defaults
+pub struct LintParams<'a> {
+    pub source: PrefSourceParams<'a>,
+    pub query_patterns: &'a [&'a str],
+    pub output_type: cli::ReportOutputType,
+}
+
+/// Run a listing for the selected application(s) and print it as JSON
+///
+/// A single application prints the listing itself. `--app all` prints an
+/// object keyed by application ID, leaving out applications whose listing
+/// fails (typically because they are not installed).
+fn output_for_apps<T, F>(
+    selection: AppSelection,
+    profiles_dir_opt: Option<&std::path::Path>,
+    what: &str,
+    list: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: Serialize,
+    F: Fn(&Application) -> ffcv::Result<T>,
+{
+    let json = match selection {
+        AppSelection::One(app) => {
+            let value = list(app).map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to list {}: {}. Make sure {} is installed.",
+                    what,
+                    e,
+                    app.name
+                )
+            })?;
+            serde_json::to_string_pretty(&value)?
+        }
+        AppSelection::All => {
+            if profiles_dir_opt.is_some() {
+                return Err(anyhow::anyhow!(
+                    "--profiles-dir cannot be combined with --app all; \
+                     choose the application the directory belongs to"
+                )
+                .into());
+            }
+
+            let mut by_app = serde_json::Map::new();
+            for app in Application::ALL {
+                if let Ok(value) = list(app) {
+                    by_app.insert(app.id.to_string(), serde_json::to_value(value)?);
+                }
+            }
+            serde_json::to_string_pretty(&by_app)?
+        }
+    };
+
+    println!("{}", json);
+    Ok(())
 }
 
 /// List all available Firefox profiles
 pub fn list_profiles(
+    app: AppSelection,
     profiles_dir_opt: Option<&std::path::Path>,
+    root_opt: Option<&std::path::Path>,
+    details: bool,
+    with_install: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let profiles = list_profiles_impl(profiles_dir_opt).map_err(|e| {
-        anyhow::anyhow!(
-            "Failed to list profiles: {}. Make sure Firefox is installed.",
-            e
-        )
-    })?;
+    let config = if details {
+        ProfileListConfig::all()
+    } else {
+        ProfileListConfig {
+            include_install: with_install,
+            ..Default::default()
+        }
+    };
 
-    let json = serde_json::to_string_pretty(&profiles)?;
-    println!("{}", json);
-    Ok(())
+    output_for_apps(app, profiles_dir_opt, "profiles", |app| match root_opt {
+        Some(root) => open_offline(root, app, None, None)?.list_profiles(&config),
+        None => list_profiles_for_app(app, profiles_dir_opt, &config),
+    })
+}
+
+/// List Firefox profile groups
+pub fn list_profile_groups(
+    app: AppSelection,
+    profiles_dir_opt: Option<&std::path::Path>,
+    root_opt: Option<&std::path::Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    output_for_apps(app, profiles_dir_opt, "profile groups", |app| {
+        let list = match root_opt {
+            Some(root) => open_offline(root, app, None, None)?.list_profile_groups()?,
+            None => list_profile_groups_for_app(app, profiles_dir_opt)?,
+        };
+        for warning in &list.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        Ok(list.groups)
+    })
+}
+
+/// Keep the entries whose key matches one of the query patterns in each list
+///
+/// Returns the compiled query, or `None` (keeping everything) when there are
+/// no patterns.
+fn retain_query_matches(
+    patterns: &[&str],
+    options: &QueryOptions,
+    lists: &mut [&mut Vec<ffcv::PrefEntry>],
+) -> Result<Option<KeyQuery>, Box<dyn std::error::Error>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let query = KeyQuery::new(patterns, options)
+        .map_err(|e| anyhow::anyhow!("Failed to apply query: {}", e))?;
+    for list in lists.iter_mut() {
+        list.retain(|entry| query.matches(&entry.key));
+    }
+    Ok(Some(query))
+}
+
+/// Open a directory tree or archive for offline analysis of one application
+fn open_offline(
+    root: &std::path::Path,
+    app: &Application,
+    install_dir_opt: Option<&std::path::Path>,
+    omni_ja_opt: Option<&std::path::Path>,
+) -> ffcv::Result<OfflineSource> {
+    OfflineSource::open(
+        root,
+        OfflineOptions {
+            application: *app,
+            install_path: install_dir_opt.map(|p| p.to_path_buf()),
+            omni_ja: omni_ja_opt.map(|p| p.to_path_buf()),
+        },
+    )
 }
 
 /// List Firefox installations
-pub fn list_installations(all: bool) -> Result<(), Box<dyn std::error::Error>> {
-    let installations = if all {
-        find_all_firefox_installations()
-            .map_err(|e| anyhow::anyhow!("Failed to find Firefox installations: {}", e))?
-    } else {
-        match find_firefox_installation()
-            .map_err(|e| anyhow::anyhow!("Failed to find Firefox installation: {}", e))?
-        {
-            Some(install) => vec![install],
-            None => {
-                // Return empty JSON array when no installation found
-                println!("[]");
-                return Ok(());
+///
+/// Always outputs a JSON array per application (empty when none is found).
+pub fn list_installations(app: AppSelection, all: bool) -> Result<(), Box<dyn std::error::Error>> {
+    output_for_apps(app, None, "installations", |app| {
+        if all {
+            find_all_installations_for_app(app)
+        } else {
+            Ok(find_installation_for_app(app)?.into_iter().collect())
+        }
+    })
+}
+
+/// List install sections from profiles.ini with their matching installations
+pub fn list_install_sections(
+    app: AppSelection,
+    profiles_dir_opt: Option<&std::path::Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    output_for_apps(app, profiles_dir_opt, "install sections", |app| {
+        list_install_sections_for_app(app, profiles_dir_opt)
+    })
+}
+
+/// A profile to view, with the offline source it was found in (if any)
+struct ConfigProfile {
+    application: Application,
+    path: std::path::PathBuf,
+    offline: Option<OfflineSource>,
+}
+
+/// Find the profile to view and the application it belongs to
+///
+/// With `--app all` the applications are tried in auto-detection order and
+/// the first one that has a matching profile wins. With `--root` the profile
+/// is looked up in the offline source instead of on this machine.
+fn resolve_config_profile(
+    params: &PrefSourceParams,
+) -> Result<ConfigProfile, Box<dyn std::error::Error>> {
+    let mut last_error = None;
+
+    for app in params.app.applications() {
+        let found = match params.root_opt {
+            Some(root) => open_offline(root, app, params.install_dir_opt, params.omni_ja_opt)
+                .and_then(|source| {
+                    let path = source.find_profile_path(params.profile_name)?;
+                    Ok((path, Some(source)))
+                }),
+            None => find_profile_path_for_app(app, params.profile_name, params.profiles_dir_opt)
+                .map(|path| (path, None)),
+        };
+        match found {
+            Ok((path, offline)) => {
+                return Ok(ConfigProfile {
+                    application: *app,
+                    path,
+                    offline,
+                })
             }
+            Err(e) => last_error = Some(e),
         }
-    };
+    }
 
-    // Always output JSON array (even if empty)
-    let json = serde_json::to_string_pretty(&installations)?;
-    println!("{}", json);
+    let reason = last_error
+        .map(|e| e.to_string())
+        .unwrap_or_else(|| "no applications to search".to_string());
+
+    Err(anyhow::anyhow!(
+        "Failed to find profile '{}': {}. Make sure {} is installed and the profile exists.\n\
+         Use 'ffcv profile' to see available profiles.",
+        params.profile_name,
+        reason,
+        match params.app {
+            AppSelection::One(app) => app.name,
+            AppSelection::All => "a supported application",
+        }
+    )
+    .into())
+}
 
-    Ok(())
+/// Directory of the installation that owns a local profile, if known
+fn owning_installation(
+    profile: &ConfigProfile,
+    profiles_dir_opt: Option<&std::path::Path>,
+) -> Option<std::path::PathBuf> {
+    find_profile_installation(&profile.application, &profile.path, profiles_dir_opt)
+        .ok()
+        .flatten()
+        .map(|link| link.installation.path)
 }
 
 /// Read preference content from standard input
@@ -88,8 +334,36 @@ fn read_stdin_content(max_file_size: usize) -> Result<String, Box<dyn std::error
     Ok(buffer)
 }
 
-/// View configuration for a specific profile
-pub fn view_config(params: ViewConfigParams) -> Result<(), Box<dyn std::error::Error>> {
+/// Preferences loaded for a command
+struct LoadedPreferences {
+    /// Preferences to show: all of them with `--all`, otherwise user values
+    entries: Vec<ffcv::PrefEntry>,
+    /// Every loaded entry with the defaults under each user value
+    layers: PrefSet,
+    /// Sources that were loaded
+    loaded_sources: Vec<PrefSource>,
+    /// User values ignored because their type differs from the default's
+    type_mismatches: Vec<ffcv::TypeMismatch>,
+}
+
+/// Load the preferences selected by `params`
+///
+/// Stdin holds user preferences only; otherwise all preference sources are
+/// merged and merge warnings are printed to stderr.
+fn load_preferences(
+    params: &PrefSourceParams,
+) -> Result<Vec<ffcv::PrefEntry>, Box<dyn std::error::Error>> {
+    Ok(load_preference_layers(params, false)?.entries)
+}
+
+/// Load the preferences selected by `params` along with their layers
+///
+/// With `with_defaults`, defaults are loaded even when only user values are
+/// kept, so the layers can tell which user values differ from their default.
+fn load_preference_layers(
+    params: &PrefSourceParams,
+    with_defaults: bool,
+) -> Result<LoadedPreferences, Box<dyn std::error::Error>> {
     // If stdin mode, use old behavior (only parse user prefs)
     if params.stdin {
         let content = read_stdin_content(params.max_file_size)?;
@@ -102,68 +376,531 @@ pub fn view_config(params: ViewConfigParams) -> Result<(), Box<dyn std::error::E
             )
         })?;
 
-        output_preferences(&preferences, &params)?;
-        return Ok(());
+        return Ok(LoadedPreferences {
+            layers: preferences.iter().cloned().collect(),
+            entries: preferences,
+            loaded_sources: vec![PrefSource::User],
+            type_mismatches: Vec::new(),
+        });
     }
 
     // Normal mode: merge all preference sources
-    let profile_path = find_profile_path(params.profile_name, params.profiles_dir_opt).map_err(|e| {
-        anyhow::anyhow!(
-            "Failed to find profile '{}': {}. Make sure Firefox is installed and the profile exists.\n\
-             Use 'ffcv profile' to see available profiles.",
-            params.profile_name,
-            e
-        )
-    })?;
+    let profile = resolve_config_profile(params)?;
+
+    let with_defaults = with_defaults || params.all;
 
     // Configure merge
     let merge_config = MergeConfig {
-        include_builtins: params.all,
-        include_globals: params.all,
+        include_builtins: with_defaults,
+        include_globals: with_defaults,
         include_user: true,
         continue_on_error: true,
     };
 
     // Merge all preferences
-    let merged = merge_all_preferences(&profile_path, params.install_dir_opt, &merge_config)
-        .map_err(|e| anyhow::anyhow!("Failed to merge preferences: {}", e))?;
+    let report = match &profile.offline {
+        Some(source) => source.merge_report(&profile.path, &merge_config),
+        None => {
+            // Prefer the defaults of the installation that owns the profile over
+            // whichever installation auto-detection would find first
+            let owning_install = if with_defaults && params.install_dir_opt.is_none() {
+                owning_installation(&profile, params.profiles_dir_opt)
+            } else {
+                None
+            };
+            let install_dir = params.install_dir_opt.or(owning_install.as_deref());
+
+            let mut merger = PrefMerger::new(merge_config).application(profile.application);
+            if let Some(omni) = params.omni_ja_opt {
+                merger = merger.omni_ja(omni);
+            }
+            merger.merge_report(&profile.path, install_dir)
+        }
+    }
+    .map_err(|e| anyhow::anyhow!("Failed to merge preferences: {}", e))?;
+    let merged = report.merged;
 
     // Display warnings
     for warning in &merged.warnings {
         eprintln!("Warning: {}", warning);
     }
 
-    // Get preferences from merged result
-    let preferences = merged.entries;
+    let entries = if params.all {
+        merged.entries
+    } else {
+        merged
+            .entries
+            .into_iter()
+            .filter(|entry| entry.source == Some(PrefSource::User))
+            .collect()
+    };
+    Ok(LoadedPreferences {
+        entries,
+        layers: report.layers,
+        loaded_sources: merged.loaded_sources,
+        type_mismatches: report.type_mismatches,
+    })
+}
+
+/// Fail on user values whose type differs from their default's (--strict-types)
+///
+/// Without any defaults nothing can be checked, which is an error too.
+fn check_types(loaded: &LoadedPreferences) -> Result<(), Box<dyn std::error::Error>> {
+    let has_defaults = loaded
+        .loaded_sources
+        .iter()
+        .any(|source| matches!(source, PrefSource::BuiltIn | PrefSource::GlobalDefault));
+    if !has_defaults {
+        return Err(anyhow::anyhow!(
+            "No default preferences were loaded, so types cannot be checked \
+             (--strict-types); pass --install-dir or --omni-ja"
+        )
+        .into());
+    }
+
+    if !loaded.type_mismatches.is_empty() {
+        let details: Vec<String> = loaded
+            .type_mismatches
+            .iter()
+            .map(|mismatch| format!("  {}", mismatch))
+            .collect();
+        return Err(anyhow::anyhow!(
+            "{} user preference(s) have a different type than their default and are \
+             ignored by Firefox (--strict-types):\n{}",
+            loaded.type_mismatches.len(),
+            details.join("\n")
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// View configuration for a specific profile
+pub fn view_config(params: ViewConfigParams) -> Result<(), Box<dyn std::error::Error>> {
+    // The `modified` flag of --where and type checks compare user values with
+    // the defaults
+    let with_defaults = params.where_expr.is_some() || params.strict_types;
+    let loaded = load_preference_layers(&params.source, with_defaults)?;
+    if params.strict_types {
+        check_types(&loaded)?;
+    }
+    output_preferences(&loaded, &params)
+}
+
+/// Show the namespace tree of a profile's preferences
+pub fn view_tree(params: TreeParams) -> Result<(), Box<dyn std::error::Error>> {
+    // Load the defaults too, so user values equal to them don't count as modified
+    let loaded = load_preference_layers(&params.source, true)?;
+    let tree = ffcv::PrefTree::build_in(&loaded.entries, &loaded.layers);
+
+    let prefix = params.prefix.unwrap_or("");
+    let node = tree
+        .find(prefix)
+        .ok_or_else(|| anyhow::anyhow!("No preferences under '{}'", prefix))?;
+
+    match params.output_type {
+        cli::ReportOutputType::Text => print!("{}", node.render(params.depth)),
+        cli::ReportOutputType::Json => {
+            let node = match params.depth {
+                Some(depth) => node.truncated(depth),
+                None => node.clone(),
+            };
+            println!("{}", serde_json::to_string_pretty(&node)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Export a profile's preferences in a deployable format
+///
+/// Preferences that cannot be exported are reported on stderr.
+pub fn export_preferences(params: ExportParams) -> Result<(), Box<dyn std::error::Error>> {
+    let mut preferences = load_preferences(&params.source)?;
+    retain_query_matches(
+        params.query_patterns,
+        &QueryOptions::default(),
+        &mut [&mut preferences],
+    )?;
+
+    match params.format {
+        cli::ExportFormat::PoliciesJson => {
+            let export = ffcv::export_policies(&preferences, &params.policy_options);
+            for warning in &export.warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            println!("{}", serde_json::to_string_pretty(&export.to_json())?);
+        }
+        cli::ExportFormat::MozillaCfg => {
+            let mut out = std::io::stdout().lock();
+            params.mozilla_cfg.write_prefs(&preferences, &mut out)?;
+            out.flush()?;
+        }
+        cli::ExportFormat::DistributionIni => {
+            for entry in &preferences {
+                if let Some(reason) = ffcv::DistributionIniFormatter::unsupported_reason(entry) {
+                    eprintln!("Warning: {}: {}", entry.key, reason);
+                }
+            }
+            let mut out = std::io::stdout().lock();
+            params.distribution.write_prefs(&preferences, &mut out)?;
+            out.flush()?;
+        }
+        cli::ExportFormat::Nix => {
+            let mut out = std::io::stdout().lock();
+            params.nix.write_prefs(&preferences, &mut out)?;
+            out.flush()?;
+        }
+    }
+
+    if let Some(path) = params.autoconfig_path {
+        std::fs::write(path, params.mozilla_cfg.autoconfig_js()).map_err(|e| {
+            anyhow::anyhow!("Failed to write autoconfig to {}: {}", path.display(), e)
+        })?;
+    }
+
+    Ok(())
+}
+
+/// One side of a diff
+#[derive(Debug, PartialEq)]
+enum DiffSide {
+    Profile(String),
+    PrefsFile(std::path::PathBuf),
+    ProfileDir(std::path::PathBuf),
+    Install(std::path::PathBuf),
+    OmniJa(std::path::PathBuf),
+}
+
+impl DiffSide {
+    /// Interpret a diff argument
+    ///
+    /// An explicit "kind:" prefix wins; otherwise existing files are prefs.js
+    /// files (or omni.ja by extension), directories holding a prefs.js are
+    /// profiles, other directories are installations and anything else is a
+    /// profile name.
+    fn parse(spec: &str) -> Self {
+        if let Some((kind, rest)) = spec.split_once(':') {
+            let path = std::path::PathBuf::from(rest);
+            match kind {
+                "profile" => return DiffSide::Profile(rest.to_string()),
+                "file" => return DiffSide::PrefsFile(path),
+                "dir" => return DiffSide::ProfileDir(path),
+                "install" => return DiffSide::Install(path),
+                "omni" => return DiffSide::OmniJa(path),
+                _ => {}
+            }
+        }
+
+        let path = std::path::Path::new(spec);
+        if path.is_file() {
+            if path.extension().is_some_and(|ext| ext == "ja") {
+                DiffSide::OmniJa(path.to_path_buf())
+            } else {
+                DiffSide::PrefsFile(path.to_path_buf())
+            }
+        } else if path.is_dir() {
+            if path.join("prefs.js").is_file() {
+                DiffSide::ProfileDir(path.to_path_buf())
+            } else {
+                DiffSide::Install(path.to_path_buf())
+            }
+        } else {
+            DiffSide::Profile(spec.to_string())
+        }
+    }
+}
+
+/// Load the built-in and global defaults of an installation or omni.ja
+///
+/// With `detect_installation`, an installation is found when neither is
+/// given. Merge warnings are printed to stderr.
+fn load_defaults(
+    app: Application,
+    install_dir: Option<&std::path::Path>,
+    omni_ja: Option<&std::path::Path>,
+    detect_installation: bool,
+) -> Result<Vec<ffcv::PrefEntry>, Box<dyn std::error::Error>> {
+    let merge_config = MergeConfig {
+        include_user: false,
+        ..MergeConfig::default()
+    };
+    let mut merger = PrefMerger::new(merge_config)
+        .application(app)
+        .detect_installation(detect_installation);
+    if let Some(omni) = omni_ja {
+        merger = merger.omni_ja(omni);
+    }
+    let merged = merger
+        .merge(std::path::Path::new(""), install_dir)
+        .map_err(|e| anyhow::anyhow!("Failed to load default preferences: {}", e))?;
+    for warning in &merged.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    Ok(merged.entries)
+}
+
+/// Load one side of a diff; `all` merges defaults into profiles
+fn load_diff_side(
+    side: &DiffSide,
+    app_selection: AppSelection,
+    profiles_dir_opt: Option<&std::path::Path>,
+    max_file_size: usize,
+    all: bool,
+) -> Result<Vec<ffcv::PrefEntry>, Box<dyn std::error::Error>> {
+    let app = app_selection.applications()[0];
+    match side {
+        DiffSide::Profile(name) => load_preferences(&PrefSourceParams {
+            app: app_selection,
+            stdin: false,
+            profile_name: name,
+            profiles_dir_opt,
+            install_dir_opt: None,
+            omni_ja_opt: None,
+            root_opt: None,
+            max_file_size,
+            all,
+        }),
+        DiffSide::PrefsFile(path) => Ok(ffcv::parse_prefs_js_file(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?),
+        DiffSide::ProfileDir(path) => {
+            let merge_config = MergeConfig {
+                include_builtins: all,
+                include_globals: all,
+                ..MergeConfig::default()
+            };
+            let merged = PrefMerger::new(merge_config)
+                .application(app)
+                .merge(path, None)
+                .map_err(|e| anyhow::anyhow!("Failed to merge preferences: {}", e))?;
+            for warning in &merged.warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            Ok(merged.entries)
+        }
+        DiffSide::Install(path) => load_defaults(app, Some(path), None, false),
+        DiffSide::OmniJa(path) => load_defaults(app, None, Some(path), false),
+    }
+}
+
+/// Compare two preference sets and print the difference
+pub fn diff_sources(params: DiffParams) -> Result<(), Box<dyn std::error::Error>> {
+    let load = |spec: &str| {
+        load_diff_side(
+            &DiffSide::parse(spec),
+            params.app,
+            params.profiles_dir_opt,
+            params.max_file_size,
+            params.all,
+        )
+    };
+    let mut a = load(params.a)?;
+    let mut b = load(params.b)?;
+    retain_query_matches(
+        params.query_patterns,
+        &QueryOptions::default(),
+        &mut [&mut a, &mut b],
+    )?;
+
+    let diff = ffcv::diff_preferences(&a, &b);
+    match params.output_type {
+        cli::DiffOutputType::Text => print!("{}", diff.to_unified(params.a, params.b)),
+        cli::DiffOutputType::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+        cli::DiffOutputType::PrefsJs => print!("{}", diff.to_prefs_js()),
+    }
+    Ok(())
+}
 
-    output_preferences(&preferences, &params)?;
+/// Compare the defaults of two installations, optionally reviewing a profile
+pub fn diff_defaults(params: DefaultsDiffParams) -> Result<(), Box<dyn std::error::Error>> {
+    let app = params.app.applications()[0];
+    let mut old_defaults = load_defaults(app, params.install_a, params.omni_a, false)?;
+    let mut new_defaults = load_defaults(app, params.install_b, params.omni_b, false)?;
+    for (defaults, side) in [(&old_defaults, 'a'), (&new_defaults, 'b')] {
+        if defaults.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No default preferences found in --install-{side} or --omni-{side}"
+            )
+            .into());
+        }
+    }
+    let mut user = match params.profile {
+        Some(spec) => load_diff_side(
+            &DiffSide::parse(spec),
+            params.app,
+            params.profiles_dir_opt,
+            ffcv::DEFAULT_MAX_OMNI_SIZE,
+            false,
+        )?,
+        None => Vec::new(),
+    };
+    retain_query_matches(
+        params.query_patterns,
+        &QueryOptions::default(),
+        &mut [&mut old_defaults, &mut new_defaults, &mut user],
+    )?;
+
+    let diff = ffcv::diff_preferences(&old_defaults, &new_defaults);
+    let review = params
+        .profile
+        .map(|_| ffcv::review_user_prefs(&user, &old_defaults, &new_defaults));
+
+    match params.output_type {
+        cli::ReportOutputType::Text => {
+            let label = |install: Option<&std::path::Path>, omni: Option<&std::path::Path>| {
+                install
+                    .or(omni)
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_default()
+            };
+            print!(
+                "{}",
+                diff.to_unified(
+                    &label(params.install_a, params.omni_a),
+                    &label(params.install_b, params.omni_b)
+                )
+            );
+            if let Some(review) = &review {
+                let sections = [
+                    (
+                        "Modified preferences now equal to the default (can be reset)",
+                        &review.redundant,
+                    ),
+                    (
+                        "Modified preferences whose default was removed",
+                        &review.removed,
+                    ),
+                ];
+                for (title, entries) in sections {
+                    if entries.is_empty() {
+                        continue;
+                    }
+                    println!("\n{}:", title);
+                    for entry in entries {
+                        println!("  {} = {}", entry.key, entry.value);
+                    }
+                }
+            }
+        }
+        cli::ReportOutputType::Json => {
+            let json = serde_json::json!({ "defaults": diff, "user": review });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+    }
+    Ok(())
+}
 
+/// Lint a profile's preferences against the installation's defaults
+pub fn lint_preferences(params: LintParams) -> Result<(), Box<dyn std::error::Error>> {
+    let source = &params.source;
+    let mut user = load_preferences(&PrefSourceParams {
+        all: false,
+        ..*source
+    })?;
+    // Lint against the installation that owns the profile, as config does
+    let (app, owning_install) = match (source.stdin, source.root_opt) {
+        (false, None) => {
+            let profile = resolve_config_profile(source)?;
+            let install = if source.install_dir_opt.is_none() && source.omni_ja_opt.is_none() {
+                owning_installation(&profile, source.profiles_dir_opt)
+            } else {
+                None
+            };
+            (profile.application, install)
+        }
+        _ => (source.app.applications()[0], None),
+    };
+    // Offline analysis must not pick up this machine's installation
+    let defaults = load_defaults(
+        app,
+        source.install_dir_opt.or(owning_install.as_deref()),
+        source.omni_ja_opt,
+        source.root_opt.is_none(),
+    )?;
+    if defaults.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No default preferences found to lint against; pass --install-dir or --omni-ja"
+        )
+        .into());
+    }
+    retain_query_matches(
+        params.query_patterns,
+        &QueryOptions::default(),
+        &mut [&mut user],
+    )?;
+
+    let findings = ffcv::lint_user_prefs(&user, &defaults);
+
+    match params.output_type {
+        cli::ReportOutputType::Text => {
+            if findings.is_empty() {
+                println!("Every preference has a default");
+                return Ok(());
+            }
+            let sections = [
+                (ffcv::LintKind::Typo, "Likely typos"),
+                (ffcv::LintKind::Obsolete, "Likely obsolete"),
+                (ffcv::LintKind::Dynamic, "Created at runtime"),
+            ];
+            for (kind, title) in sections {
+                let matching: Vec<&ffcv::LintFinding> =
+                    findings.iter().filter(|f| f.kind == kind).collect();
+                if matching.is_empty() {
+                    continue;
+                }
+                println!("{} ({}):", title, matching.len());
+                for finding in matching {
+                    let hint = if finding.suggestions.is_empty() {
+                        String::new()
+                    } else {
+                        format!("  (did you mean {}?)", finding.suggestions.join(", "))
+                    };
+                    println!("  {} = {}{}", finding.key, finding.entry.value, hint);
+                }
+                println!();
+            }
+            println!(
+                "{} of {} preferences have no default",
+                findings.len(),
+                user.len()
+            );
+        }
+        cli::ReportOutputType::Json => {
+            println!("{}", serde_json::to_string_pretty(&findings)?);
+        }
+    }
     Ok(())
 }
 
 /// Output preferences based on configuration
 fn output_preferences(
-    preferences: &[ffcv::PrefEntry],
+    loaded: &LoadedPreferences,
     params: &ViewConfigParams,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut output_prefs = preferences.to_vec();
+    let mut output_prefs = loaded.entries.clone();
 
     // Handle --get mode: single preference retrieval with raw output
     if let Some(ref get_key) = params.get {
-        if let Some(entry) = output_prefs.iter().find(|e| e.key == *get_key) {
+        let path = JsonPrefPath::parse(get_key);
+        if let Some(entry) = output_prefs.iter().find(|e| e.key == path.key) {
             // Check unexplained-only flag
             if params.unexplained_only && entry.explanation.is_some() {
                 return Err(anyhow::anyhow!(
                     "Preference '{}' has an explanation, but --unexplained-only was specified",
-                    get_key
+                    path.key
                 )
                 .into());
             }
-            output_raw_value(&entry.value)?;
+            if path.pointer.is_some() || params.decode_json {
+                let json = path.resolve(entry).map_err(|e| anyhow::anyhow!("{}", e))?;
+                output_raw_json(&json)?;
+            } else {
+                output_raw_value(&entry.value)?;
+            }
             return Ok(());
         }
         // If preference not found, return error
-        return Err(anyhow::anyhow!("Preference '{}' not found", get_key).into());
+        return Err(anyhow::anyhow!("Preference '{}' not found", path.key).into());
     }
 
     // Apply --show-only-modified filter if flag is set
@@ -174,10 +911,24 @@ fn output_preferences(
         });
     }
 
-    // Apply queries if provided
-    if !params.query_patterns.is_empty() {
-        output_prefs = query_preferences(&output_prefs, params.query_patterns)
-            .map_err(|e| anyhow::anyhow!("Failed to apply query: {}", e))?;
+    // Apply queries if provided; a pattern may reach into JSON with "#/pointer"
+    let query_paths: Vec<JsonPrefPath> = params
+        .query_patterns
+        .iter()
+        .map(|pattern| JsonPrefPath::parse(pattern))
+        .collect();
+    let key_patterns: Vec<&str> = query_paths.iter().map(|path| path.key).collect();
+    let key_query = retain_query_matches(
+        &key_patterns,
+        &params.query_options,
+        &mut [&mut output_prefs],
+    )?;
+
+    // Apply the --where expression
+    if let Some(expression) = params.where_expr {
+        let query = PrefQuery::parse(expression)
+            .map_err(|e| anyhow::anyhow!("Failed to parse --where expression: {}", e))?;
+        output_prefs.retain(|entry| query.matches_in(entry, &loaded.layers));
     }
 
     // Apply unexplained-only filter if flag is set
@@ -188,29 +939,100 @@ fn output_preferences(
         });
     }
 
-    let json = match params.output_type {
-        cli::OutputType::JsonObject => {
-            // Convert Vec<PrefEntry> to BTreeMap for JSON object output (sorted by key)
-            // Note: json-object does NOT include source or source_file
-            let json_map: std::collections::BTreeMap<String, serde_json::Value> = output_prefs
-                .iter()
-                .map(|entry| (entry.key.clone(), entry.value.to_json_value()))
-                .collect();
-            serde_json::to_string_pretty(&json_map)?
-        }
-        cli::OutputType::JsonArray => {
-            // Use Vec<PrefEntry> directly for array output
-            // Note: json-array DOES include source and source_file (full entry)
-            let mut sorted_entries = output_prefs.clone();
+    // Replace entries matched by pointer patterns with the values they point to
+    let has_pointers = query_paths.iter().any(|path| path.pointer.is_some());
+    if has_pointers {
+        output_prefs = select_json_pointers(output_prefs, &query_paths, &params.query_options)?;
+    }
+    let decode_json = params.decode_json || has_pointers;
+
+    let formatter: Box<dyn PrefFormatter> = match params.output_type {
+        // Note: json-object does NOT include source or source_file
+        cli::OutputType::JsonObject => Box::new(JsonObjectFormatter { decode_json }),
+        // Note: json-array DOES include source and source_file (full entry)
+        cli::OutputType::JsonArray => Box::new(JsonArrayFormatter { decode_json }),
+        cli::OutputType::Text => Box::new(TextFormatter {
+            highlight: use_color().then_some(key_query.as_ref()).flatten(),
+            decode_json,
+        }),
+        cli::OutputType::Table => Box::new(TableFormatter {
+            color: use_color(),
+            ..TableFormatter::default()
+        }),
+        cli::OutputType::Csv => Box::new(DelimitedFormatter::csv()),
+        cli::OutputType::Tsv => Box::new(DelimitedFormatter::tsv()),
+        cli::OutputType::Yaml => Box::new(YamlFormatter { decode_json }),
+        cli::OutputType::Toml => Box::new(TomlFormatter),
+        cli::OutputType::Ndjson => Box::new(NdjsonFormatter { decode_json }),
+        cli::OutputType::PrefsJs => Box::new(PrefsJsFormatter),
+    };
 
-            // Sort alphabetically by key for deterministic output order
-            sorted_entries.sort_by(|a, b| a.key.cmp(&b.key));
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    formatter.write_prefs(&output_prefs, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
 
-            serde_json::to_string_pretty(&sorted_entries)?
+/// Expand entries for query patterns of the form `glob#/json/pointer`
+///
+/// An entry matched by a pattern without a pointer is kept as-is. For each
+/// matching pattern with a pointer, an entry keyed `key#/pointer` holding the
+/// value found there is added; pointers that don't resolve are skipped.
+fn select_json_pointers(
+    entries: Vec<ffcv::PrefEntry>,
+    paths: &[JsonPrefPath],
+    options: &QueryOptions,
+) -> Result<Vec<ffcv::PrefEntry>, Box<dyn std::error::Error>> {
+    let queries = paths
+        .iter()
+        .map(|path| Ok((KeyQuery::new(&[path.key], options)?, path.pointer)))
+        .collect::<ffcv::Result<Vec<_>>>()?;
+
+    let mut selected = Vec::new();
+    for entry in entries {
+        let mut keep_whole = false;
+        let mut pointed = Vec::new();
+        for (query, pointer) in &queries {
+            if !query.matches(&entry.key) {
+                continue;
+            }
+            let Some(pointer) = pointer else {
+                keep_whole = true;
+                continue;
+            };
+            let path = JsonPrefPath {
+                key: &entry.key,
+                pointer: Some(pointer),
+            };
+            if let Ok(value) = path.resolve(&entry) {
+                pointed.push(ffcv::PrefEntry {
+                    key: format!("{}#{}", entry.key, pointer),
+                    value: encode_json_pref(&value),
+                    ..entry.clone()
+                });
+            }
         }
-    };
+        if keep_whole {
+            selected.push(entry);
+        }
+        selected.extend(pointed);
+    }
+    Ok(selected)
+}
 
-    println!("{}", json);
+/// Whether to color text output: stdout is a terminal and NO_COLOR is unset
+fn use_color() -> bool {
+    use std::io::IsTerminal;
+    std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+}
+
+/// Output a decoded JSON value: strings raw, everything else as pretty JSON
+fn output_raw_json(value: &serde_json::Value) -> Result<(), Box<dyn std::error::Error>> {
+    match value {
+        serde_json::Value::String(s) => println!("{}", s),
+        other => println!("{}", serde_json::to_string_pretty(other)?),
+    }
     Ok(())
 }
 
@@ -259,8 +1081,8 @@ mod tests {
         let json_str = serde_json::to_string(&entry).unwrap();
         assert!(json_str.contains("\"pref_type\":\"user\""));
         assert!(json_str.contains("\"key\":\"test.key\""));
-        // PrefValue::String serializes as {"String": "test value"}
-        assert!(json_str.contains("\"value\":{\"String\":\"test value\"}"));
+        // Values serialize untagged, with their type alongside
+        assert!(json_str.contains("\"value\":\"test value\",\"type\":\"String\""));
         // explanation should not be present when None
         assert!(!json_str.contains("explanation"));
     }
@@ -526,4 +1348,69 @@ mod tests {
         let size_in_mb = max_size / 1_048_576;
         assert_eq!(size_in_mb, 10);
     }
+
+    #[test]
+    fn test_check_types_needs_defaults_and_lists_mismatches() {
+        use ffcv::{PrefSet, PrefSource, TypeMismatch};
+
+        let user = ffcv::parse_prefs_js(r#"user_pref("network.trr.mode", "2");"#).unwrap();
+        let defaults = ffcv::parse_prefs_js(r#"pref("network.trr.mode", 0);"#).unwrap();
+        let mut loaded = super::LoadedPreferences {
+            entries: user.clone(),
+            layers: PrefSet::new(),
+            loaded_sources: vec![PrefSource::User],
+            type_mismatches: Vec::new(),
+        };
+        let err = super::check_types(&loaded).unwrap_err().to_string();
+        assert!(
+            err.contains("No default preferences were loaded"),
+            "{}",
+            err
+        );
+
+        loaded.loaded_sources.push(PrefSource::GlobalDefault);
+        assert!(super::check_types(&loaded).is_ok());
+
+        loaded
+            .type_mismatches
+            .extend(TypeMismatch::check(&user[0], &defaults[0]));
+        let err = super::check_types(&loaded).unwrap_err().to_string();
+        assert!(
+            err.contains(
+                "network.trr.mode: user value \"2\" (String) ignored; the default 0 is Integer"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_diff_side_parse() {
+        use super::DiffSide;
+        use std::path::PathBuf;
+
+        let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/firefox-esr115");
+        assert_eq!(
+            DiffSide::parse(fixtures),
+            DiffSide::ProfileDir(PathBuf::from(fixtures))
+        );
+        let omni = format!("{}/omni-ja-esr115.ja", fixtures);
+        assert_eq!(
+            DiffSide::parse(&omni),
+            DiffSide::OmniJa(PathBuf::from(&omni))
+        );
+        let prefs = format!("{}/prefs.js", fixtures);
+        assert_eq!(
+            DiffSide::parse(&prefs),
+            DiffSide::PrefsFile(PathBuf::from(&prefs))
+        );
+        assert_eq!(
+            DiffSide::parse(&format!("install:{}", fixtures)),
+            DiffSide::Install(PathBuf::from(fixtures))
+        );
+        assert_eq!(
+            DiffSide::parse("default-release"),
+            DiffSide::Profile("default-release".to_string())
+        );
+    }
 }
@@ -0,0 +1,261 @@
+//! Deployment formats: autoconfig (`mozilla.cfg`) and `distribution.ini`
+//!
+//! Besides `user.js` ([`PrefsJsFormatter`]) and enterprise policies
+//! ([`export_policies`](crate::export_policies)), Firefox deployments set
+//! preferences with:
+//!
+//! - **Autoconfig**: a `mozilla.cfg` in the installation directory calling
+//!   `lockPref`, `defaultPref` and `pref`, enabled by an `autoconfig.js` in
+//!   `defaults/pref/` ([`MozillaCfgFormatter::autoconfig_js`]). The file may be
+//!   obscured by shifting every byte by `general.config.obscure_value`.
+//! - **`distribution.ini`**: the `[Preferences]` section of a file in the
+//!   installation's `distribution/` directory sets default values. It cannot
+//!   lock preferences.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ffcv::{parse_prefs_js, DistributionIniFormatter, MozillaCfgFormatter, PrefFormatter};
+//!
+//! let prefs = parse_prefs_js(r#"
+//!     lock_pref("app.update.enabled", false);
+//!     pref("browser.startup.homepage", "https://intranet.example/");
+//! "#)?;
+//!
+//! let cfg = MozillaCfgFormatter::default().format_prefs(&prefs)?;
+//! assert!(cfg.contains("lockPref(\"app.update.enabled\", false);\n"));
+//! assert!(cfg.contains("defaultPref(\"browser.startup.homepage\", \"https://intranet.example/\");\n"));
+//!
+//! let ini = DistributionIniFormatter::default().format_prefs(&prefs)?;
+//! assert!(ini.contains("\nbrowser.startup.homepage=\"https://intranet.example/\"\n"));
+//! assert!(ini.contains("; app.update.enabled: distribution.ini cannot lock preferences\n"));
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::error::Result;
+use crate::formatter::{prefs_js_literal, sorted, PrefFormatter, PrefsJsFormatter};
+use crate::types::{PrefEntry, PrefType, PrefValue};
+use std::io::Write;
+
+/// Byte shift Firefox uses for obscured autoconfig files by default
+pub const DEFAULT_OBSCURE_VALUE: u8 = 13;
+
+/// Autoconfig file (`mozilla.cfg`)
+///
+/// Locked preferences become `lockPref`, `pref` and `sticky_pref` defaults
+/// become `defaultPref` and user preferences become `pref`, which sets the
+/// user value on every startup. Floats are written as strings and null
+/// values as comments, as in [`PrefsJsFormatter`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MozillaCfgFormatter {
+    /// Shift every byte by this value (`general.config.obscure_value`)
+    pub obscure_value: Option<u8>,
+}
+
+impl MozillaCfgFormatter {
+    /// The `autoconfig.js` that makes Firefox load the generated file
+    ///
+    /// Install it as `defaults/pref/autoconfig.js` and the generated file as
+    /// `mozilla.cfg` in the installation directory.
+    pub fn autoconfig_js(&self) -> String {
+        format!(
+            "// Load mozilla.cfg from the installation directory\n\
+             pref(\"general.config.filename\", \"mozilla.cfg\");\n\
+             pref(\"general.config.obscure_value\", {});\n",
+            self.obscure_value.unwrap_or(0)
+        )
+    }
+
+    /// Shift every byte by `value`, as Firefox expects of obscured files
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ffcv::MozillaCfgFormatter;
+    ///
+    /// assert_eq!(MozillaCfgFormatter::obscure(b"pref", 13), b"}\x7frs");
+    /// ```
+    pub fn obscure(content: &[u8], value: u8) -> Vec<u8> {
+        content.iter().map(|b| b.wrapping_add(value)).collect()
+    }
+}
+
+impl PrefFormatter for MozillaCfgFormatter {
+    fn write_prefs(&self, entries: &[PrefEntry], out: &mut dyn Write) -> Result<()> {
+        // Firefox skips the first line of mozilla.cfg
+        let mut content = String::from("// mozilla.cfg generated by ffcv\n");
+        for entry in sorted(entries) {
+            let function = if entry.is_locked() {
+                "lockPref"
+            } else {
+                match entry.pref_type {
+                    PrefType::User => "pref",
+                    _ => "defaultPref",
+                }
+            };
+            let key = PrefsJsFormatter::quote(&entry.key);
+            let Some(value) = prefs_js_literal(&entry.value) else {
+                content.push_str(&format!("// {}: null cannot be set\n", key));
+                continue;
+            };
+            content.push_str(&format!("{}({}, {});\n", function, key, value));
+        }
+
+        match self.obscure_value {
+            Some(value) => out.write_all(&Self::obscure(content.as_bytes(), value))?,
+            None => out.write_all(content.as_bytes())?,
+        }
+        Ok(())
+    }
+}
+
+/// `distribution.ini` with a `[Global]` and a `[Preferences]` section
+///
+/// Values are written JSON-encoded, which is how Firefox parses them, so
+/// strings are quoted and escaped. Preferences the file cannot express
+/// ([`unsupported_reason`](Self::unsupported_reason)) are written as comments.
+#[derive(Debug, Clone)]
+pub struct DistributionIniFormatter {
+    /// Distribution identifier (`[Global] id`)
+    pub id: String,
+    /// Distribution version (`[Global] version`)
+    pub version: String,
+    /// Description shown in the About dialog (`[Global] about`)
+    pub about: Option<String>,
+}
+
+impl Default for DistributionIniFormatter {
+    fn default() -> Self {
+        Self {
+            id: "ffcv".to_string(),
+            version: "1.0".to_string(),
+            about: None,
+        }
+    }
+}
+
+impl DistributionIniFormatter {
+    /// Why an entry cannot be written to `distribution.ini`, if it cannot
+    pub fn unsupported_reason(entry: &PrefEntry) -> Option<&'static str> {
+        if entry.is_locked() {
+            Some("distribution.ini cannot lock preferences")
+        } else if entry.value == PrefValue::Null {
+            Some("null cannot be set")
+        } else if entry.key.is_empty()
+            || entry.key.starts_with(['[', ';', '#'])
+            || entry.key.contains(['=', '\n', '\r'])
+        {
+            Some("key cannot be written as an ini key")
+        } else {
+            None
+        }
+    }
+
+    /// Global section value on a single line
+    fn global_value(value: &str) -> String {
+        value.replace(['\n', '\r'], " ")
+    }
+}
+
+impl PrefFormatter for DistributionIniFormatter {
+    fn write_prefs(&self, entries: &[PrefEntry], out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "[Global]")?;
+        writeln!(out, "id={}", Self::global_value(&self.id))?;
+        writeln!(out, "version={}", Self::global_value(&self.version))?;
+        if let Some(about) = &self.about {
+            writeln!(out, "about={}", Self::global_value(about))?;
+        }
+        writeln!(out)?;
+        writeln!(out, "[Preferences]")?;
+        for entry in sorted(entries) {
+            if let Some(reason) = Self::unsupported_reason(entry) {
+                writeln!(
+                    out,
+                    "; {}: {}",
+                    entry.key.replace(['\n', '\r'], " "),
+                    reason
+                )?;
+                continue;
+            }
+            if let Some(value) = prefs_js_literal(&entry.value) {
+                writeln!(out, "{}={}", entry.key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_prefs_js;
+
+    #[test]
+    fn test_mozilla_cfg_functions_and_obscuring() {
+        let prefs = parse_prefs_js(
+            r#"
+            user_pref("b.user", "say \"hi\"");
+            sticky_pref("c.sticky", 1.5);
+            pref("a.locked", 1, true);
+            "#,
+        )
+        .unwrap();
+
+        let formatter = MozillaCfgFormatter::default();
+        let cfg = formatter.format_prefs(&prefs).unwrap();
+        assert_eq!(
+            cfg,
+            "// mozilla.cfg generated by ffcv\n\
+             lockPref(\"a.locked\", 1);\n\
+             pref(\"b.user\", \"say \\\"hi\\\"\");\n\
+             defaultPref(\"c.sticky\", \"1.5\");\n"
+        );
+        assert!(formatter
+            .autoconfig_js()
+            .contains("pref(\"general.config.obscure_value\", 0);"));
+
+        let obscured = MozillaCfgFormatter {
+            obscure_value: Some(DEFAULT_OBSCURE_VALUE),
+        };
+        let mut bytes = Vec::new();
+        obscured.write_prefs(&prefs, &mut bytes).unwrap();
+        let restored: Vec<u8> = bytes.iter().map(|b| b.wrapping_sub(13)).collect();
+        assert_eq!(String::from_utf8(restored).unwrap(), cfg);
+        assert!(obscured
+            .autoconfig_js()
+            .contains("pref(\"general.config.obscure_value\", 13);"));
+    }
+
+    #[test]
+    fn test_distribution_ini_quoting() {
+        let prefs = parse_prefs_js(
+            r#"
+            pref("browser.a", "line\nbreak \"q\"");
+            pref("browser.b", 42);
+            pref("browser.c", true);
+            pref("weird=key", 1);
+            lock_pref("browser.d", false);
+            "#,
+        )
+        .unwrap();
+
+        let formatter = DistributionIniFormatter {
+            about: Some("Corporate\nbuild".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            formatter.format_prefs(&prefs).unwrap(),
+            "[Global]\n\
+             id=ffcv\n\
+             version=1.0\n\
+             about=Corporate build\n\
+             \n\
+             [Preferences]\n\
+             browser.a=\"line\\nbreak \\\"q\\\"\"\n\
+             browser.b=42\n\
+             browser.c=true\n\
+             ; browser.d: distribution.ini cannot lock preferences\n\
+             ; weird=key: key cannot be written as an ini key\n"
+        );
+    }
+}
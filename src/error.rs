@@ -6,7 +6,10 @@
 use std::path::PathBuf;
 
 /// Errors that can occur during Firefox preference parsing and operations
+///
+/// New variants may be added in minor releases; match with a wildcard arm.
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
     /// Lexer error during tokenization
     #[error("Lexer error at line {line}, column {column}: {message}")]
@@ -40,14 +43,50 @@ pub enum Error {
     #[error("Invalid profile directory: {0}")]
     InvalidProfileDirectory(PathBuf),
 
+    /// A metadata file in a profile directory could not be parsed
+    #[error("Failed to parse {}: {message}", .path.display())]
+    ProfileFileParse { path: PathBuf, message: String },
+
+    /// Preference not present
+    #[error("Preference '{0}' not found")]
+    PrefNotFound(String),
+
+    /// Preference value has the wrong type or an unknown value
+    #[error("Invalid value for preference '{key}': {message}")]
+    InvalidPrefValue { key: String, message: String },
+
     /// Invalid glob pattern in query
     #[error("Invalid glob pattern: {0}")]
     InvalidGlobPattern(String),
 
+    /// Invalid query expression
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+
+    /// Preferences could not be written in an output format
+    #[error("Formatting error: {0}")]
+    Format(String),
+
     /// profiles.ini parsing error
     #[error("Failed to parse profiles.ini: {0}")]
     ProfilesIniParse(String),
 
+    /// Profile group store could not be read
+    #[error("Profile group store error: {0}")]
+    ProfileGroupStore(String),
+
+    /// Offline source could not be opened or escapes its root
+    #[error("Offline source error: {0}")]
+    OfflineSource(String),
+
+    /// Application identifier not recognised
+    #[error("Unknown application {0}")]
+    UnknownApplication(String),
+
+    /// Policy status not recognised
+    #[error("Unknown policy status {0}")]
+    UnknownPolicyStatus(String),
+
     /// Firefox installation not found
     #[error("Firefox installation not found. Searched paths: {searched_paths}")]
     FirefoxNotFound { searched_paths: String },
@@ -109,6 +109,13 @@ pub(crate) fn get_preference_explanation_static(key: &str) -> Option<&'static st
               'strict', and 'custom'. Standard mode blocks basic trackers in private windows only. \
               Strict mode blocks all known trackers everywhere and may cause some websites to break. \
               Custom mode allows granular control over which tracking protections are enabled.",
+                ),
+                (
+                    "network.cookie.cookieBehavior",
+                    "Controls which cookies Firefox accepts. Values: 0 = accept all cookies, \
+              1 = block third-party cookies, 2 = block all cookies, 3 = block cookies from \
+              unvisited sites, 4 = block cross-site tracking cookies, 5 = block tracking cookies \
+              and isolate other cross-site cookies (Total Cookie Protection, the default).",
                 ),
                 // Network and DNS
                 (
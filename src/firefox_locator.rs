@@ -3,52 +3,13 @@
 //! This module provides functionality to locate Firefox installations
 //! across different platforms (Linux, macOS, Windows).
 
+use crate::application::Application;
 use crate::error::{Error, Result};
-use crate::types::FirefoxInstallation;
-use std::fs;
+use crate::install_metadata::{read_ini, read_installation_metadata};
+use crate::install_probe::{default_probes, InstallationProbe};
+use crate::types::{FirefoxInstallation, Installation, Packaging};
 use std::path::{Path, PathBuf};
 
-/// Platform-specific Firefox installation search paths
-#[cfg(target_os = "linux")]
-const FIREFOX_SEARCH_PATHS: &[&str] = &[
-    "/usr/lib/firefox",
-    "/usr/lib64/firefox",
-    "/opt/firefox",
-    "/usr/local/firefox",
-    "/opt/firefox-beta",
-    "/opt/firefox-esr",
-];
-
-#[cfg(target_os = "macos")]
-const FIREFOX_SEARCH_PATHS: &[&str] = &[
-    "/Applications/Firefox.app/Contents/Resources",
-    "/Applications/Firefox Beta.app/Contents/Resources",
-    "/Applications/Firefox Developer Edition.app/Contents/Resources",
-    "/Applications/Firefox ESR.app/Contents/Resources",
-];
-
-#[cfg(target_os = "windows")]
-const FIREFOX_SEARCH_PATHS: &[&str] = &[
-    r"C:\Program Files\Mozilla Firefox",
-    r"C:\Program Files\Firefox Beta",
-    r"C:\Program Files\Firefox ESR",
-    r"C:\Program Files\Mozilla Firefox ESR",
-    r"C:\Program Files (x86)\Mozilla Firefox",
-    r"C:\Program Files (x86)\Firefox Beta",
-    r"C:\Program Files (x86)\Firefox ESR",
-    r"C:\Program Files (x86)\Mozilla Firefox ESR",
-    r"C:\Program Files\Mozilla Firefox Developer Edition",
-];
-
-/// Additional search paths for NixOS
-#[cfg(target_os = "linux")]
-const NIX_STORE_PATHS: &[&str] = &[
-    "/nix/var/nix/profiles/default/bin/firefox",
-    "/run/current-system/sw/bin/firefox",
-    // User-specific profile paths (we'll search /etc/profiles too)
-    "/etc/profiles",
-];
-
 /// Find the first valid Firefox installation on the system
 ///
 /// This function searches common Firefox installation paths for the current
@@ -72,15 +33,36 @@ const NIX_STORE_PATHS: &[&str] = &[
 /// }
 /// ```
 pub fn find_firefox_installation() -> Result<Option<FirefoxInstallation>> {
-    let search_paths = get_all_search_paths();
+    Ok(find_installation_for_app(&Application::FIREFOX)?.map(FirefoxInstallation::from))
+}
 
-    for path in search_paths {
-        if let Ok(install) = validate_installation(&path) {
-            return Ok(Some(install));
-        }
-    }
+/// Find the first valid installation of the given application
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ffcv::{find_installation_for_app, Application};
+///
+/// if let Some(install) = find_installation_for_app(&Application::THUNDERBIRD)? {
+///     println!("Thunderbird {} at {:?}", install.version, install.path);
+/// }
+/// # Ok::<(), ffcv::Error>(())
+/// ```
+pub fn find_installation_for_app(app: &Application) -> Result<Option<Installation>> {
+    Ok(find_first_installation(app, &default_probes()))
+}
 
-    Ok(None)
+/// The first valid installation, running each probe only if the previous
+/// ones found none
+fn find_first_installation(
+    app: &Application,
+    probes: &[Box<dyn InstallationProbe>],
+) -> Option<Installation> {
+    search_paths(app, probes).find_map(|(path, probe)| {
+        validate_installation(&path, app)
+            .ok()
+            .map(|install| install.found_by(probe))
+    })
 }
 
 /// Find all Firefox installations on the system
@@ -108,16 +90,168 @@ pub fn find_firefox_installation() -> Result<Option<FirefoxInstallation>> {
 /// }
 /// ```
 pub fn find_all_firefox_installations() -> Result<Vec<FirefoxInstallation>> {
+    Ok(find_all_installations_for_app(&Application::FIREFOX)?
+        .into_iter()
+        .map(FirefoxInstallation::from)
+        .collect())
+}
+
+/// Find all installations of the given application
+pub fn find_all_installations_for_app(app: &Application) -> Result<Vec<Installation>> {
+    find_all_installations_with_probes(app, &default_probes())
+}
+
+/// Find all installations of an application using a custom set of probes
+///
+/// Each installation records the name of the probe that found it. An
+/// installation reachable through several paths (symlinks, wrapper scripts,
+/// or copies with an identical omni.ja) is reported once, with the other
+/// paths listed in [`Installation::aliases`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ffcv::{find_all_installations_with_probes, Application, ConfigFileProbe, InstallationProbe};
+///
+/// let probes: Vec<Box<dyn InstallationProbe>> =
+///     vec![Box::new(ConfigFileProbe::new("/etc/ffcv/installations"))];
+/// let installs = find_all_installations_with_probes(&Application::FIREFOX, &probes)?;
+/// # Ok::<(), ffcv::Error>(())
+/// ```
+pub fn find_all_installations_with_probes(
+    app: &Application,
+    probes: &[Box<dyn InstallationProbe>],
+) -> Result<Vec<Installation>> {
     let mut installations = Vec::new();
-    let search_paths = get_all_search_paths();
 
-    for path in search_paths {
-        if let Ok(install) = validate_installation(&path) {
-            installations.push(install);
+    for (path, probe) in search_paths(app, probes) {
+        if let Ok(install) = validate_installation(&path, app) {
+            installations.push(install.found_by(probe));
+        }
+    }
+
+    Ok(dedupe_installations(installations, app))
+}
+
+/// Identity of an installation on disk, used for deduplication
+struct InstallIdentity {
+    /// Installation directory with all symlinks resolved
+    canonical: PathBuf,
+    /// Device and inode of the installation directory (Unix only)
+    file_id: Option<(u64, u64)>,
+    /// Resolved path and size of the application omni.ja
+    omni_ja: Option<(PathBuf, u64)>,
+}
+
+impl InstallIdentity {
+    fn of(install: &Installation, app: &Application) -> Self {
+        let canonical = install
+            .path
+            .canonicalize()
+            .unwrap_or_else(|_| install.path.clone());
+
+        let omni_ja = app.omni_ja_paths(&canonical).into_iter().find_map(|omni| {
+            let omni = omni.canonicalize().ok()?;
+            let len = std::fs::metadata(&omni).ok()?.len();
+            Some((omni, len))
+        });
+
+        Self {
+            file_id: file_id(&canonical),
+            canonical,
+            omni_ja,
+        }
+    }
+
+    /// Whether two identities refer to the same installation
+    fn same_as(&self, other: &InstallIdentity) -> bool {
+        if self.canonical == other.canonical {
+            return true;
+        }
+
+        if let (Some(a), Some(b)) = (self.file_id, other.file_id) {
+            if a == b {
+                return true;
+            }
+        }
+
+        match (&self.omni_ja, &other.omni_ja) {
+            (Some((path_a, len_a)), Some((path_b, len_b))) if len_a == len_b => {
+                path_a == path_b || files_identical(path_a, path_b)
+            }
+            _ => false,
         }
     }
+}
 
-    Ok(installations)
+/// Device and inode of a file
+#[cfg(unix)]
+fn file_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Device and inode of a file (unavailable on this platform)
+#[cfg(not(unix))]
+fn file_id(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+/// Compare two files byte by byte
+fn files_identical(a: &Path, b: &Path) -> bool {
+    use std::io::Read;
+
+    let (Ok(mut file_a), Ok(mut file_b)) = (std::fs::File::open(a), std::fs::File::open(b)) else {
+        return false;
+    };
+
+    let mut buf_a = vec![0u8; 64 * 1024];
+    let mut buf_b = vec![0u8; 64 * 1024];
+
+    loop {
+        let Ok(read_a) = file_a.read(&mut buf_a) else {
+            return false;
+        };
+        if read_a == 0 {
+            // Sizes were compared before, so both files are exhausted
+            return true;
+        }
+        if file_b.read_exact(&mut buf_b[..read_a]).is_err() || buf_a[..read_a] != buf_b[..read_a] {
+            return false;
+        }
+    }
+}
+
+/// Merge installations that refer to the same files
+///
+/// The first occurrence (in probe priority order) is kept; the paths of
+/// later duplicates, and the resolved path when it differs, become aliases.
+fn dedupe_installations(installations: Vec<Installation>, app: &Application) -> Vec<Installation> {
+    let mut unique: Vec<(Installation, InstallIdentity)> = Vec::new();
+
+    for install in installations {
+        let identity = InstallIdentity::of(&install, app);
+
+        match unique
+            .iter_mut()
+            .find(|(_, known)| known.same_as(&identity))
+        {
+            Some((existing, _)) => {
+                existing.add_alias(install.path);
+                existing.add_alias(identity.canonical);
+            }
+            None => unique.push((install, identity)),
+        }
+    }
+
+    unique
+        .into_iter()
+        .map(|(mut install, identity)| {
+            install.add_alias(identity.canonical);
+            install
+        })
+        .collect()
 }
 
 /// Get the Firefox version from an installation directory
@@ -176,7 +310,7 @@ pub fn get_firefox_version(install_path: &Path) -> Result<String> {
         if let Some(parent) = install_path.parent() {
             let browser_ini = parent.join("browserconfig.properties");
             if browser_ini.exists() {
-                if let Ok(content) = fs::read_to_string(&browser_ini) {
+                if let Ok(content) = std::fs::read_to_string(&browser_ini) {
                     for line in content.lines() {
                         if line.contains("version") {
                             if let Some(version) = line.split('=').nth(1) {
@@ -202,25 +336,23 @@ pub fn get_firefox_version(install_path: &Path) -> Result<String> {
 /// # Arguments
 ///
 /// * `path` - Path to validate
+/// * `app` - Application the installation is expected to belong to
 ///
 /// # Returns
 ///
 /// - `Ok(installation)` - Valid Firefox installation
 /// - `Err(_)` - Not a valid Firefox installation or error reading files
-fn validate_installation(path: &str) -> Result<FirefoxInstallation> {
-    let install_path = PathBuf::from(path);
+pub(crate) fn validate_installation(path: &Path, app: &Application) -> Result<Installation> {
+    let install_path = path.to_path_buf();
 
     if !install_path.exists() {
         return Err(Error::FirefoxNotFound {
-            searched_paths: path.to_string(),
+            searched_paths: path.display().to_string(),
         });
     }
 
-    // Check for omni.ja (in browser/ or root)
-    let omni_ja_paths = [
-        install_path.join("browser/omni.ja"),
-        install_path.join("omni.ja"),
-    ];
+    // Check for omni.ja (in the application directory or root)
+    let omni_ja_paths = app.omni_ja_paths(&install_path);
 
     let has_omni_ja = omni_ja_paths.iter().any(|p| p.exists());
 
@@ -235,181 +367,256 @@ fn validate_installation(path: &str) -> Result<FirefoxInstallation> {
     // At least one of these files should exist for a valid installation
     if !has_omni_ja && !has_greprefs {
         return Err(Error::FirefoxNotFound {
-            searched_paths: format!("{} (no omni.ja or greprefs.js found)", path),
+            searched_paths: format!("{} (no omni.ja or greprefs.js found)", path.display()),
         });
     }
 
     // Try to get version
     let version = get_firefox_version(&install_path).unwrap_or_else(|_| "unknown".to_string());
 
-    Ok(FirefoxInstallation {
+    let metadata = read_installation_metadata(&install_path, app, &version);
+
+    Ok(Installation {
         version,
+        metadata,
+        probe: None,
+        aliases: Vec::new(),
+        application: app.id.to_string(),
+        packaging: detect_packaging(&install_path),
         path: install_path,
         has_greprefs,
         has_omni_ja,
     })
 }
 
+/// Determine how an installation was packaged from its path
+///
+/// Symlinks are resolved first so that e.g. `/snap/firefox/current` and Nix
+/// profile links are recognised by their real location.
+fn detect_packaging(install_path: &Path) -> Packaging {
+    let resolved = install_path
+        .canonicalize()
+        .unwrap_or_else(|_| install_path.to_path_buf());
+    let path = resolved.to_string_lossy();
+
+    if path.starts_with("/nix/store/") {
+        Packaging::Nix
+    } else if path.contains("/flatpak/app/") {
+        Packaging::Flatpak
+    } else if path.starts_with("/snap/") {
+        Packaging::Snap
+    } else {
+        Packaging::System
+    }
+}
+
 /// Extract version from an .ini file
 ///
-/// Parses standard INI format files to find the Version field.
+/// Reads `Version` from the `[App]` section of application.ini, or the
+/// `Milestone` from the `[Build]` section of platform.ini.
 fn extract_version_from_ini(ini_path: &Path) -> Result<String> {
-    let content = fs::read_to_string(ini_path).map_err(|e| Error::FirefoxNotFound {
-        searched_paths: format!("{} (cannot read: {})", ini_path.display(), e),
+    let ini = read_ini(ini_path).ok_or_else(|| Error::FirefoxNotFound {
+        searched_paths: format!("{} (cannot read)", ini_path.display()),
     })?;
 
-    for line in content.lines() {
-        let line = line.trim();
-
-        // Look for "Version=X.Y.Z" format
-        if line.starts_with("Version=") || line.starts_with("Version =") {
-            if let Some(version) = line.split('=').nth(1) {
-                return Ok(version.trim().to_string());
-            }
-        }
-    }
-
-    Err(Error::FirefoxNotFound {
-        searched_paths: format!("{} (no version found)", ini_path.display()),
-    })
+    ini.get("App", "Version")
+        .or_else(|| ini.get("Build", "Milestone"))
+        .map(|version| version.trim().to_string())
+        .filter(|version| !version.is_empty())
+        .ok_or_else(|| Error::FirefoxNotFound {
+            searched_paths: format!("{} (no version found)", ini_path.display()),
+        })
 }
 
-/// Get all search paths for the current platform
+/// Candidate directories of all probes, tagged with the probe name
 ///
-/// Combines standard paths with platform-specific additions like Nix store paths.
-fn get_all_search_paths() -> Vec<String> {
-    let mut paths: Vec<String> = FIREFOX_SEARCH_PATHS.iter().map(|s| s.to_string()).collect();
+/// Paths proposed by several probes are kept once, attributed to the first
+/// probe that found them. Probes run lazily: a probe's candidates are only computed once those of
+/// the previous probes have been consumed.
+fn search_paths<'a>(
+    app: &'a Application,
+    probes: &'a [Box<dyn InstallationProbe>],
+) -> impl Iterator<Item = (PathBuf, &'static str)> + 'a {
+    let mut seen = std::collections::HashSet::new();
+    probes
+        .iter()
+        .flat_map(move |probe| {
+            let name = probe.name();
+            probe
+                .candidates(app)
+                .into_iter()
+                .map(move |path| (path, name))
+        })
+        .filter(move |(path, _)| seen.insert(path.clone()))
+}
 
-    // Add Nix store paths on Linux
-    #[cfg(target_os = "linux")]
-    {
-        for nix_path in NIX_STORE_PATHS {
-            let nix_path_buf = PathBuf::from(nix_path);
-
-            // If it's a direct path to a firefox binary, resolve it
-            if nix_path_buf.exists() || nix_path_buf.is_symlink() {
-                if let Ok(canonical) = nix_path_buf.canonicalize() {
-                    // Check if it's a firefox binary
-                    if canonical.ends_with("firefox") || canonical.ends_with("firefox-bin") {
-                        // This is a binary, get the lib/firefox directory
-                        if let Some(bin_dir) = canonical.parent() {
-                            if let Some(store_base) = bin_dir.parent() {
-                                let lib_firefox = store_base.join("lib/firefox");
-                                if lib_firefox.exists() {
-                                    paths.push(lib_firefox.to_string_lossy().to_string());
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            // Also check if nix_path itself is a directory (like /etc/profiles)
-            // and try to find firefox binaries recursively
-            if nix_path_buf.is_dir() {
-                // Search recursively for firefox binaries (need depth 4 for /etc/profiles/per-user/USER/bin/firefox)
-                let entries = walk_dir_depth(&nix_path_buf, 4);
-                for entry in entries {
-                    if entry.ends_with("firefox") || entry.ends_with("firefox-bin") {
-                        if let Ok(canonical) = entry.canonicalize() {
-                            // Get the lib/firefox directory
-                            if let Some(bin_dir) = canonical.parent() {
-                                if let Some(store_base) = bin_dir.parent() {
-                                    let lib_firefox = store_base.join("lib/firefox");
-                                    if lib_firefox.exists() {
-                                        paths.push(lib_firefox.to_string_lossy().to_string());
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    #[test]
+    fn test_get_all_search_paths_not_empty() {
+        let probes = default_probes();
+        assert!(search_paths(&Application::FIREFOX, &probes)
+            .next()
+            .is_some());
     }
 
-    // Deduplicate paths while preserving order
-    let mut seen = std::collections::HashSet::new();
-    paths.retain(|p| seen.insert(p.clone()));
-
-    paths
-}
-
-/// Walk a directory up to a specified depth, collecting paths that end with "firefox"
-#[cfg(target_os = "linux")]
-fn walk_dir_depth(dir: &Path, max_depth: usize) -> Vec<PathBuf> {
-    let mut results = Vec::new();
-    let mut current_dirs = vec![dir.to_path_buf()];
-
-    for _depth in 0..max_depth {
-        let mut next_dirs = Vec::new();
-
-        for dir_path in &current_dirs {
-            if let Ok(entries) = fs::read_dir(dir_path) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    let file_name = path.file_name();
-
-                    // Check if this is a firefox binary
-                    let is_firefox = file_name.is_some() && {
-                        let name = file_name.unwrap().to_string_lossy();
-                        name == "firefox" || name == "firefox-bin"
-                    };
-
-                    // If it's a firefox binary, add it to results
-                    if is_firefox {
-                        results.push(path.clone());
-                    }
+    /// Probe proposing a fixed directory and counting its runs
+    struct CountingProbe {
+        dir: PathBuf,
+        runs: std::rc::Rc<std::cell::Cell<usize>>,
+    }
 
-                    // If it's a directory, add to next iteration
-                    if path.is_dir() {
-                        next_dirs.push(path);
-                    }
-                }
-            }
+    impl InstallationProbe for CountingProbe {
+        fn name(&self) -> &'static str {
+            "counting"
         }
 
-        current_dirs = next_dirs;
-        if current_dirs.is_empty() {
-            break;
+        fn candidates(&self, _app: &Application) -> Vec<PathBuf> {
+            self.runs.set(self.runs.get() + 1);
+            vec![self.dir.clone()]
         }
     }
 
-    results
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
     #[test]
-    fn test_get_all_search_paths_not_empty() {
-        let paths = get_all_search_paths();
-        assert!(!paths.is_empty());
+    fn test_find_first_installation_skips_later_probes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("omni.ja"), b"").unwrap();
+        let runs: Vec<_> = (0..3).map(|_| std::rc::Rc::default()).collect();
+        let dirs = [
+            temp_dir.path().join("missing"),
+            temp_dir.path().to_path_buf(),
+            temp_dir.path().join("unused"),
+        ];
+        let probes: Vec<Box<dyn InstallationProbe>> = dirs
+            .into_iter()
+            .zip(&runs)
+            .map(|(dir, runs)| {
+                Box::new(CountingProbe {
+                    dir,
+                    runs: std::rc::Rc::clone(runs),
+                }) as Box<dyn InstallationProbe>
+            })
+            .collect();
+
+        let install = find_first_installation(&Application::FIREFOX, &probes).unwrap();
+        assert_eq!(install.path, temp_dir.path());
+        assert_eq!(install.probe.as_deref(), Some("counting"));
+
+        let counts: Vec<usize> = runs.iter().map(|runs| runs.get()).collect();
+        assert_eq!(counts, vec![1, 1, 0]);
     }
 
     #[test]
     fn test_validate_nonexistent_path() {
-        let result = validate_installation("/nonexistent/firefox/path/xyz123");
+        let result = validate_installation(
+            Path::new("/nonexistent/firefox/path/xyz123"),
+            &Application::FIREFOX,
+        );
         assert!(result.is_err());
     }
 
     #[cfg(target_os = "linux")]
     #[test]
     fn test_firefox_search_paths_include_standard_locations() {
-        let paths = get_all_search_paths();
-        assert!(paths.iter().any(|p| p.contains("firefox")));
+        let probes = default_probes();
+        assert!(search_paths(&Application::FIREFOX, &probes)
+            .any(|(p, probe)| probe == "search-paths" && p.to_string_lossy().contains("firefox")));
+    }
+
+    #[test]
+    fn test_validate_installation_uses_app_layout() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("omni.ja"), b"").unwrap();
+
+        let install = validate_installation(temp_dir.path(), &Application::THUNDERBIRD).unwrap();
+        assert_eq!(install.application, "thunderbird");
+        assert!(install.has_omni_ja);
+    }
+
+    #[test]
+    fn test_detect_packaging() {
+        assert_eq!(
+            detect_packaging(Path::new(
+                "/var/lib/flatpak/app/org.mozilla.firefox/current/active/files/lib/firefox"
+            )),
+            Packaging::Flatpak
+        );
+        assert_eq!(
+            detect_packaging(Path::new("/snap/firefox/current/usr/lib/firefox")),
+            Packaging::Snap
+        );
+        assert_eq!(
+            detect_packaging(Path::new("/nix/store/abc-firefox-128.0/lib/firefox")),
+            Packaging::Nix
+        );
+        assert_eq!(
+            detect_packaging(Path::new("/nonexistent/opt/firefox")),
+            Packaging::System
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dedupe_installations_across_symlinks() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let real = temp_dir.path().join("firefox-esr");
+        std::fs::create_dir_all(&real).unwrap();
+        std::fs::write(real.join("omni.ja"), b"omni").unwrap();
+        let link = temp_dir.path().join("firefox");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let app = Application::FIREFOX;
+        let installs = vec![
+            validate_installation(&link, &app).unwrap(),
+            validate_installation(&real, &app).unwrap(),
+        ];
+
+        let deduped = dedupe_installations(installs, &app);
+        assert_eq!(deduped.len(), 1);
+        assert_eq!(deduped[0].path, link);
+        assert_eq!(deduped[0].aliases, vec![real.canonicalize().unwrap()]);
+    }
+
+    #[test]
+    fn test_dedupe_installations_by_identical_omni_ja() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let app = Application::FIREFOX;
+        let mut installs = Vec::new();
+
+        for (name, content) in [("a", "same"), ("b", "same"), ("c", "diff")] {
+            let dir = temp_dir.path().join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("omni.ja"), content).unwrap();
+            installs.push(validate_installation(&dir, &app).unwrap());
+        }
+
+        let deduped = dedupe_installations(installs, &app);
+        assert_eq!(deduped.len(), 2);
+        assert!(deduped[0]
+            .aliases
+            .contains(&temp_dir.path().join("b").canonicalize().unwrap()));
+        assert_eq!(deduped[1].path, temp_dir.path().join("c"));
     }
 
     #[test]
     fn test_extract_version_from_ini_content() {
-        let ini_content = r#"
-[App]
-Version=128.0
-Name=Firefox
-"#;
-        // This would require creating a temp file and testing extract_version_from_ini
-        // For now, we'll just verify the logic structure
-        assert!(ini_content.contains("Version=128.0"));
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let app_ini = temp_dir.path().join("application.ini");
+        std::fs::write(
+            &app_ini,
+            "[App]\nVersion=128.0\nName=Firefox\n\n[Gecko]\nMinVersion=128.0\n",
+        )
+        .unwrap();
+        assert_eq!(extract_version_from_ini(&app_ini).unwrap(), "128.0");
+
+        let platform_ini = temp_dir.path().join("platform.ini");
+        std::fs::write(
+            &platform_ini,
+            "[Build]\nBuildID=20240101\nMilestone=115.12.0\n",
+        )
+        .unwrap();
+        assert_eq!(extract_version_from_ini(&platform_ini).unwrap(), "115.12.0");
     }
 }
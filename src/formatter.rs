@@ -0,0 +1,771 @@
+//! Output formats for preference lists
+//!
+//! Every format implements [`PrefFormatter`], which writes a slice of
+//! [`PrefEntry`]s to any [`std::io::Write`]. Formatters sort entries by key, so
+//! output is deterministic whatever order the entries were loaded in.
+//!
+//! | Formatter | Output |
+//! |-----------|--------|
+//! | [`JsonObjectFormatter`] | `{"key": value}` object |
+//! | [`JsonArrayFormatter`] | array of full entries ([`PREF_JSON_SCHEMA`](crate::PREF_JSON_SCHEMA)) |
+//! | [`NdjsonFormatter`] | one entry object per line, for streaming |
+//! | [`TextFormatter`] | `key = value` lines, optionally highlighting query matches |
+//! | [`TableFormatter`] | aligned columns with value type and source, optionally colored |
+//! | [`DelimitedFormatter`] | CSV or TSV with a header row |
+//! | [`YamlFormatter`] | `key: value` mapping |
+//! | [`TomlFormatter`] | tables nested by dot path |
+//! | [`PrefsJsFormatter`] | `user_pref(...)` lines, usable as a `user.js` |
+//!
+//! Implement the trait to add a format of your own.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ffcv::{parse_prefs_js, PrefFormatter, PrefsJsFormatter, TomlFormatter};
+//!
+//! let prefs = parse_prefs_js(r#"
+//!     user_pref("network.trr.mode", 2);
+//!     user_pref("network.trr.uri", "https://dns.example/\"q\"");
+//! "#)?;
+//!
+//! assert_eq!(
+//!     PrefsJsFormatter.format_prefs(&prefs)?,
+//!     "user_pref(\"network.trr.mode\", 2);\n\
+//!      user_pref(\"network.trr.uri\", \"https://dns.example/\\\"q\\\"\");\n"
+//! );
+//! assert_eq!(
+//!     TomlFormatter.format_prefs(&prefs)?,
+//!     "[network.trr]\nmode = 2\nuri = 'https://dns.example/\"q\"'\n"
+//! );
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::error::{Error, Result};
+use crate::json_pref::{decode_json_pref, json_or_plain, JsonPrefPath};
+use crate::query::KeyQuery;
+use crate::types::{PrefEntry, PrefSource, PrefValue, PrefValueExt};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// A preference output format
+pub trait PrefFormatter {
+    /// Write `entries` to `out`
+    fn write_prefs(&self, entries: &[PrefEntry], out: &mut dyn Write) -> Result<()>;
+
+    /// Format `entries` into a string
+    fn format_prefs(&self, entries: &[PrefEntry]) -> Result<String> {
+        let mut out = Vec::new();
+        self.write_prefs(entries, &mut out)?;
+        String::from_utf8(out).map_err(|e| Error::Format(e.to_string()))
+    }
+}
+
+/// Entries sorted by key
+pub(crate) fn sorted(entries: &[PrefEntry]) -> Vec<&PrefEntry> {
+    let mut sorted: Vec<&PrefEntry> = entries.iter().collect();
+    sorted.sort_by(|a, b| a.key.cmp(&b.key));
+    sorted
+}
+
+/// A preference value in a form Firefox can store
+///
+/// Firefox preferences have no float type, so floats become strings, which
+/// is how Firefox stores them; null cannot be stored at all.
+pub(crate) enum StoredValue<'a> {
+    Bool(bool),
+    Integer(i64),
+    String(Cow<'a, str>),
+}
+
+impl<'a> StoredValue<'a> {
+    /// The stored form of a value, or `None` for null
+    pub(crate) fn of(value: &'a PrefValue) -> Option<Self> {
+        match value {
+            PrefValue::Bool(b) => Some(Self::Bool(*b)),
+            PrefValue::Integer(i) => Some(Self::Integer(*i)),
+            PrefValue::Float(f) => Some(Self::String(Cow::Owned(f.to_string()))),
+            PrefValue::String(s) => Some(Self::String(Cow::Borrowed(s))),
+            PrefValue::Null => None,
+        }
+    }
+}
+
+/// A value as a prefs.js literal, or `None` for null
+///
+/// The literal is also valid JSON.
+pub(crate) fn prefs_js_literal(value: &PrefValue) -> Option<String> {
+    Some(match StoredValue::of(value)? {
+        StoredValue::Bool(b) => b.to_string(),
+        StoredValue::Integer(i) => i.to_string(),
+        StoredValue::String(s) => PrefsJsFormatter::quote(&s),
+    })
+}
+
+/// JSON value of an entry, with the decoded document of a JSON-valued
+/// string added as a `json` field
+fn entry_json(entry: &PrefEntry) -> Result<serde_json::Value> {
+    let mut json = serde_json::to_value(entry).map_err(|e| Error::Format(e.to_string()))?;
+    if let Some(document) = decode_json_pref(&entry.value) {
+        json["json"] = document;
+    }
+    Ok(json)
+}
+
+fn write_json(out: &mut dyn Write, json: &impl serde::Serialize) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *out, json).map_err(|e| Error::Format(e.to_string()))?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Short name of a preference source, as used in JSON output
+fn source_name(source: &Option<PrefSource>) -> &'static str {
+    match source {
+        Some(PrefSource::BuiltIn) => "builtin",
+        Some(PrefSource::GlobalDefault) => "global",
+        Some(PrefSource::User) => "user",
+        Some(PrefSource::SystemPolicy) => "policy",
+        None => "",
+    }
+}
+
+/// Value as a bare string: strings unquoted, null empty
+fn plain_value(value: &PrefValue) -> String {
+    match value {
+        PrefValue::String(s) => s.clone(),
+        PrefValue::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// `{"key": value}` object; source and type are not included
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonObjectFormatter {
+    /// Replace JSON-valued strings with the decoded document
+    pub decode_json: bool,
+}
+
+impl PrefFormatter for JsonObjectFormatter {
+    fn write_prefs(&self, entries: &[PrefEntry], out: &mut dyn Write) -> Result<()> {
+        let map: BTreeMap<&str, serde_json::Value> = entries
+            .iter()
+            .map(|entry| {
+                let value = if self.decode_json {
+                    json_or_plain(&entry.value)
+                } else {
+                    entry.value.to_json_value()
+                };
+                (entry.key.as_str(), value)
+            })
+            .collect();
+        write_json(out, &map)
+    }
+}
+
+/// Array of full entries
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonArrayFormatter {
+    /// Add the decoded document of JSON-valued strings as a `json` field
+    pub decode_json: bool,
+}
+
+impl PrefFormatter for JsonArrayFormatter {
+    fn write_prefs(&self, entries: &[PrefEntry], out: &mut dyn Write) -> Result<()> {
+        let entries = sorted(entries);
+        if !self.decode_json {
+            return write_json(out, &entries);
+        }
+        let entries = entries
+            .into_iter()
+            .map(entry_json)
+            .collect::<Result<Vec<_>>>()?;
+        write_json(out, &entries)
+    }
+}
+
+/// Newline-delimited JSON: one compact entry object per line
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NdjsonFormatter {
+    /// Add the decoded document of JSON-valued strings as a `json` field
+    pub decode_json: bool,
+}
+
+impl PrefFormatter for NdjsonFormatter {
+    fn write_prefs(&self, entries: &[PrefEntry], out: &mut dyn Write) -> Result<()> {
+        for entry in sorted(entries) {
+            let written = if self.decode_json {
+                serde_json::to_writer(&mut *out, &entry_json(entry)?)
+            } else {
+                serde_json::to_writer(&mut *out, entry)
+            };
+            written.map_err(|e| Error::Format(e.to_string()))?;
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// `key = value` lines
+///
+/// Keys of `key#/pointer` entries are highlighted on the key part only.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextFormatter<'a> {
+    /// Pretty-print the decoded document of JSON-valued strings
+    pub decode_json: bool,
+    /// Highlight the parts of each key matched by this query in bold yellow
+    pub highlight: Option<&'a KeyQuery>,
+}
+
+impl PrefFormatter for TextFormatter<'_> {
+    fn write_prefs(&self, entries: &[PrefEntry], out: &mut dyn Write) -> Result<()> {
+        for entry in sorted(entries) {
+            let key = match self.highlight {
+                Some(query) => {
+                    let pref_key = JsonPrefPath::parse(&entry.key).key;
+                    highlight_ranges(&entry.key, &query.match_ranges(pref_key))
+                }
+                None => entry.key.clone(),
+            };
+            match self
+                .decode_json
+                .then(|| decode_json_pref(&entry.value))
+                .flatten()
+            {
+                Some(document) => writeln!(
+                    out,
+                    "{} = {}",
+                    key,
+                    serde_json::to_string_pretty(&document)
+                        .map_err(|e| Error::Format(e.to_string()))?
+                )?,
+                None => writeln!(out, "{} = {}", key, entry.value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Wrap the given byte ranges of `text` in bold yellow ANSI escapes
+fn highlight_ranges(text: &str, ranges: &[std::ops::Range<usize>]) -> String {
+    let mut out = String::with_capacity(text.len() + ranges.len() * 12);
+    let mut pos = 0;
+    for range in ranges {
+        out.push_str(&text[pos..range.start]);
+        out.push_str("\x1b[1;33m");
+        out.push_str(&text[range.clone()]);
+        out.push_str("\x1b[0m");
+        pos = range.end;
+    }
+    out.push_str(&text[pos..]);
+    out
+}
+
+/// Aligned table of key, value, value type and source
+#[derive(Debug, Clone, Copy)]
+pub struct TableFormatter {
+    /// Use ANSI colors: bold header, sources colored by origin
+    pub color: bool,
+    /// Truncate longer values to this many characters
+    pub max_value_width: Option<usize>,
+}
+
+impl Default for TableFormatter {
+    fn default() -> Self {
+        Self {
+            color: false,
+            max_value_width: Some(60),
+        }
+    }
+}
+
+impl TableFormatter {
+    /// Value cell: control characters escaped, truncated to the maximum width
+    fn value_cell(&self, value: &PrefValue) -> String {
+        let text: String = value
+            .to_string()
+            .chars()
+            .flat_map(|c| {
+                let escaped: Vec<char> = if c.is_control() {
+                    c.escape_default().collect()
+                } else {
+                    vec![c]
+                };
+                escaped
+            })
+            .collect();
+        match self.max_value_width {
+            Some(max) if max > 0 && text.chars().count() > max => {
+                let mut truncated: String = text.chars().take(max - 1).collect();
+                truncated.push('…');
+                truncated
+            }
+            _ => text,
+        }
+    }
+
+    fn paint(&self, text: &str, code: &str) -> String {
+        if self.color && !code.is_empty() {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+impl PrefFormatter for TableFormatter {
+    fn write_prefs(&self, entries: &[PrefEntry], out: &mut dyn Write) -> Result<()> {
+        const HEADER: [&str; 4] = ["KEY", "VALUE", "TYPE", "SOURCE"];
+
+        let rows: Vec<(&PrefEntry, [String; 4])> = sorted(entries)
+            .into_iter()
+            .map(|entry| {
+                let cells = [
+                    entry.key.clone(),
+                    self.value_cell(&entry.value),
+                    entry.value.type_name().to_string(),
+                    source_name(&entry.source).to_string(),
+                ];
+                (entry, cells)
+            })
+            .collect();
+
+        let mut widths = HEADER.map(|title| title.chars().count());
+        for (_, cells) in &rows {
+            for (width, cell) in widths.iter_mut().zip(cells) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        // Pad before painting so escape codes don't count towards the width
+        let pad = |text: &str, width: usize| {
+            format!("{}{}", text, " ".repeat(width - text.chars().count()))
+        };
+
+        let header: Vec<String> = HEADER
+            .iter()
+            .zip(widths)
+            .map(|(title, width)| pad(title, width))
+            .collect();
+        writeln!(out, "{}", self.paint(header.join("  ").trim_end(), "1"))?;
+
+        for (entry, cells) in &rows {
+            let source_color = match entry.source {
+                Some(PrefSource::User) => "32",
+                Some(PrefSource::BuiltIn) => "2",
+                Some(PrefSource::GlobalDefault) => "34",
+                Some(PrefSource::SystemPolicy) => "35",
+                None => "",
+            };
+            let line = [
+                pad(&cells[0], widths[0]),
+                pad(&cells[1], widths[1]),
+                pad(&cells[2], widths[2]),
+                self.paint(&cells[3], source_color),
+            ]
+            .join("  ");
+            writeln!(out, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// CSV or TSV with a header row
+///
+/// Columns are `key`, `value`, `type`, `pref_type` and `source`. Strings are
+/// written without quotes and null as an empty field; `type` tells them apart.
+/// Fields containing the delimiter, quotes or newlines are quoted CSV-style.
+#[derive(Debug, Clone, Copy)]
+pub struct DelimitedFormatter {
+    /// Field delimiter byte
+    pub delimiter: u8,
+}
+
+impl DelimitedFormatter {
+    /// Comma-separated values
+    pub fn csv() -> Self {
+        Self { delimiter: b',' }
+    }
+
+    /// Tab-separated values
+    pub fn tsv() -> Self {
+        Self { delimiter: b'\t' }
+    }
+}
+
+impl PrefFormatter for DelimitedFormatter {
+    fn write_prefs(&self, entries: &[PrefEntry], out: &mut dyn Write) -> Result<()> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .from_writer(out);
+        writer
+            .write_record(["key", "value", "type", "pref_type", "source"])
+            .map_err(std::io::Error::from)?;
+        for entry in sorted(entries) {
+            let pref_type = serde_json::to_value(&entry.pref_type)
+                .ok()
+                .and_then(|json| json.as_str().map(str::to_string))
+                .unwrap_or_default();
+            writer
+                .write_record([
+                    entry.key.as_str(),
+                    &plain_value(&entry.value),
+                    entry.value.type_name(),
+                    &pref_type,
+                    source_name(&entry.source),
+                ])
+                .map_err(std::io::Error::from)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// YAML mapping of key to value
+///
+/// Strings are always double-quoted, so values like `"yes"` or `"1.0"` keep
+/// their type. Decoded JSON documents are written in flow style.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct YamlFormatter {
+    /// Replace JSON-valued strings with the decoded document
+    pub decode_json: bool,
+}
+
+impl YamlFormatter {
+    /// Whether a key can be written as a plain (unquoted) YAML scalar
+    fn is_plain_key(key: &str) -> bool {
+        const RESERVED: [&str; 11] = [
+            "true", "false", "yes", "no", "on", "off", "y", "n", "null", "~", "",
+        ];
+        key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+            && !RESERVED.contains(&key.to_ascii_lowercase().as_str())
+    }
+}
+
+impl PrefFormatter for YamlFormatter {
+    fn write_prefs(&self, entries: &[PrefEntry], out: &mut dyn Write) -> Result<()> {
+        if entries.is_empty() {
+            writeln!(out, "{{}}")?;
+            return Ok(());
+        }
+        for entry in sorted(entries) {
+            let key = if Self::is_plain_key(&entry.key) {
+                entry.key.clone()
+            } else {
+                serde_json::Value::from(entry.key.as_str()).to_string()
+            };
+            // JSON scalars and flow collections are valid YAML
+            let value = if self.decode_json {
+                json_or_plain(&entry.value)
+            } else {
+                entry.value.to_json_value()
+            };
+            writeln!(out, "{}: {}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// TOML tables nested by dot path
+///
+/// `network.trr.mode = 2` becomes `mode = 2` in table `[network.trr]`. When a
+/// key is both a value and a namespace (`a.b` and `a.b.c`), the namespace's
+/// preferences are written as quoted keys (`"b.c"`) next to the value.
+/// TOML has no null, so null values are skipped.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TomlFormatter;
+
+/// Namespace tree used to build the TOML document
+#[derive(Default)]
+struct TomlNode {
+    value: Option<toml::Value>,
+    children: BTreeMap<String, TomlNode>,
+}
+
+impl TomlNode {
+    fn insert(&mut self, key: &str, value: toml::Value) {
+        let node = key.split('.').fold(self, |node, segment| {
+            node.children.entry(segment.to_string()).or_default()
+        });
+        node.value = Some(value);
+    }
+
+    /// Nested table of this node's children
+    fn into_table(self) -> toml::Table {
+        let mut table = toml::Table::new();
+        for (name, child) in self.children {
+            match child.value {
+                Some(value) => {
+                    let mut flattened = Vec::new();
+                    for (rest, grandchild) in child.children {
+                        grandchild.flatten(&format!("{}.{}", name, rest), &mut flattened);
+                    }
+                    table.insert(name, value);
+                    table.extend(flattened);
+                }
+                None => {
+                    table.insert(name, toml::Value::Table(child.into_table()));
+                }
+            }
+        }
+        table
+    }
+
+    /// All values below this node as `(dotted key, value)` pairs
+    fn flatten(self, path: &str, out: &mut Vec<(String, toml::Value)>) {
+        if let Some(value) = self.value {
+            out.push((path.to_string(), value));
+        }
+        for (name, child) in self.children {
+            child.flatten(&format!("{}.{}", path, name), out);
+        }
+    }
+}
+
+impl PrefFormatter for TomlFormatter {
+    fn write_prefs(&self, entries: &[PrefEntry], out: &mut dyn Write) -> Result<()> {
+        let mut root = TomlNode::default();
+        for entry in entries {
+            let value = match &entry.value {
+                PrefValue::Bool(b) => toml::Value::Boolean(*b),
+                PrefValue::Integer(i) => toml::Value::Integer(*i),
+                PrefValue::Float(f) => toml::Value::Float(*f),
+                PrefValue::String(s) => toml::Value::String(s.clone()),
+                PrefValue::Null => continue,
+            };
+            root.insert(&entry.key, value);
+        }
+        let document =
+            toml::to_string(&root.into_table()).map_err(|e| Error::Format(e.to_string()))?;
+        out.write_all(document.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// `user_pref(...)` lines, usable as a `user.js`
+///
+/// Every entry is written as `user_pref` whatever its [`PrefType`](crate::PrefType).
+/// Firefox preferences have no float type, so floats are written as strings,
+/// which is how Firefox stores them; null values are written as comments.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrefsJsFormatter;
+
+impl PrefsJsFormatter {
+    /// Quote and escape a string as a JavaScript string literal
+    pub fn quote(text: &str) -> String {
+        let mut quoted = String::with_capacity(text.len() + 2);
+        quoted.push('"');
+        for c in text.chars() {
+            match c {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                '\r' => quoted.push_str("\\r"),
+                '\t' => quoted.push_str("\\t"),
+                c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+}
+
+impl PrefFormatter for PrefsJsFormatter {
+    fn write_prefs(&self, entries: &[PrefEntry], out: &mut dyn Write) -> Result<()> {
+        for entry in sorted(entries) {
+            let key = Self::quote(&entry.key);
+            let Some(value) = prefs_js_literal(&entry.value) else {
+                writeln!(out, "// {}: null cannot be set in prefs.js", key)?;
+                continue;
+            };
+            writeln!(out, "user_pref({}, {});", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_prefs_js;
+    use crate::types::PrefType;
+
+    fn entry(key: &str, value: PrefValue, source: Option<PrefSource>) -> PrefEntry {
+        PrefEntry {
+            key: key.to_string(),
+            value,
+            pref_type: PrefType::User,
+            explanation: None,
+            source,
+            source_file: None,
+            locked: None,
+        }
+    }
+
+    #[test]
+    fn test_prefs_js_round_trip() {
+        let prefs = vec![
+            entry("b.str", PrefValue::String("a\"b\\c\nd\u{1}é".into()), None),
+            entry("a.int", PrefValue::Integer(-3), None),
+            entry("c.bool", PrefValue::Bool(false), None),
+            entry("d.float", PrefValue::Float(1.5), None),
+            entry("e.null", PrefValue::Null, None),
+        ];
+        let output = PrefsJsFormatter.format_prefs(&prefs).unwrap();
+        assert!(output.starts_with("user_pref(\"a.int\", -3);\n"));
+        assert!(output.contains("// \"e.null\": null cannot be set in prefs.js\n"));
+
+        let parsed = parse_prefs_js(&output).unwrap();
+        let values: Vec<(&str, &PrefValue)> =
+            parsed.iter().map(|e| (e.key.as_str(), &e.value)).collect();
+        assert_eq!(
+            values,
+            vec![
+                ("a.int", &PrefValue::Integer(-3)),
+                ("b.str", &PrefValue::String("a\"b\\c\nd\u{1}é".into())),
+                ("c.bool", &PrefValue::Bool(false)),
+                ("d.float", &PrefValue::String("1.5".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_toml_nesting_and_conflicts() {
+        let prefs = vec![
+            entry("a.b", PrefValue::Integer(1), None),
+            entry("a.b.c", PrefValue::Bool(true), None),
+            entry("a.d.e", PrefValue::String("x".into()), None),
+            entry("top", PrefValue::Float(0.5), None),
+            entry("gone", PrefValue::Null, None),
+        ];
+        let output = TomlFormatter.format_prefs(&prefs).unwrap();
+        let parsed: toml::Table = output.parse().unwrap();
+        assert_eq!(parsed["top"].as_float(), Some(0.5));
+        assert_eq!(parsed["a"]["b"].as_integer(), Some(1));
+        assert_eq!(parsed["a"]["b.c"].as_bool(), Some(true));
+        assert_eq!(parsed["a"]["d"]["e"].as_str(), Some("x"));
+        assert!(!parsed.contains_key("gone"));
+    }
+
+    #[test]
+    fn test_text_decoding_and_highlight() {
+        let prefs = vec![
+            entry("network.proxy.type", PrefValue::Integer(1), None),
+            entry("a.json", PrefValue::String(r#"{"x":1}"#.into()), None),
+        ];
+        assert_eq!(
+            TextFormatter::default().format_prefs(&prefs).unwrap(),
+            "a.json = \"{\"x\":1}\"\nnetwork.proxy.type = 1\n"
+        );
+
+        let query = KeyQuery::new(
+            &["proxy"],
+            &crate::QueryOptions {
+                mode: crate::MatchMode::Search,
+                case_insensitive: false,
+            },
+        )
+        .unwrap();
+        let text = TextFormatter {
+            decode_json: true,
+            highlight: Some(&query),
+        };
+        assert_eq!(
+            text.format_prefs(&prefs).unwrap(),
+            "a.json = {\n  \"x\": 1\n}\n\
+             network.\x1b[1;33mproxy\x1b[0m.type = 1\n"
+        );
+    }
+
+    #[test]
+    fn test_table_alignment_and_color() {
+        let prefs = vec![
+            entry(
+                "long.key.name",
+                PrefValue::Integer(1),
+                Some(PrefSource::User),
+            ),
+            entry(
+                "k",
+                PrefValue::String("x".repeat(10)),
+                Some(PrefSource::BuiltIn),
+            ),
+        ];
+        let table = TableFormatter {
+            color: false,
+            max_value_width: Some(6),
+        };
+        assert_eq!(
+            table.format_prefs(&prefs).unwrap(),
+            "KEY            VALUE   TYPE     SOURCE\n\
+             k              \"xxxx…  String   builtin\n\
+             long.key.name  1       Integer  user\n"
+        );
+
+        let colored = TableFormatter {
+            color: true,
+            ..table
+        };
+        let output = colored.format_prefs(&prefs).unwrap();
+        assert!(output.contains("\x1b[32muser\x1b[0m"));
+        assert!(output.starts_with("\x1b[1mKEY            VALUE   TYPE     SOURCE\x1b[0m\n"));
+    }
+
+    #[test]
+    fn test_delimited_and_yaml() {
+        let prefs = vec![
+            entry(
+                "a",
+                PrefValue::String("x,y \"z\"".into()),
+                Some(PrefSource::User),
+            ),
+            entry("b", PrefValue::Null, None),
+        ];
+        assert_eq!(
+            DelimitedFormatter::csv().format_prefs(&prefs).unwrap(),
+            "key,value,type,pref_type,source\n\
+             a,\"x,y \"\"z\"\"\",String,user,user\n\
+             b,,Null,user,\n"
+        );
+        assert_eq!(
+            DelimitedFormatter::tsv().format_prefs(&prefs).unwrap(),
+            "key\tvalue\ttype\tpref_type\tsource\n\
+             a\t\"x,y \"\"z\"\"\"\tString\tuser\tuser\n\
+             b\t\tNull\tuser\t\n"
+        );
+
+        let prefs = vec![
+            entry("yes", PrefValue::String("on".into()), None),
+            entry("ui.state", PrefValue::String("{\"v\":[1]}".into()), None),
+        ];
+        assert_eq!(
+            YamlFormatter { decode_json: true }
+                .format_prefs(&prefs)
+                .unwrap(),
+            "ui.state: {\"v\":[1]}\n\"yes\": \"on\"\n"
+        );
+    }
+
+    #[test]
+    fn test_ndjson_one_entry_per_line() {
+        let prefs = parse_prefs_js(
+            r#"user_pref("b", 2);
+            user_pref("a", "{\"x\":1}");"#,
+        )
+        .unwrap();
+        let output = NdjsonFormatter { decode_json: true }
+            .format_prefs(&prefs)
+            .unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["key"], "a");
+        assert_eq!(lines[0]["json"]["x"], 1);
+        assert_eq!(lines[1]["value"], 2);
+    }
+}
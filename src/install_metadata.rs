@@ -0,0 +1,494 @@
+//! Firefox installation metadata
+//!
+//! This module reads build information from an installation directory:
+//! `application.ini` (application name, vendor, version, build ID),
+//! `platform.ini` (Gecko platform version), the update channel from
+//! `defaults/pref/channel-prefs.js` or `update-settings.ini`, and the CPU
+//! architecture from the executable header (ELF, Mach-O or PE).
+//!
+//! The update channel is the most reliable way to tell an ESR build from a
+//! regular release, since both use plain `X.Y.Z` version numbers in
+//! `application.ini`.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use ffcv::{find_installation_for_app, Application, ReleaseType};
+//!
+//! if let Some(install) = find_installation_for_app(&Application::FIREFOX)? {
+//!     let meta = &install.metadata;
+//!     println!(
+//!         "{} {} ({:?}, build {:?}, {:?})",
+//!         meta.app_name.as_deref().unwrap_or("Firefox"),
+//!         install.version,
+//!         meta.release_type,
+//!         meta.build_id,
+//!         meta.architecture,
+//!     );
+//!     if meta.release_type == ReleaseType::Esr {
+//!         println!("Extended Support Release");
+//!     }
+//! }
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::application::Application;
+use crate::parser::parse_prefs_js_file;
+use crate::types::PrefValue;
+use serde::Serialize;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Preference holding the update channel in channel-prefs.js
+const UPDATE_CHANNEL_PREF: &str = "app.update.channel";
+
+/// Number of header bytes read from an executable to detect its architecture
+const HEADER_SIZE: usize = 4096;
+
+/// Release channel classification of an installation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReleaseType {
+    /// Regular release channel
+    Release,
+    /// Extended Support Release
+    Esr,
+    /// Beta channel
+    Beta,
+    /// Developer Edition (the "aurora" update channel)
+    DeveloperEdition,
+    /// Nightly builds
+    Nightly,
+    /// Local or unofficial build, or not enough information to classify
+    #[default]
+    Unknown,
+}
+
+impl ReleaseType {
+    /// Classify an installation from its update channel and version
+    ///
+    /// The update channel wins when it is known; otherwise the version
+    /// suffix is used (`esr`, `b<N>`, `a1`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ffcv::ReleaseType;
+    ///
+    /// assert_eq!(ReleaseType::classify(Some("esr"), "115.12.0"), ReleaseType::Esr);
+    /// assert_eq!(ReleaseType::classify(None, "130.0b5"), ReleaseType::Beta);
+    /// assert_eq!(ReleaseType::classify(None, "128.0.3"), ReleaseType::Release);
+    /// ```
+    pub fn classify(update_channel: Option<&str>, version: &str) -> ReleaseType {
+        match update_channel {
+            Some("release") => return ReleaseType::Release,
+            Some("esr") => return ReleaseType::Esr,
+            Some("beta") => return ReleaseType::Beta,
+            Some("aurora") => return ReleaseType::DeveloperEdition,
+            Some(channel) if channel.starts_with("nightly") => return ReleaseType::Nightly,
+            _ => {}
+        }
+
+        // Strip the numeric part, leaving suffixes such as "esr", "b5" or "a1"
+        let version = version.trim();
+        let suffix = version.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+        if version.is_empty() || suffix.len() == version.len() {
+            ReleaseType::Unknown
+        } else if suffix.is_empty() {
+            ReleaseType::Release
+        } else if suffix.starts_with("esr") {
+            ReleaseType::Esr
+        } else if suffix.starts_with('b') {
+            ReleaseType::Beta
+        } else if suffix.starts_with('a') {
+            ReleaseType::Nightly
+        } else {
+            ReleaseType::Unknown
+        }
+    }
+}
+
+/// Build metadata of a Firefox installation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct InstallationMetadata {
+    /// Application name from application.ini (e.g., "Firefox", "Thunderbird")
+    pub app_name: Option<String>,
+    /// Vendor from application.ini (e.g., "Mozilla")
+    pub vendor: Option<String>,
+    /// Build ID from application.ini (e.g., "20240604163519")
+    pub build_id: Option<String>,
+    /// Gecko platform version from platform.ini
+    pub platform_version: Option<String>,
+    /// Update channel (e.g., "release", "esr", "beta", "nightly")
+    pub update_channel: Option<String>,
+    /// CPU architecture of the main executable (e.g., "x86_64", "aarch64")
+    pub architecture: Option<String>,
+    /// Release classification derived from the update channel and version
+    pub release_type: ReleaseType,
+}
+
+/// Read build metadata from an installation directory
+///
+/// Every field is optional; files that are missing or unreadable simply
+/// leave the corresponding field unset.
+///
+/// # Arguments
+///
+/// * `install_path` - Path to the installation directory
+/// * `app` - Application the installation belongs to
+/// * `version` - Version already read for the installation (used for classification)
+pub fn read_installation_metadata(
+    install_path: &Path,
+    app: &Application,
+    version: &str,
+) -> InstallationMetadata {
+    let application_ini = read_ini(&install_path.join("application.ini"));
+    let platform_ini = read_ini(&install_path.join("platform.ini"));
+
+    let app_value = |key: &str| {
+        application_ini
+            .as_ref()
+            .and_then(|ini| ini.get("App", key))
+            .filter(|v| !v.is_empty())
+    };
+
+    let build_id = app_value("BuildID").or_else(|| {
+        platform_ini
+            .as_ref()
+            .and_then(|ini| ini.get("Build", "BuildID"))
+    });
+
+    let platform_version = platform_ini
+        .as_ref()
+        .and_then(|ini| ini.get("Build", "Milestone"))
+        .or_else(|| {
+            application_ini
+                .as_ref()
+                .and_then(|ini| ini.get("Gecko", "MaxVersion"))
+        })
+        .filter(|v| !v.is_empty());
+
+    let update_channel = read_update_channel(install_path);
+    let release_type = ReleaseType::classify(update_channel.as_deref(), version);
+
+    InstallationMetadata {
+        app_name: app_value("Name"),
+        vendor: app_value("Vendor"),
+        build_id,
+        platform_version,
+        update_channel,
+        architecture: detect_architecture(install_path, app),
+        release_type,
+    }
+}
+
+/// Read an ini file case-sensitively, returning `None` if it can't be read
+pub(crate) fn read_ini(path: &Path) -> Option<configparser::ini::Ini> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let mut ini = configparser::ini::Ini::new_cs();
+    ini.read(content).ok()?;
+    Some(ini)
+}
+
+/// Read the update channel of an installation
+///
+/// `channel-prefs.js` is checked first, then the MAR channel IDs in
+/// `update-settings.ini` (e.g., `firefox-mozilla-esr` → `esr`).
+fn read_update_channel(install_path: &Path) -> Option<String> {
+    let channel_prefs = [
+        install_path.join("defaults/pref/channel-prefs.js"),
+        install_path.join("browser/defaults/preferences/channel-prefs.js"),
+    ];
+
+    for path in channel_prefs.iter().filter(|p| p.exists()) {
+        let channel = parse_prefs_js_file(path).ok().and_then(|prefs| {
+            prefs
+                .into_iter()
+                .find(|p| p.key == UPDATE_CHANNEL_PREF)
+                .and_then(|p| match p.value {
+                    PrefValue::String(channel) if !channel.is_empty() => Some(channel),
+                    _ => None,
+                })
+        });
+        if channel.is_some() {
+            return channel;
+        }
+    }
+
+    let settings = read_ini(&install_path.join("update-settings.ini"))?;
+    let mar_channels = settings.get("Settings", "ACCEPTED_MAR_CHANNEL_IDS")?;
+    mar_channel_to_update_channel(&mar_channels)
+}
+
+/// Convert the first MAR channel ID to an update channel name
+///
+/// MAR channel IDs have the form `<app>-mozilla-<channel>`, e.g.
+/// `firefox-mozilla-release` or `thunderbird-comm-esr`.
+fn mar_channel_to_update_channel(mar_channels: &str) -> Option<String> {
+    let first = mar_channels.split(',').next()?.trim();
+    let channel = first.rsplit('-').next()?;
+    (!channel.is_empty()).then(|| channel.to_string())
+}
+
+/// Detect the CPU architecture of an installation's main executable
+fn detect_architecture(install_path: &Path, app: &Application) -> Option<String> {
+    executable_candidates(install_path, app)
+        .iter()
+        .find_map(|path| read_executable_architecture(path))
+}
+
+/// Possible locations of the main executable within an installation
+fn executable_candidates(install_path: &Path, app: &Application) -> Vec<PathBuf> {
+    let mut dirs = vec![install_path.to_path_buf()];
+
+    // macOS bundles keep the executable in Contents/MacOS next to Contents/Resources
+    if install_path
+        .file_name()
+        .map(|n| n == "Resources")
+        .unwrap_or(false)
+    {
+        if let Some(contents) = install_path.parent() {
+            dirs.push(contents.join("MacOS"));
+        }
+    }
+
+    dirs.iter()
+        .flat_map(|dir| app.binary_names.iter().map(move |name| dir.join(name)))
+        .collect()
+}
+
+/// Read the architecture from an executable's header
+///
+/// Returns `None` for files that are not ELF, Mach-O or PE executables (such
+/// as the shell script wrappers some distributions install).
+pub(crate) fn read_executable_architecture(path: &Path) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    file.by_ref()
+        .take(HEADER_SIZE as u64)
+        .read_to_end(&mut header)
+        .ok()?;
+    parse_executable_architecture(&header)
+}
+
+/// Parse the architecture from the first bytes of an executable
+fn parse_executable_architecture(header: &[u8]) -> Option<String> {
+    match header.get(0..4)? {
+        [0x7f, b'E', b'L', b'F'] => parse_elf_architecture(header),
+        [0xcf, 0xfa, 0xed, 0xfe] | [0xce, 0xfa, 0xed, 0xfe] => {
+            macho_cpu_name(read_u32(header, 4, false)?).map(str::to_string)
+        }
+        [0xca, 0xfe, 0xba, 0xbe] => parse_fat_macho_architecture(header),
+        [b'M', b'Z', ..] => parse_pe_architecture(header),
+        _ => None,
+    }
+}
+
+fn parse_elf_architecture(header: &[u8]) -> Option<String> {
+    // EI_DATA: 1 = little endian, 2 = big endian
+    let big_endian = *header.get(5)? == 2;
+    let machine = read_u16(header, 18, big_endian)?;
+
+    let name = match machine {
+        0x03 => "x86",
+        0x08 => "mips",
+        0x14 => "powerpc",
+        0x15 => "powerpc64",
+        0x16 => "s390x",
+        0x28 => "arm",
+        0x3e => "x86_64",
+        0xb7 => "aarch64",
+        0xf3 => "riscv64",
+        0x102 => "loongarch64",
+        _ => return Some(format!("elf-machine-{:#x}", machine)),
+    };
+    Some(name.to_string())
+}
+
+fn parse_fat_macho_architecture(header: &[u8]) -> Option<String> {
+    let count = read_u32(header, 4, true)? as usize;
+    let mut names = Vec::new();
+
+    for i in 0..count {
+        // fat_arch entries are 20 bytes, starting after the 8-byte header
+        let cpu_type = read_u32(header, 8 + i * 20, true)?;
+        if let Some(name) = macho_cpu_name(cpu_type) {
+            names.push(name);
+        }
+    }
+
+    (!names.is_empty()).then(|| format!("universal({})", names.join(",")))
+}
+
+fn macho_cpu_name(cpu_type: u32) -> Option<&'static str> {
+    match cpu_type {
+        0x0000_0007 => Some("x86"),
+        0x0100_0007 => Some("x86_64"),
+        0x0000_000c => Some("arm"),
+        0x0100_000c => Some("aarch64"),
+        _ => None,
+    }
+}
+
+fn parse_pe_architecture(header: &[u8]) -> Option<String> {
+    let pe_offset = read_u32(header, 0x3c, false)? as usize;
+    if header.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+        return None;
+    }
+
+    let name = match read_u16(header, pe_offset + 4, false)? {
+        0x014c => "x86",
+        0x8664 => "x86_64",
+        0xaa64 => "aarch64",
+        0x01c4 => "arm",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+fn read_u16(data: &[u8], offset: usize, big_endian: bool) -> Option<u16> {
+    let bytes: [u8; 2] = data.get(offset..offset + 2)?.try_into().ok()?;
+    Some(if big_endian {
+        u16::from_be_bytes(bytes)
+    } else {
+        u16::from_le_bytes(bytes)
+    })
+}
+
+fn read_u32(data: &[u8], offset: usize, big_endian: bool) -> Option<u32> {
+    let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+    Some(if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_classify_release_type() {
+        assert_eq!(
+            ReleaseType::classify(Some("esr"), "115.12.0"),
+            ReleaseType::Esr
+        );
+        assert_eq!(
+            ReleaseType::classify(Some("release"), "128.0"),
+            ReleaseType::Release
+        );
+        assert_eq!(
+            ReleaseType::classify(Some("aurora"), "130.0b2"),
+            ReleaseType::DeveloperEdition
+        );
+        assert_eq!(
+            ReleaseType::classify(Some("nightly-try"), "131.0a1"),
+            ReleaseType::Nightly
+        );
+        assert_eq!(ReleaseType::classify(None, "115.12.0esr"), ReleaseType::Esr);
+        assert_eq!(ReleaseType::classify(None, "131.0a1"), ReleaseType::Nightly);
+        assert_eq!(ReleaseType::classify(None, "unknown"), ReleaseType::Unknown);
+    }
+
+    #[test]
+    fn test_read_installation_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let install = temp_dir.path();
+
+        std::fs::write(
+            install.join("application.ini"),
+            "[App]\nVendor=Mozilla\nName=Firefox\nVersion=115.12.0\nBuildID=20240604163519\n\n\
+             [Gecko]\nMinVersion=115.12.0\nMaxVersion=115.12.0\n",
+        )
+        .unwrap();
+        std::fs::write(
+            install.join("platform.ini"),
+            "[Build]\nBuildID=20240604163519\nMilestone=115.12.0\n",
+        )
+        .unwrap();
+        std::fs::write(
+            install.join("update-settings.ini"),
+            "[Settings]\nACCEPTED_MAR_CHANNEL_IDS=firefox-mozilla-esr\n",
+        )
+        .unwrap();
+
+        let meta = read_installation_metadata(install, &Application::FIREFOX, "115.12.0");
+        assert_eq!(meta.app_name.as_deref(), Some("Firefox"));
+        assert_eq!(meta.vendor.as_deref(), Some("Mozilla"));
+        assert_eq!(meta.build_id.as_deref(), Some("20240604163519"));
+        assert_eq!(meta.platform_version.as_deref(), Some("115.12.0"));
+        assert_eq!(meta.update_channel.as_deref(), Some("esr"));
+        assert_eq!(meta.release_type, ReleaseType::Esr);
+        assert_eq!(meta.architecture, None);
+    }
+
+    #[test]
+    fn test_channel_prefs_take_precedence() {
+        let temp_dir = TempDir::new().unwrap();
+        let install = temp_dir.path();
+        std::fs::create_dir_all(install.join("defaults/pref")).unwrap();
+        std::fs::write(
+            install.join("defaults/pref/channel-prefs.js"),
+            "pref(\"app.update.channel\", \"beta\");\n",
+        )
+        .unwrap();
+        std::fs::write(
+            install.join("update-settings.ini"),
+            "[Settings]\nACCEPTED_MAR_CHANNEL_IDS=firefox-mozilla-release\n",
+        )
+        .unwrap();
+
+        assert_eq!(read_update_channel(install).as_deref(), Some("beta"));
+    }
+
+    #[test]
+    fn test_parse_executable_architecture() {
+        let mut elf = vec![0u8; 64];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[5] = 1;
+        elf[18] = 0x3e;
+        assert_eq!(
+            parse_executable_architecture(&elf).as_deref(),
+            Some("x86_64")
+        );
+
+        let mut macho = vec![0u8; 32];
+        macho[..4].copy_from_slice(&[0xcf, 0xfa, 0xed, 0xfe]);
+        macho[4..8].copy_from_slice(&0x0100_000cu32.to_le_bytes());
+        assert_eq!(
+            parse_executable_architecture(&macho).as_deref(),
+            Some("aarch64")
+        );
+
+        let mut fat = vec![0u8; 48];
+        fat[..4].copy_from_slice(&[0xca, 0xfe, 0xba, 0xbe]);
+        fat[4..8].copy_from_slice(&2u32.to_be_bytes());
+        fat[8..12].copy_from_slice(&0x0100_0007u32.to_be_bytes());
+        fat[28..32].copy_from_slice(&0x0100_000cu32.to_be_bytes());
+        assert_eq!(
+            parse_executable_architecture(&fat).as_deref(),
+            Some("universal(x86_64,aarch64)")
+        );
+
+        let mut pe = vec![0u8; 256];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        pe[0x84..0x86].copy_from_slice(&0xaa64u16.to_le_bytes());
+        assert_eq!(
+            parse_executable_architecture(&pe).as_deref(),
+            Some("aarch64")
+        );
+
+        assert_eq!(parse_executable_architecture(b"#!/bin/sh\n"), None);
+    }
+
+    #[test]
+    fn test_detect_architecture_of_current_executable() {
+        let exe = std::env::current_exe().unwrap();
+        assert!(read_executable_architecture(&exe).is_some());
+    }
+}
@@ -0,0 +1,851 @@
+//! Installation discovery probes
+//!
+//! Finding installations is split into independent strategies implementing
+//! [`InstallationProbe`]. Each probe proposes candidate directories for an
+//! [`Application`]; the locator validates them and records which probe found
+//! each installation in [`Installation::probe`](crate::Installation::probe).
+//!
+//! The default probes, cheapest first, are:
+//!
+//! - `search-paths` - the application's well-known installation directories
+//! - `path` - executables on `$PATH`, following symlinks and wrapper scripts
+//! - `config` - extra paths listed in the user's `ffcv/installations` file
+//! - `nix` - binaries in Nix profiles (Linux)
+//! - `desktop-entry` - `Exec=` lines of `.desktop` files in XDG data dirs (Linux)
+//! - `dpkg` - file lists in `/var/lib/dpkg/info` (Linux)
+//! - `rpm` - file lists in the RPM database `/var/lib/rpm/rpmdb.sqlite` (Linux)
+//! - `dev-build` - local builds under `~/src/obj-*/dist/bin` and `~/src/*/obj-*/dist/bin`
+//!
+//! When only one installation is needed, the locator stops at the first
+//! probe whose candidates contain a valid installation.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use ffcv::{default_probes, find_all_installations_with_probes, Application};
+//!
+//! let probes = default_probes();
+//! for install in find_all_installations_with_probes(&Application::FIREFOX, &probes)? {
+//!     println!("{} found by {:?}", install.path.display(), install.probe);
+//! }
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+//!
+//! # Extra installations file
+//!
+//! The `config` probe reads `$XDG_CONFIG_HOME/ffcv/installations` (or
+//! `~/.config/ffcv/installations`, `%APPDATA%\ffcv\installations` on
+//! Windows). Each line is a path or glob pattern; `~/` is expanded and `#`
+//! starts a comment. Lines belong to Firefox unless they follow an
+//! `[<application id>]` header:
+//!
+//! ```text
+//! /opt/firefox-nightly
+//! ~/builds/*/dist/bin
+//!
+//! [thunderbird]
+//! ~/src/comm-central/obj-x86_64-pc-linux-gnu/dist/bin
+//! ```
+
+use crate::application::Application;
+use crate::install_metadata::read_ini;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Nix profile directories that may contain application binaries
+const NIX_PROFILE_PATHS: &[&str] = &[
+    "/nix/var/nix/profiles/default/bin",
+    "/run/current-system/sw/bin",
+    // User-specific profile paths (searched recursively)
+    "/etc/profiles",
+];
+
+/// Directory holding dpkg package file lists
+const DPKG_INFO_DIR: &str = "/var/lib/dpkg/info";
+
+/// SQLite RPM database used by current rpm releases
+const RPM_DB_PATH: &str = "/var/lib/rpm/rpmdb.sqlite";
+
+/// Local build locations relative to the home directory
+const DEV_BUILD_PATTERNS: &[&str] = &["src/obj-*/dist/bin", "src/*/obj-*/dist/bin"];
+
+/// Maximum size of a wrapper script that is inspected for the real binary
+const MAX_SCRIPT_SIZE: u64 = 64 * 1024;
+
+/// A strategy for discovering installation directories
+///
+/// Implementations only propose candidates; whether a candidate is a valid
+/// installation is decided by the locator. Probes should be cheap and must
+/// not fail: unreadable sources simply yield no candidates.
+pub trait InstallationProbe {
+    /// Short identifier reported with each installation (e.g., "path", "dpkg")
+    fn name(&self) -> &'static str;
+
+    /// Candidate installation directories for an application
+    fn candidates(&self, app: &Application) -> Vec<PathBuf>;
+}
+
+/// The probes used by the locator, in priority order
+///
+/// Probes that only check a few files come before those scanning
+/// directories or package databases.
+pub fn default_probes() -> Vec<Box<dyn InstallationProbe>> {
+    let mut probes: Vec<Box<dyn InstallationProbe>> =
+        vec![Box::new(SearchPathProbe), Box::new(PathProbe)];
+
+    if let Some(path) = ConfigFileProbe::default_path() {
+        probes.push(Box::new(ConfigFileProbe::new(path)));
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        probes.push(Box::new(NixProfileProbe));
+        probes.push(Box::new(DesktopEntryProbe));
+        probes.push(Box::new(DpkgProbe));
+        probes.push(Box::new(RpmProbe));
+    }
+
+    probes.push(Box::new(DevBuildProbe));
+
+    probes
+}
+
+/// Well-known installation directories from the [`Application`] descriptor
+pub struct SearchPathProbe;
+
+impl InstallationProbe for SearchPathProbe {
+    fn name(&self) -> &'static str {
+        "search-paths"
+    }
+
+    fn candidates(&self, app: &Application) -> Vec<PathBuf> {
+        app.installation_search_paths()
+    }
+}
+
+/// Application binaries linked into Nix profiles
+///
+/// A binary such as `/etc/profiles/per-user/alice/bin/firefox` resolves to
+/// `/nix/store/<hash>-firefox-<version>/bin/firefox`, whose installation
+/// lives in the `lib/firefox` directory of the same store path.
+pub struct NixProfileProbe;
+
+impl InstallationProbe for NixProfileProbe {
+    fn name(&self) -> &'static str {
+        "nix"
+    }
+
+    fn candidates(&self, app: &Application) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+
+        for nix_path in NIX_PROFILE_PATHS {
+            let nix_path = Path::new(nix_path);
+            if !nix_path.is_dir() {
+                continue;
+            }
+
+            // Depth 4 is needed for /etc/profiles/per-user/USER/bin/firefox
+            for binary in walk_dir_depth(nix_path, 4, app) {
+                if let Some(lib_dir) = nix_lib_directory(&binary) {
+                    paths.push(lib_dir);
+                }
+            }
+        }
+
+        paths
+    }
+}
+
+/// Executables found on `$PATH`
+///
+/// Symlinks are resolved, and shell wrapper scripts (as installed by many
+/// distributions) are searched for the absolute path of the real binary.
+pub struct PathProbe;
+
+impl InstallationProbe for PathProbe {
+    fn name(&self) -> &'static str {
+        "path"
+    }
+
+    fn candidates(&self, app: &Application) -> Vec<PathBuf> {
+        app.binary_names
+            .iter()
+            .filter_map(|name| find_on_path(name))
+            .flat_map(|binary| executable_install_dirs(&binary, app))
+            .collect()
+    }
+}
+
+/// `Exec=` lines of desktop entries in the XDG data directories
+pub struct DesktopEntryProbe;
+
+impl InstallationProbe for DesktopEntryProbe {
+    fn name(&self) -> &'static str {
+        "desktop-entry"
+    }
+
+    fn candidates(&self, app: &Application) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+
+        for data_dir in xdg_data_dirs() {
+            let Ok(entries) = std::fs::read_dir(data_dir.join("applications")) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().map(|e| e != "desktop").unwrap_or(true) {
+                    continue;
+                }
+
+                let Some(program) = desktop_entry_program(&path) else {
+                    continue;
+                };
+
+                // Only follow launchers of this application (including
+                // variants such as "firefox-esr" or wrapper scripts)
+                let launches_app = program
+                    .file_name()
+                    .map(|name| {
+                        let name = name.to_string_lossy();
+                        app.is_binary_name(&name) || name.starts_with(app.id)
+                    })
+                    .unwrap_or(false);
+                if !launches_app {
+                    continue;
+                }
+
+                let binary = if program.is_absolute() {
+                    Some(program)
+                } else {
+                    program.to_str().and_then(find_on_path)
+                };
+
+                if let Some(binary) = binary {
+                    paths.extend(executable_install_dirs(&binary, app));
+                }
+            }
+        }
+
+        paths
+    }
+}
+
+/// File lists of installed Debian packages
+pub struct DpkgProbe;
+
+impl InstallationProbe for DpkgProbe {
+    fn name(&self) -> &'static str {
+        "dpkg"
+    }
+
+    fn candidates(&self, app: &Application) -> Vec<PathBuf> {
+        let Ok(entries) = std::fs::read_dir(DPKG_INFO_DIR) else {
+            return Vec::new();
+        };
+
+        let mut paths = Vec::new();
+
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            // Package lists are named <package>.list or <package>:<arch>.list
+            let Some(package) = file_name.strip_suffix(".list") else {
+                continue;
+            };
+            if !package_matches(package, app) {
+                continue;
+            }
+
+            if let Ok(content) = std::fs::read_to_string(entry.path()) {
+                paths.extend(install_dirs_from_file_list(content.lines()));
+            }
+        }
+
+        paths
+    }
+}
+
+/// File lists of installed RPM packages, read from the SQLite rpm database
+pub struct RpmProbe;
+
+impl InstallationProbe for RpmProbe {
+    fn name(&self) -> &'static str {
+        "rpm"
+    }
+
+    fn candidates(&self, app: &Application) -> Vec<PathBuf> {
+        let db_path = Path::new(RPM_DB_PATH);
+        if !db_path.exists() {
+            return Vec::new();
+        }
+
+        read_rpm_headers(db_path)
+            .iter()
+            .filter_map(|blob| RpmHeader::parse(blob))
+            .filter(|header| {
+                header
+                    .name()
+                    .map(|name| package_matches(&name, app))
+                    .unwrap_or(false)
+            })
+            .flat_map(|header| {
+                let files = header.file_paths();
+                install_dirs_from_file_list(files.iter().map(String::as_str))
+            })
+            .collect()
+    }
+}
+
+/// Local Firefox builds in the usual source checkout locations
+///
+/// Only object directories containing one of the application's binaries are
+/// proposed, so a Firefox build isn't reported as Thunderbird.
+pub struct DevBuildProbe;
+
+impl InstallationProbe for DevBuildProbe {
+    fn name(&self) -> &'static str {
+        "dev-build"
+    }
+
+    fn candidates(&self, app: &Application) -> Vec<PathBuf> {
+        let Some(home) = std::env::var_os("HOME").map(PathBuf::from) else {
+            return Vec::new();
+        };
+
+        DEV_BUILD_PATTERNS
+            .iter()
+            .flat_map(|pattern| expand_glob(&home.join(pattern)))
+            .filter(|dir| contains_binary(dir, app))
+            .collect()
+    }
+}
+
+/// Extra installation paths listed in a user configuration file
+pub struct ConfigFileProbe {
+    path: PathBuf,
+}
+
+impl ConfigFileProbe {
+    /// Create a probe reading the given file
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Default location of the extra installations file
+    pub fn default_path() -> Option<PathBuf> {
+        #[cfg(target_os = "windows")]
+        {
+            std::env::var_os("APPDATA")
+                .map(|appdata| PathBuf::from(appdata).join("ffcv/installations"))
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| {
+                    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+                })
+                .map(|config| config.join("ffcv/installations"))
+        }
+    }
+}
+
+impl InstallationProbe for ConfigFileProbe {
+    fn name(&self) -> &'static str {
+        "config"
+    }
+
+    fn candidates(&self, app: &Application) -> Vec<PathBuf> {
+        let Ok(content) = std::fs::read_to_string(&self.path) else {
+            return Vec::new();
+        };
+
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        let mut section = Application::FIREFOX.id.to_string();
+        let mut paths = Vec::new();
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_lowercase();
+                continue;
+            }
+
+            if section != app.id {
+                continue;
+            }
+
+            let path = match (line.strip_prefix("~/"), &home) {
+                (Some(rest), Some(home)) => home.join(rest),
+                _ => PathBuf::from(line),
+            };
+            paths.extend(expand_glob(&path));
+        }
+
+        paths
+    }
+}
+
+/// Whether a package name belongs to an application (e.g. "firefox-esr:amd64")
+fn package_matches(package: &str, app: &Application) -> bool {
+    let package = package.split(':').next().unwrap_or(package);
+    package == app.id
+        || package
+            .strip_prefix(app.id)
+            .map(|rest| {
+                rest.starts_with('-') && !rest.ends_with("-l10n") && !rest.contains("-l10n-")
+            })
+            .unwrap_or(false)
+}
+
+/// Installation directories mentioned in a package file list
+///
+/// Every directory containing an `application.ini` is an installation root.
+fn install_dirs_from_file_list<'a>(files: impl Iterator<Item = &'a str>) -> Vec<PathBuf> {
+    files
+        .map(str::trim)
+        .filter_map(|file| file.strip_suffix("/application.ini"))
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Locate an executable name in the directories of `$PATH`
+fn find_on_path(name: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Installation directories reachable from an executable
+///
+/// The executable's resolved parent directory is always a candidate. If the
+/// executable is a script, absolute paths to one of the application's
+/// binaries inside it are followed as well.
+fn executable_install_dirs(binary: &Path, app: &Application) -> Vec<PathBuf> {
+    let resolved = binary
+        .canonicalize()
+        .unwrap_or_else(|_| binary.to_path_buf());
+    let mut dirs = Vec::new();
+
+    if let Some(script) = read_script(&resolved) {
+        for target in script_binary_paths(&script, app) {
+            let target = target.canonicalize().unwrap_or(target);
+            if let Some(parent) = target.parent() {
+                dirs.push(parent.to_path_buf());
+            }
+        }
+    }
+
+    if let Some(parent) = resolved.parent() {
+        dirs.push(parent.to_path_buf());
+    }
+
+    dirs
+}
+
+/// Read a file if it is a (small) script starting with `#!`
+fn read_script(path: &Path) -> Option<String> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut content = Vec::new();
+    file.take(MAX_SCRIPT_SIZE).read_to_end(&mut content).ok()?;
+    if !content.starts_with(b"#!") {
+        return None;
+    }
+    String::from_utf8(content).ok()
+}
+
+/// Absolute paths in a script whose file name is one of the application's binaries
+fn script_binary_paths(script: &str, app: &Application) -> Vec<PathBuf> {
+    script
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(|line| {
+            line.split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '=' | ';'))
+        })
+        .filter(|token| token.starts_with('/'))
+        .map(PathBuf::from)
+        .filter(|path| {
+            path.file_name()
+                .map(|name| app.is_binary_name(&name.to_string_lossy()))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+/// XDG data directories, user directory first
+fn xdg_data_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    match std::env::var_os("XDG_DATA_HOME") {
+        Some(data_home) => dirs.push(PathBuf::from(data_home)),
+        None => {
+            if let Some(home) = std::env::var_os("HOME") {
+                dirs.push(PathBuf::from(home).join(".local/share"));
+            }
+        }
+    }
+
+    match std::env::var_os("XDG_DATA_DIRS") {
+        Some(data_dirs) => dirs.extend(std::env::split_paths(&data_dirs)),
+        None => {
+            dirs.push(PathBuf::from("/usr/local/share"));
+            dirs.push(PathBuf::from("/usr/share"));
+        }
+    }
+
+    dirs
+}
+
+/// Program of the `Exec=` key in a desktop entry's `[Desktop Entry]` group
+///
+/// Leading `env` invocations and `VAR=value` assignments are skipped.
+fn desktop_entry_program(path: &Path) -> Option<PathBuf> {
+    let ini = read_ini(path)?;
+    let exec = ini.get("Desktop Entry", "Exec")?;
+
+    exec.split_whitespace()
+        .map(|token| token.trim_matches('"'))
+        .find(|token| *token != "env" && !token.contains('='))
+        .map(PathBuf::from)
+}
+
+/// Walk a directory up to a specified depth, collecting the application's binaries
+fn walk_dir_depth(dir: &Path, max_depth: usize, app: &Application) -> Vec<PathBuf> {
+    let mut results = Vec::new();
+    let mut current_dirs = vec![dir.to_path_buf()];
+
+    for _depth in 0..max_depth {
+        let mut next_dirs = Vec::new();
+
+        for dir_path in &current_dirs {
+            if let Ok(entries) = std::fs::read_dir(dir_path) {
+                for entry in entries.flatten() {
+                    let path = entry.path();
+
+                    // Check if this is one of the application's binaries
+                    let is_binary = path
+                        .file_name()
+                        .map(|name| app.is_binary_name(&name.to_string_lossy()))
+                        .unwrap_or(false);
+
+                    if is_binary {
+                        results.push(path.clone());
+                    }
+
+                    // If it's a directory, add to next iteration
+                    if path.is_dir() {
+                        next_dirs.push(path);
+                    }
+                }
+            }
+        }
+
+        current_dirs = next_dirs;
+        if current_dirs.is_empty() {
+            break;
+        }
+    }
+
+    results
+}
+
+/// Resolve a Nix profile binary to the `lib/<name>` directory of its store path
+fn nix_lib_directory(binary: &Path) -> Option<PathBuf> {
+    let canonical = binary.canonicalize().ok()?;
+    let name = canonical.file_name()?.to_string_lossy();
+    let lib_name = name.strip_suffix("-bin").unwrap_or(&name).to_string();
+    let store_base = canonical.parent()?.parent()?;
+    let lib_dir = store_base.join("lib").join(lib_name);
+    lib_dir.exists().then_some(lib_dir)
+}
+
+/// Whether a directory contains one of the application's executables
+fn contains_binary(dir: &Path, app: &Application) -> bool {
+    app.binary_names.iter().any(|name| dir.join(name).is_file())
+}
+
+/// Expand a path containing glob characters; plain paths are returned as-is
+fn expand_glob(path: &Path) -> Vec<PathBuf> {
+    let pattern = path.to_string_lossy();
+    if !pattern.contains(['*', '?', '[']) {
+        return vec![path.to_path_buf()];
+    }
+
+    match glob::glob(&pattern) {
+        Ok(paths) => paths.flatten().collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Read all package header blobs from an SQLite RPM database
+fn read_rpm_headers(db_path: &Path) -> Vec<Vec<u8>> {
+    use rusqlite::{Connection, OpenFlags};
+
+    let Ok(conn) = Connection::open_with_flags(
+        db_path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    ) else {
+        return Vec::new();
+    };
+
+    let Ok(mut stmt) = conn.prepare("SELECT blob FROM Packages") else {
+        return Vec::new();
+    };
+
+    stmt.query_map([], |row| row.get::<_, Vec<u8>>(0))
+        .map(|rows| rows.flatten().collect())
+        .unwrap_or_default()
+}
+
+/// A package header as stored in the RPM database
+///
+/// The blob holds a big-endian index of `(tag, type, offset, count)` entries
+/// followed by the data store they point into.
+struct RpmHeader<'a> {
+    index: Vec<(u32, u32, usize, usize)>,
+    data: &'a [u8],
+}
+
+impl<'a> RpmHeader<'a> {
+    const TAG_NAME: u32 = 1000;
+    const TAG_DIRINDEXES: u32 = 1116;
+    const TAG_BASENAMES: u32 = 1117;
+    const TAG_DIRNAMES: u32 = 1118;
+
+    const TYPE_INT32: u32 = 4;
+    const TYPE_STRING: u32 = 6;
+    const TYPE_STRING_ARRAY: u32 = 8;
+
+    fn parse(blob: &'a [u8]) -> Option<Self> {
+        let count = be_u32(blob, 0)? as usize;
+        let data_len = be_u32(blob, 4)? as usize;
+        let data_start = 8 + count.checked_mul(16)?;
+        let data = blob.get(data_start..data_start.checked_add(data_len)?)?;
+
+        let index = (0..count)
+            .map(|i| {
+                let entry = 8 + i * 16;
+                Some((
+                    be_u32(blob, entry)?,
+                    be_u32(blob, entry + 4)?,
+                    be_u32(blob, entry + 8)? as usize,
+                    be_u32(blob, entry + 12)? as usize,
+                ))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self { index, data })
+    }
+
+    fn entry(&self, tag: u32, kind: u32) -> Option<(usize, usize)> {
+        self.index
+            .iter()
+            .find(|(t, k, _, _)| *t == tag && *k == kind)
+            .map(|(_, _, offset, count)| (*offset, *count))
+    }
+
+    fn strings(&self, tag: u32, kind: u32) -> Vec<String> {
+        let Some((offset, count)) = self.entry(tag, kind) else {
+            return Vec::new();
+        };
+
+        self.data
+            .get(offset..)
+            .unwrap_or_default()
+            .split(|b| *b == 0)
+            .take(count)
+            .map(|s| String::from_utf8_lossy(s).to_string())
+            .collect()
+    }
+
+    fn name(&self) -> Option<String> {
+        self.strings(Self::TAG_NAME, Self::TYPE_STRING)
+            .into_iter()
+            .next()
+    }
+
+    fn file_paths(&self) -> Vec<String> {
+        let dirnames = self.strings(Self::TAG_DIRNAMES, Self::TYPE_STRING_ARRAY);
+        let basenames = self.strings(Self::TAG_BASENAMES, Self::TYPE_STRING_ARRAY);
+        let Some((offset, count)) = self.entry(Self::TAG_DIRINDEXES, Self::TYPE_INT32) else {
+            return Vec::new();
+        };
+
+        basenames
+            .iter()
+            .take(count)
+            .enumerate()
+            .filter_map(|(i, base)| {
+                let dir = dirnames.get(be_u32(self.data, offset + i * 4)? as usize)?;
+                Some(format!("{}{}", dir, base))
+            })
+            .collect()
+    }
+}
+
+fn be_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+    Some(u32::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Build an RPM header blob from (tag, type, count, data) entries
+    fn rpm_header(entries: &[(u32, u32, u32, Vec<u8>)]) -> Vec<u8> {
+        let mut index = Vec::new();
+        let mut data = Vec::new();
+        for (tag, kind, count, bytes) in entries {
+            index.extend_from_slice(&tag.to_be_bytes());
+            index.extend_from_slice(&kind.to_be_bytes());
+            index.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            index.extend_from_slice(&count.to_be_bytes());
+            data.extend_from_slice(bytes);
+        }
+
+        let mut blob = Vec::new();
+        blob.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        blob.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        blob.extend(index);
+        blob.extend(data);
+        blob
+    }
+
+    #[test]
+    fn test_rpm_header_file_paths() {
+        let blob = rpm_header(&[
+            (
+                RpmHeader::TAG_NAME,
+                RpmHeader::TYPE_STRING,
+                1,
+                b"firefox\0".to_vec(),
+            ),
+            (
+                RpmHeader::TAG_DIRNAMES,
+                RpmHeader::TYPE_STRING_ARRAY,
+                2,
+                b"/usr/bin/\0/usr/lib64/firefox/\0".to_vec(),
+            ),
+            (
+                RpmHeader::TAG_BASENAMES,
+                RpmHeader::TYPE_STRING_ARRAY,
+                2,
+                b"firefox\0application.ini\0".to_vec(),
+            ),
+            (
+                RpmHeader::TAG_DIRINDEXES,
+                RpmHeader::TYPE_INT32,
+                2,
+                [0u32.to_be_bytes(), 1u32.to_be_bytes()].concat(),
+            ),
+        ]);
+
+        let header = RpmHeader::parse(&blob).unwrap();
+        assert_eq!(header.name().as_deref(), Some("firefox"));
+
+        let files = header.file_paths();
+        assert_eq!(
+            files,
+            vec!["/usr/bin/firefox", "/usr/lib64/firefox/application.ini"]
+        );
+        assert_eq!(
+            install_dirs_from_file_list(files.iter().map(String::as_str)),
+            vec![PathBuf::from("/usr/lib64/firefox")]
+        );
+    }
+
+    #[test]
+    fn test_package_matches() {
+        let app = Application::FIREFOX;
+        assert!(package_matches("firefox", &app));
+        assert!(package_matches("firefox-esr:amd64", &app));
+        assert!(!package_matches("firefox-esr-l10n-de", &app));
+        assert!(!package_matches("firefoxpwa", &app));
+    }
+
+    #[test]
+    fn test_script_binary_paths() {
+        let script =
+            "#!/bin/sh\n# run /usr/lib/old/firefox\nexec /usr/lib/firefox/firefox \"$@\"\n";
+        assert_eq!(
+            script_binary_paths(script, &Application::FIREFOX),
+            vec![PathBuf::from("/usr/lib/firefox/firefox")]
+        );
+    }
+
+    #[test]
+    fn test_desktop_entry_program() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = temp_dir.path().join("firefox.desktop");
+        std::fs::write(
+            &entry,
+            "[Desktop Entry]\nName=Firefox\nExec=env MOZ_ENABLE_WAYLAND=1 /opt/firefox/firefox %u\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            desktop_entry_program(&entry),
+            Some(PathBuf::from("/opt/firefox/firefox"))
+        );
+    }
+
+    #[test]
+    fn test_config_file_probe_sections_and_globs() {
+        let temp_dir = TempDir::new().unwrap();
+        let builds = temp_dir.path().join("builds");
+        std::fs::create_dir_all(builds.join("a/dist/bin")).unwrap();
+        std::fs::create_dir_all(builds.join("b/dist/bin")).unwrap();
+
+        let config = temp_dir.path().join("installations");
+        std::fs::write(
+            &config,
+            format!(
+                "# extra installs\n{}/*/dist/bin\n\n[thunderbird]\n/opt/thunderbird-daily\n",
+                builds.display()
+            ),
+        )
+        .unwrap();
+
+        let probe = ConfigFileProbe::new(&config);
+        let mut firefox = probe.candidates(&Application::FIREFOX);
+        firefox.sort();
+        assert_eq!(
+            firefox,
+            vec![builds.join("a/dist/bin"), builds.join("b/dist/bin")]
+        );
+        assert_eq!(
+            probe.candidates(&Application::THUNDERBIRD),
+            vec![PathBuf::from("/opt/thunderbird-daily")]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_executable_install_dirs_follows_wrapper_script() {
+        let temp_dir = TempDir::new().unwrap();
+        let install = temp_dir.path().join("lib/firefox");
+        std::fs::create_dir_all(&install).unwrap();
+        std::fs::write(install.join("firefox"), b"\x7fELF").unwrap();
+
+        let bin = temp_dir.path().join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        let wrapper = bin.join("firefox");
+        std::fs::write(
+            &wrapper,
+            format!("#!/bin/sh\nexec {}/firefox \"$@\"\n", install.display()),
+        )
+        .unwrap();
+
+        let dirs = executable_install_dirs(&wrapper, &Application::FIREFOX);
+        assert_eq!(dirs[0], install.canonicalize().unwrap());
+    }
+}
@@ -0,0 +1,380 @@
+//! Firefox install sections
+//!
+//! Since Firefox 67 every installation gets its own default profile. The
+//! mapping is stored in profiles.ini as `[Install<HASH>]` sections (and
+//! mirrored in installs.ini as `[<HASH>]` sections), where `<HASH>` is the
+//! CityHash64 of the installation directory path encoded as UTF-16.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use ffcv::list_install_sections;
+//!
+//! for section in list_install_sections(None)? {
+//!     let install = section
+//!         .installation
+//!         .as_ref()
+//!         .map(|i| i.path.display().to_string())
+//!         .unwrap_or_else(|| "not installed".to_string());
+//!     println!("{} -> {:?} ({})", section.hash, section.default, install);
+//! }
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::application::Application;
+use crate::cityhash::city_hash64;
+use crate::error::{Error, Result};
+use crate::firefox_locator::find_all_installations_for_app;
+use crate::profile::get_profiles_directory_for_app;
+use crate::types::Installation;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// An `[Install…]` section from profiles.ini or installs.ini
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallSection {
+    /// Install hash (16 uppercase hex characters)
+    pub hash: String,
+    /// Default profile path for this installation, as written in the ini file
+    pub default: Option<String>,
+    /// Whether the default profile is locked to this installation
+    pub locked: bool,
+    /// Detected Firefox installation whose directory hashes to `hash`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub installation: Option<Installation>,
+    /// The installation path (or alias) that hashes to `hash`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matched_path: Option<PathBuf>,
+}
+
+/// List install sections and match them to detected Firefox installations
+///
+/// Sections are read from profiles.ini and installs.ini; when both files
+/// describe the same hash, profiles.ini wins as it does in Firefox.
+///
+/// # Arguments
+///
+/// * `profiles_dir_opt` - Optional custom profiles directory path
+pub fn list_install_sections(profiles_dir_opt: Option<&Path>) -> Result<Vec<InstallSection>> {
+    list_install_sections_for_app(&Application::FIREFOX, profiles_dir_opt)
+}
+
+/// List install sections of the given application
+///
+/// Sections are matched against the application's own installations.
+pub fn list_install_sections_for_app(
+    app: &Application,
+    profiles_dir_opt: Option<&Path>,
+) -> Result<Vec<InstallSection>> {
+    let profiles_dir = get_profiles_directory_for_app(app, profiles_dir_opt)?;
+    let mut sections = read_install_sections(&profiles_dir, app)?;
+    let installations = find_all_installations_for_app(app)?;
+    match_installations(&mut sections, &installations);
+    Ok(sections)
+}
+
+/// Attach installations to the sections whose hash they produce
+///
+/// Every path of an installation (including its aliases) is hashed, since
+/// Firefox may have been started through any of them. Aliases are hashed as
+/// they are: resolving a symlink alias would just give the target's hash.
+pub(crate) fn match_installations(sections: &mut [InstallSection], installations: &[Installation]) {
+    let hashed: Vec<(String, &PathBuf, &Installation)> = installations
+        .iter()
+        .flat_map(|install| {
+            let aliases = install.aliases.iter().map(move |alias| {
+                let hash_dir = install_hash_directory(alias);
+                (
+                    install_hash_for_path_str(&hash_dir.to_string_lossy()),
+                    alias,
+                    install,
+                )
+            });
+            std::iter::once((compute_install_hash(&install.path), &install.path, install))
+                .chain(aliases)
+        })
+        .collect();
+
+    for section in sections.iter_mut() {
+        let matched = hashed.iter().find(|(hash, _, _)| *hash == section.hash);
+        section.installation = matched.map(|(_, _, install)| (*install).clone());
+        section.matched_path = matched.map(|(_, path, _)| (*path).clone());
+    }
+}
+
+/// Read install sections from a profiles directory without detecting installations
+pub(crate) fn read_install_sections(
+    profiles_dir: &Path,
+    app: &Application,
+) -> Result<Vec<InstallSection>> {
+    let mut sections = Vec::new();
+
+    let profiles_ini = profiles_dir.join(app.profiles_ini);
+    if profiles_ini.exists() {
+        sections.extend(parse_install_sections(&profiles_ini, Some("Install"))?);
+    }
+
+    let installs_ini = profiles_dir.join(app.installs_ini);
+    if installs_ini.exists() {
+        for section in parse_install_sections(&installs_ini, None)? {
+            if !sections.iter().any(|s| s.hash == section.hash) {
+                sections.push(section);
+            }
+        }
+    }
+
+    Ok(sections)
+}
+
+/// Parse install sections from an ini file
+///
+/// `prefix` is the section name prefix in front of the hash ("Install" for
+/// profiles.ini, none for installs.ini).
+fn parse_install_sections(ini_path: &Path, prefix: Option<&str>) -> Result<Vec<InstallSection>> {
+    use configparser::ini::Ini;
+
+    // Case-sensitive so the hash keeps Firefox's uppercase spelling
+    let mut ini = Ini::new_cs();
+    let content = std::fs::read_to_string(ini_path)?;
+
+    if let Err(e) = ini.read(content) {
+        return Err(Error::ProfilesIniParse(format!(
+            "Failed to parse {}: {}",
+            ini_path.display(),
+            e
+        )));
+    }
+
+    let mut sections = Vec::new();
+
+    for sec_name in ini.sections() {
+        let hash = match prefix {
+            Some(prefix) => match sec_name.strip_prefix(prefix) {
+                Some(hash) => hash,
+                None => continue,
+            },
+            None => sec_name.as_str(),
+        };
+
+        if !is_install_hash(hash) {
+            continue;
+        }
+
+        sections.push(InstallSection {
+            hash: hash.to_uppercase(),
+            default: ini.get(&sec_name, "Default").filter(|d| !d.is_empty()),
+            locked: ini.get(&sec_name, "Locked").as_deref() == Some("1"),
+            installation: None,
+            matched_path: None,
+        });
+    }
+
+    Ok(sections)
+}
+
+/// Check whether a string looks like an install hash (16 hex characters)
+fn is_install_hash(s: &str) -> bool {
+    s.len() == 16 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Compute the install hash for a Firefox installation directory
+///
+/// Symlinks are resolved first, as Firefox hashes the real path of its
+/// binary's directory. For macOS installations given as
+/// `Firefox.app/Contents/Resources` (or `Contents/MacOS`), the hash is taken
+/// over the `.app` bundle path like Firefox does.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ffcv::compute_install_hash;
+/// use std::path::Path;
+///
+/// let hash = compute_install_hash(Path::new("/usr/lib/firefox"));
+/// println!("profiles.ini section: [Install{}]", hash);
+/// ```
+pub fn compute_install_hash(install_dir: &Path) -> String {
+    let resolved = std::fs::canonicalize(install_dir).unwrap_or_else(|_| install_dir.to_path_buf());
+    let hash_dir = install_hash_directory(&resolved);
+    install_hash_for_path_str(&hash_dir.to_string_lossy())
+}
+
+/// Compute the install hash for a path string, without touching the filesystem
+///
+/// Firefox hashes the UTF-16 code units of the path on every platform, so
+/// this can be used to compute hashes for paths from other systems, e.g. a
+/// Windows path while analysing a profile on Linux.
+///
+/// # Example
+///
+/// ```rust
+/// use ffcv::install_hash_for_path_str;
+///
+/// assert_eq!(
+///     install_hash_for_path_str(r"C:\Program Files\Mozilla Firefox"),
+///     "308046B0AF4A39CB"
+/// );
+/// ```
+pub fn install_hash_for_path_str(path: &str) -> String {
+    let bytes: Vec<u8> = path
+        .encode_utf16()
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect();
+    format!("{:016X}", city_hash64(&bytes))
+}
+
+/// Get the directory Firefox uses for hashing an installation path
+///
+/// This is the installation directory itself, except for macOS bundles where
+/// it is the `.app` directory.
+fn install_hash_directory(path: &Path) -> PathBuf {
+    if let Some(contents) = path.parent() {
+        let in_bundle = matches!(
+            path.file_name().and_then(|n| n.to_str()),
+            Some("Resources") | Some("MacOS")
+        ) && contents.file_name().and_then(|n| n.to_str()) == Some("Contents");
+
+        if in_bundle {
+            if let Some(bundle) = contents.parent() {
+                if bundle.extension().map(|e| e == "app").unwrap_or(false) {
+                    return bundle.to_path_buf();
+                }
+            }
+        }
+    }
+
+    path.to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_install_hash_vectors() {
+        // Hashes observed in real profiles.ini files
+        assert_eq!(
+            install_hash_for_path_str("/usr/lib/firefox"),
+            "4F96D1932A9F858E"
+        );
+        assert_eq!(
+            install_hash_for_path_str(r"C:\Program Files\Mozilla Firefox"),
+            "308046B0AF4A39CB"
+        );
+        // 76 bytes of UTF-16, exercising the >64 byte code path
+        assert_eq!(
+            install_hash_for_path_str(r"C:\Program Files (x86)\Mozilla Firefox"),
+            "E7CF176E110C211B"
+        );
+    }
+
+    #[test]
+    fn test_install_hash_directory_macos_bundle() {
+        assert_eq!(
+            install_hash_directory(Path::new("/Applications/Firefox.app/Contents/Resources")),
+            PathBuf::from("/Applications/Firefox.app")
+        );
+        assert_eq!(
+            install_hash_directory(Path::new("/usr/lib/firefox")),
+            PathBuf::from("/usr/lib/firefox")
+        );
+    }
+
+    #[test]
+    fn test_read_install_sections() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("profiles.ini"),
+            "[Install4F96D1932A9F858E]\n\
+             Default=Profiles/abc.default-release\n\
+             Locked=1\n\n\
+             [BackgroundTasksProfiles]\n\
+             MozillaBackgroundTask-4F96D1932A9F858E-backgroundupdate=xyz.Background\n\n\
+             [Profile0]\n\
+             Name=default-release\n\
+             IsRelative=1\n\
+             Path=Profiles/abc.default-release\n\n\
+             [General]\n\
+             Version=2\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("installs.ini"),
+            "[4F96D1932A9F858E]\n\
+             Default=Profiles/stale.default\n\n\
+             [308046B0AF4A39CB]\n\
+             Default=Profiles/win.default\n",
+        )
+        .unwrap();
+
+        let sections = read_install_sections(temp_dir.path(), &Application::FIREFOX).unwrap();
+        assert_eq!(sections.len(), 2);
+
+        assert_eq!(sections[0].hash, "4F96D1932A9F858E");
+        assert_eq!(
+            sections[0].default.as_deref(),
+            Some("Profiles/abc.default-release")
+        );
+        assert!(sections[0].locked);
+
+        assert_eq!(sections[1].hash, "308046B0AF4A39CB");
+        assert!(!sections[1].locked);
+    }
+
+    #[test]
+    fn test_match_installations() {
+        let temp_dir = TempDir::new().unwrap();
+        let install_path = temp_dir.path().join("firefox");
+        std::fs::create_dir_all(&install_path).unwrap();
+        let hash = compute_install_hash(&install_path);
+
+        let mut sections = vec![
+            InstallSection {
+                hash: hash.clone(),
+                default: Some("Profiles/a".to_string()),
+                locked: true,
+                installation: None,
+                matched_path: None,
+            },
+            InstallSection {
+                hash: "308046B0AF4A39CB".to_string(),
+                default: Some("Profiles/b".to_string()),
+                locked: true,
+                installation: None,
+                matched_path: None,
+            },
+        ];
+        let alias_path = temp_dir.path().join("firefox-link");
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&install_path, &alias_path).unwrap();
+        let alias_hash = install_hash_for_path_str(&alias_path.to_string_lossy());
+        sections.push(InstallSection {
+            hash: alias_hash,
+            default: Some("Profiles/c".to_string()),
+            locked: false,
+            installation: None,
+            matched_path: None,
+        });
+
+        let installations = vec![Installation {
+            version: "128.0".to_string(),
+            application: "firefox".to_string(),
+            path: install_path.clone(),
+            packaging: crate::types::Packaging::System,
+            metadata: Default::default(),
+            probe: None,
+            aliases: vec![alias_path.clone()],
+            has_greprefs: true,
+            has_omni_ja: true,
+        }];
+
+        match_installations(&mut sections, &installations);
+        assert_eq!(
+            sections[0].installation.as_ref().map(|i| &i.path),
+            Some(&install_path)
+        );
+        assert!(sections[1].installation.is_none());
+        assert_eq!(sections[2].matched_path.as_ref(), Some(&alias_path));
+    }
+}
@@ -0,0 +1,217 @@
+//! JSON-valued string preferences
+//!
+//! Firefox has no structured preference type, so complex data such as
+//! `browser.uiCustomization.state` or `extensions.webextensions.uuids` is
+//! stored as a JSON document inside a string. Decoding is opt-in: nothing in
+//! the parser or merger changes; call these helpers where structure matters.
+//!
+//! A path of the form `key#/json/pointer` addresses a value inside such a
+//! preference, using [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901)
+//! syntax after the `#`. Only `#/` starts a pointer, so keys such as
+//! `print.printer_HP_#2.print_margin_top` keep their `#`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ffcv::{decode_json_pref, encode_json_pref, parse_prefs_js, PrefLookup};
+//!
+//! let prefs = parse_prefs_js(r#"
+//!     user_pref("browser.uiCustomization.state", "{\"placements\":{\"nav-bar\":[\"back-button\",\"urlbar-container\"]},\"currentVersion\":20}");
+//! "#)?;
+//!
+//! let nav_bar = prefs.get_json("browser.uiCustomization.state#/placements/nav-bar")?;
+//! assert_eq!(nav_bar[1], "urlbar-container");
+//!
+//! // Re-encoding is canonical: compact, object keys sorted
+//! let state = decode_json_pref(&prefs[0].value).unwrap();
+//! assert_eq!(
+//!     encode_json_pref(&state).to_string(),
+//!     r#""{"currentVersion":20,"placements":{"nav-bar":["back-button","urlbar-container"]}}""#
+//! );
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::error::{Error, Result};
+use crate::types::{PrefEntry, PrefValue, PrefValueExt};
+
+/// A preference key with an optional JSON pointer (`key#/pointer`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonPrefPath<'a> {
+    /// Preference key
+    pub key: &'a str,
+    /// JSON pointer into the decoded value, starting with `/`
+    pub pointer: Option<&'a str>,
+}
+
+impl<'a> JsonPrefPath<'a> {
+    /// Split a path at the first `#/`
+    ///
+    /// A `#` not followed by `/` is part of the key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ffcv::JsonPrefPath;
+    ///
+    /// let path = JsonPrefPath::parse("browser.newtabpage.pinned#/0/url");
+    /// assert_eq!(path.key, "browser.newtabpage.pinned");
+    /// assert_eq!(path.pointer, Some("/0/url"));
+    /// assert_eq!(JsonPrefPath::parse("network.trr.mode").pointer, None);
+    ///
+    /// let path = JsonPrefPath::parse("print.printer_HP_#2.print_margin_top");
+    /// assert_eq!(path.key, "print.printer_HP_#2.print_margin_top");
+    /// assert_eq!(path.pointer, None);
+    /// ```
+    pub fn parse(path: &'a str) -> Self {
+        match path.find("#/") {
+            Some(hash) => Self {
+                key: &path[..hash],
+                pointer: Some(&path[hash + 1..]),
+            },
+            None => Self {
+                key: path,
+                pointer: None,
+            },
+        }
+    }
+
+    /// Resolve the path against a preference entry with this key
+    ///
+    /// Fails when the entry is not a JSON-valued string or the pointer does
+    /// not exist in it. Without a pointer, non-JSON values are converted as-is.
+    pub fn resolve(&self, entry: &PrefEntry) -> Result<serde_json::Value> {
+        let pointer = match self.pointer {
+            Some(pointer) => pointer,
+            None => return Ok(json_or_plain(&entry.value)),
+        };
+
+        let document = decode_json_pref(&entry.value).ok_or_else(|| Error::InvalidPrefValue {
+            key: entry.key.clone(),
+            message: format!(
+                "expected a JSON object or array in a String, found {} {}",
+                entry.value.type_name(),
+                entry.value
+            ),
+        })?;
+
+        document
+            .pointer(pointer)
+            .cloned()
+            .ok_or_else(|| Error::PrefNotFound(format!("{}#{}", self.key, pointer)))
+    }
+}
+
+/// Decode a JSON-valued string preference
+///
+/// Only strings holding a JSON object or array are decoded; plain strings
+/// that happen to be valid JSON scalars (`"42"`, `"true"`) are left alone.
+pub fn decode_json_pref(value: &PrefValue) -> Option<serde_json::Value> {
+    let text = value.as_str()?.trim();
+    if !(text.starts_with('{') || text.starts_with('[')) {
+        return None;
+    }
+    serde_json::from_str::<serde_json::Value>(text)
+        .ok()
+        .filter(|json| json.is_object() || json.is_array())
+}
+
+/// Encode a JSON value as a preference value
+///
+/// Objects and arrays become canonical JSON strings (compact, object keys
+/// sorted), so decoding and re-encoding a preference is deterministic.
+/// Scalars map to the matching native preference type.
+pub fn encode_json_pref(json: &serde_json::Value) -> PrefValue {
+    match json {
+        serde_json::Value::Null => PrefValue::Null,
+        serde_json::Value::Bool(b) => PrefValue::Bool(*b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => PrefValue::Integer(i),
+            None => PrefValue::Float(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => PrefValue::String(s.clone()),
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+            // serde_json keeps object keys sorted, which makes this canonical
+            PrefValue::String(json.to_string())
+        }
+    }
+}
+
+/// JSON form of a preference value, decoding JSON-valued strings
+pub fn json_or_plain(value: &PrefValue) -> serde_json::Value {
+    decode_json_pref(value).unwrap_or_else(|| value.to_json_value())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_prefs_js;
+    use crate::pref_set::PrefLookup;
+
+    #[test]
+    fn test_decode_only_objects_and_arrays() {
+        let json = PrefValue::String(r#" [{"url":"https://example.com"}] "#.to_string());
+        assert!(decode_json_pref(&json).unwrap().is_array());
+        assert!(decode_json_pref(&PrefValue::String("42".to_string())).is_none());
+        assert!(decode_json_pref(&PrefValue::String("{not json".to_string())).is_none());
+        assert!(decode_json_pref(&PrefValue::Integer(1)).is_none());
+
+        assert_eq!(
+            json_or_plain(&PrefValue::String("plain".to_string())),
+            serde_json::json!("plain")
+        );
+    }
+
+    #[test]
+    fn test_pointer_resolution_and_errors() {
+        let prefs = parse_prefs_js(
+            r#"
+            user_pref("extensions.webextensions.uuids", "{\"uBlock0@raymondhill.net\":\"1234\"}");
+            user_pref("network.trr.mode", 2);
+            user_pref("print.printer_HP_#2.print_margin_top", "0.5");
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            prefs
+                .get_json("extensions.webextensions.uuids#/uBlock0@raymondhill.net")
+                .unwrap(),
+            "1234"
+        );
+        assert_eq!(prefs.get_json("network.trr.mode").unwrap(), 2);
+        // A '#' without '/' belongs to the key
+        assert_eq!(
+            prefs
+                .get_json("print.printer_HP_#2.print_margin_top")
+                .unwrap(),
+            "0.5"
+        );
+        assert!(matches!(
+            prefs.get_json("extensions.webextensions.uuids#/missing"),
+            Err(Error::PrefNotFound(_))
+        ));
+        assert!(matches!(
+            prefs.get_json("network.trr.mode#/x"),
+            Err(Error::InvalidPrefValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_canonical_round_trip() {
+        let original =
+            PrefValue::String(r#"{ "b": [1, 2.5], "a": {"y": null, "x": true} }"#.into());
+        let encoded = encode_json_pref(&decode_json_pref(&original).unwrap());
+        assert_eq!(
+            encoded,
+            PrefValue::String(r#"{"a":{"x":true,"y":null},"b":[1,2.5]}"#.to_string())
+        );
+        assert_eq!(
+            encode_json_pref(&decode_json_pref(&encoded).unwrap()),
+            encoded
+        );
+        assert_eq!(
+            encode_json_pref(&serde_json::json!(3)),
+            PrefValue::Integer(3)
+        );
+    }
+}
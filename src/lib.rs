@@ -9,12 +9,43 @@
 //!
 //! - Parse Firefox prefs.js files with full JavaScript escape sequence support
 //! - Extract and merge Firefox's built-in default preferences from omni.ja archives
-//! - Auto-discover Firefox installations across platforms (Linux, macOS, Windows)
+//! - Auto-discover Firefox installations across platforms (Linux, macOS, Windows),
+//!   including Flatpak and Snap packages
+//! - Discover installations via `$PATH`, desktop entries, dpkg/rpm databases,
+//!   local builds and a user list of extra paths (pluggable [`InstallationProbe`]s)
+//! - Read installation build metadata (build ID, update channel, architecture, ESR/beta/nightly)
 //! - Detect and manage Firefox profiles across platforms
-//! - Query preferences using glob patterns (e.g., `"network.*"`, `"browser.*.enabled"`)
+//! - Support Firefox forks and other Gecko applications (Thunderbird, LibreWolf, Waterfox, ...)
+//! - Map profiles.ini install sections to detected installations
+//! - Link each profile to the installation that owns it (compatibility.ini, install sections)
+//! - Analyse profiles offline from a directory tree, mounted image or `.zip`/`.tar.gz` archive
+//! - Read profile metadata (creation time, last-used Firefox build, size)
+//! - Discover profile groups from Firefox's profile management store
+//! - Query preferences using glob patterns (e.g., `"network.*"`, `"browser.*.enabled"`),
+//!   regular expressions or plain-text search, optionally case-insensitive
+//! - Filter preferences with value-aware expressions (e.g., `value > 100 AND NOT locked`)
+//! - Index preferences in a [`PrefSet`] for O(1) lookup, prefix iteration and set operations
+//! - Read preferences as typed values, decoding documented enums such as [`TrrMode`]
+//! - Decode JSON-valued string preferences and address values inside them (`key#/json/pointer`)
+//! - Versioned JSON output with plain values and an explicit `type` ([`PREF_JSON_SCHEMA`])
+//! - Write preferences as a table, CSV/TSV, YAML, TOML, NDJSON or `user.js` lines
+//!   through pluggable [`PrefFormatter`]s
+//! - Export preferences as an enterprise `policies.json`, optionally mapping known
+//!   preferences onto dedicated policies such as `DNSOverHTTPS`
+//! - Export autoconfig (`mozilla.cfg`, optionally obscured, with its `autoconfig.js`)
+//!   and `distribution.ini` files
+//! - Export Nix attribute sets for home-manager and NixOS Firefox modules
+//! - Diff two preference sets (added, removed and changed preferences, type changes)
+//!   and turn the difference into a `user.js` patch
+//! - Compare the defaults of two Firefox versions and find user preferences that
+//!   became redundant or refer to removed defaults
+//! - Lint user preferences without a default as obsolete, dynamically created or
+//!   likely typos, with suggested keys
+//! - Group preferences into a namespace tree with per-branch and modified counts
 //! - Get human-readable explanations for documented preferences
 //! - Support for all four preference types: user, default, locked, and sticky
 //! - Track preference sources (built-in, global defaults, user-modified)
+//! - Detect user values whose type differs from the default's, which Firefox ignores
 //!
 //! ## Quick Start
 //!
@@ -197,10 +228,16 @@
 //! ## Platform Support
 //!
 //! This library automatically detects Firefox profiles on:
-//! - **Linux**: `~/.mozilla/firefox/`
+//! - **Linux**: `~/.mozilla/firefox/`, or for sandboxed packages
+//!   `~/.var/app/org.mozilla.firefox/.mozilla/firefox/` (Flatpak) and
+//!   `~/snap/firefox/common/.mozilla/firefox/` (Snap)
 //! - **macOS**: `~/Library/Application Support/Firefox/`
 //! - **Windows**: `%APPDATA%\Mozilla\Firefox\Profiles\`
 //!
+//! Other Gecko-based applications are described by [`Application`]; pass one
+//! of its constants (e.g. [`Application::THUNDERBIRD`]) to the `*_for_app`
+//! functions to look up that application's profiles and installations.
+//!
 //! ## See Also
 //!
 //! - [Firefox preferences documentation](https://support.mozilla.org/en-US/kb/about-config-editor-firefox)
@@ -208,40 +245,137 @@
 
 // Re-export all public types at crate root
 pub use types::{
-    FirefoxInstallation, MergedPreferences, PrefEntry, PrefSource, PrefType, PrefValue,
-    PrefValueExt,
+    FirefoxInstallation, Installation, MergeReport, MergedPreferences, Packaging, PrefEntry,
+    PrefSource, PrefType, PrefValue, PrefValueExt, TypeMismatch,
 };
 
 // Re-export error types
 pub use error::{Error, Result};
 
+// Re-export application descriptors
+pub use application::{Application, PlatformPaths};
+
 // Re-export profile types
-pub use profile::ProfileInfo;
+pub use profile::{ProfileInfo, ProfileListConfig};
+pub use profile_groups::{GroupProfile, ProfileGroup, ProfileGroupList};
+pub use profile_install::{InstallLinkSource, ProfileInstallation};
+pub use profile_metadata::{ProfileCompatibility, ProfileStats, ProfileTimes};
 
 // Re-export all public functions at crate root
 pub use parser::{parse_prefs_js, parse_prefs_js_file};
-pub use profile::{find_profile_path, get_prefs_path, list_profiles};
-pub use query::query_preferences;
+pub use profile::{
+    find_profile_path, find_profile_path_for_app, get_prefs_path, list_profiles,
+    list_profiles_for_app, list_profiles_with_config,
+};
+pub use profile_groups::{list_profile_groups, list_profile_groups_for_app, read_profile_group};
+pub use profile_install::find_profile_installation;
+pub use profile_metadata::{collect_profile_stats, read_profile_compatibility, read_profile_times};
+pub use query::{
+    query_preferences, query_preferences_with_options, KeyQuery, MatchMode, QueryOptions,
+};
+pub use query_expr::{filter_preferences, PrefQuery};
 
 // Re-export Firefox locator
 pub use firefox_locator::{
-    find_all_firefox_installations, find_firefox_installation, get_firefox_version,
+    find_all_firefox_installations, find_all_installations_for_app,
+    find_all_installations_with_probes, find_firefox_installation, find_installation_for_app,
+    get_firefox_version,
+};
+
+// Re-export installation probes
+pub use install_probe::{
+    default_probes, ConfigFileProbe, DesktopEntryProbe, DevBuildProbe, DpkgProbe,
+    InstallationProbe, NixProfileProbe, PathProbe, RpmProbe, SearchPathProbe,
 };
 
+// Re-export installation metadata
+pub use install_metadata::{read_installation_metadata, InstallationMetadata, ReleaseType};
+
+// Re-export install sections
+pub use install_section::{
+    compute_install_hash, install_hash_for_path_str, list_install_sections,
+    list_install_sections_for_app, InstallSection,
+};
+
+// Re-export offline analysis
+pub use offline::{OfflineOptions, OfflineSource};
+
 // Re-export omni_extractor
 pub use omni_extractor::{ExtractConfig, OmniExtractor, DEFAULT_MAX_OMNI_SIZE};
 
 // Re-export pref_merger
-pub use pref_merger::{get_effective_pref, merge_all_preferences, MergeConfig};
+pub use pref_merger::{get_effective_pref, merge_all_preferences, MergeConfig, PrefMerger};
+
+// Re-export preference diff
+pub use pref_diff::{diff_preferences, review_user_prefs, PrefChange, PrefDiff, UserPrefReview};
+
+// Re-export preference lint
+pub use lint::{lint_user_prefs, LintFinding, LintKind, DYNAMIC_PREF_PATTERNS};
+
+// Re-export indexed preference collection
+pub use pref_set::{PrefLookup, PrefSet};
+
+// Re-export typed preference access
+pub use typed_pref::{
+    ContentBlockingCategory, CookieBehavior, FromPrefValue, KnownPref, StartupPage, TrrMode,
+};
+
+// Re-export JSON-valued preference helpers
+pub use json_pref::{decode_json_pref, encode_json_pref, json_or_plain, JsonPrefPath};
+
+// Re-export JSON schema
+pub use schema::{PREF_JSON_SCHEMA, PREF_SCHEMA_VERSION};
+
+// Re-export namespace tree
+pub use pref_tree::PrefTree;
+
+// Re-export enterprise policy export
+pub use policies::{
+    export_policies, is_policy_settable, PoliciesExport, PolicyOptions, PolicyStatus,
+    POLICY_PREF_PREFIXES, POLICY_SECURITY_PREFS,
+};
+
+// Re-export deployment formats
+pub use deploy::{DistributionIniFormatter, MozillaCfgFormatter, DEFAULT_OBSCURE_VALUE};
+
+// Re-export Nix export
+pub use nix::{NixFormatter, NixWrapper};
+
+// Re-export output formatters
+pub use formatter::{
+    DelimitedFormatter, JsonArrayFormatter, JsonObjectFormatter, NdjsonFormatter, PrefFormatter,
+    PrefsJsFormatter, TableFormatter, TextFormatter, TomlFormatter, YamlFormatter,
+};
 
 // All modules are private - use re-exports above for public API
+mod application;
+mod cityhash;
+mod deploy;
 mod error;
 mod explanations;
 mod firefox_locator;
+mod formatter;
+mod install_metadata;
+mod install_probe;
+mod install_section;
+mod json_pref;
 mod lexer;
+mod lint;
+mod nix;
+mod offline;
 mod omni_extractor;
 mod parser;
+mod policies;
+mod pref_diff;
 mod pref_merger;
+mod pref_set;
+mod pref_tree;
 mod profile;
+mod profile_groups;
+mod profile_install;
+mod profile_metadata;
 mod query;
+mod query_expr;
+mod schema;
+mod typed_pref;
 mod types;
@@ -0,0 +1,250 @@
+//! Lint user preferences against the installation's defaults
+//!
+//! Profiles accumulate preferences for features removed years ago.
+//! [`lint_user_prefs`] lists user preferences that have no default in the
+//! current installation and classifies each one:
+//!
+//! - **Dynamic**: created at runtime under a namespace that never has defaults
+//!   ([`DYNAMIC_PREF_PATTERNS`], e.g. `services.settings.*`, `media.gmp-*`)
+//! - **Typo**: close to a default key, which is suggested
+//! - **Obsolete**: anything else, most likely left over from a removed feature
+//!
+//! # Example
+//!
+//! ```rust
+//! use ffcv::{lint_user_prefs, parse_prefs_js, LintKind};
+//!
+//! let defaults = parse_prefs_js(r#"
+//!     pref("network.trr.mode", 0);
+//!     pref("browser.startup.page", 1);
+//! "#)?;
+//! let user = parse_prefs_js(r#"
+//!     user_pref("network.trr.mdoe", 2);
+//!     user_pref("browser.startup.page", 3);
+//!     user_pref("dom.ipc.plugins.enabled", false);
+//!     user_pref("media.gmp-gmpopenh264.version", "2.3.2");
+//! "#)?;
+//!
+//! let findings = lint_user_prefs(&user, &defaults);
+//! assert_eq!(findings.len(), 3);
+//! assert_eq!(findings[0].key, "dom.ipc.plugins.enabled");
+//! assert_eq!(findings[0].kind, LintKind::Obsolete);
+//! assert_eq!(findings[1].kind, LintKind::Dynamic);
+//! assert_eq!(findings[2].kind, LintKind::Typo);
+//! assert_eq!(findings[2].suggestions, vec!["network.trr.mode"]);
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::pref_set::PrefSet;
+use crate::types::PrefEntry;
+use glob::Pattern;
+use serde::Serialize;
+use std::fmt;
+
+/// Namespaces whose preferences Firefox creates at runtime without defaults
+pub const DYNAMIC_PREF_PATTERNS: &[&str] = &[
+    "app.update.lastUpdateTime.*",
+    "browser.laterrun.*",
+    "browser.migration.*",
+    "browser.safebrowsing.provider.*.lastupdatetime",
+    "browser.safebrowsing.provider.*.nextupdatetime",
+    "browser.safebrowsing.provider.*.updatebackoff*",
+    "datareporting.policy.*",
+    "extensions.webextensions.uuids",
+    "gfx.blacklist.*",
+    "idle.lastDailyNotification",
+    "media.gmp-*",
+    "media.gmp.*",
+    "print.printer_*",
+    "privacy.sanitize.pending",
+    "services.settings.*",
+    "services.sync.*",
+    "storage.vacuum.last.*",
+    "toolkit.startup.last_success",
+    "toolkit.telemetry.cachedClientID",
+    "toolkit.telemetry.previousBuildID",
+];
+
+/// Maximum number of suggested keys per finding
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Why a user preference has no default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintKind {
+    /// Most likely left over from a removed feature
+    Obsolete,
+    /// Created at runtime under a namespace without defaults
+    Dynamic,
+    /// Close to a default key; probably misspelled
+    Typo,
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintKind::Obsolete => write!(f, "obsolete"),
+            LintKind::Dynamic => write!(f, "dynamic"),
+            LintKind::Typo => write!(f, "typo"),
+        }
+    }
+}
+
+/// A user preference with no default in the installation
+#[derive(Debug, Clone, Serialize)]
+pub struct LintFinding {
+    /// Preference key
+    pub key: String,
+    /// The user's entry
+    pub entry: PrefEntry,
+    /// Likely reason the default is missing
+    pub kind: LintKind,
+    /// Default keys within typo distance, closest first
+    pub suggestions: Vec<String>,
+}
+
+/// Largest edit distance at which a default key counts as a typo of `key`
+///
+/// One edit per eight characters, between one and three.
+fn typo_distance(key: &str) -> usize {
+    (key.chars().count() / 8).clamp(1, 3)
+}
+
+/// Edit distance between two keys, or `None` if it exceeds `max`
+///
+/// Counts insertions, deletions, substitutions and transpositions of adjacent
+/// characters (optimal string alignment), stopping early once two rows in a
+/// row exceed `max` (a transposition reaches back two rows).
+fn edit_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let mut before: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        if current.iter().all(|&d| d > max) && previous.iter().all(|&d| d > max) {
+            return None;
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+    Some(previous[b.len()]).filter(|&d| d <= max)
+}
+
+/// Default keys close enough to `key` to be what the user meant
+fn suggest(key: &str, default_keys: &[(&str, Vec<char>)]) -> Vec<String> {
+    let chars: Vec<char> = key.chars().collect();
+    let max = typo_distance(key);
+    let mut close: Vec<(usize, &str)> = default_keys
+        .iter()
+        .filter_map(|(candidate, candidate_chars)| {
+            edit_distance(&chars, candidate_chars, max).map(|d| (d, *candidate))
+        })
+        .collect();
+    close.sort();
+    close
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+/// List user preferences that have no default, sorted by key
+///
+/// `user` holds the profile's preferences (only entries without a default
+/// are reported) and `defaults` the installation's built-in and global
+/// defaults. Dynamic namespaces take precedence over typo detection.
+pub fn lint_user_prefs(user: &[PrefEntry], defaults: &[PrefEntry]) -> Vec<LintFinding> {
+    let user = PrefSet::from(user.to_vec());
+    let defaults = PrefSet::from(defaults.to_vec());
+    let dynamic: Vec<Pattern> = DYNAMIC_PREF_PATTERNS
+        .iter()
+        .filter_map(|pattern| Pattern::new(pattern).ok())
+        .collect();
+    let default_keys: Vec<(&str, Vec<char>)> = defaults
+        .iter_sorted()
+        .map(|entry| (entry.key.as_str(), entry.key.chars().collect()))
+        .collect();
+
+    user.iter_sorted()
+        .filter(|entry| !defaults.contains_key(&entry.key))
+        .map(|entry| {
+            let (kind, suggestions) = if dynamic.iter().any(|p| p.matches(&entry.key)) {
+                (LintKind::Dynamic, Vec::new())
+            } else {
+                let suggestions = suggest(&entry.key, &default_keys);
+                let kind = if suggestions.is_empty() {
+                    LintKind::Obsolete
+                } else {
+                    LintKind::Typo
+                };
+                (kind, suggestions)
+            };
+            LintFinding {
+                key: entry.key.clone(),
+                entry: entry.clone(),
+                kind,
+                suggestions,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_prefs_js;
+
+    fn distance(a: &str, b: &str, max: usize) -> Option<usize> {
+        let a: Vec<char> = a.chars().collect();
+        let b: Vec<char> = b.chars().collect();
+        edit_distance(&a, &b, max)
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(distance("network.trr.mode", "network.trr.mode", 2), Some(0));
+        assert_eq!(distance("network.trr.mdoe", "network.trr.mode", 2), Some(1));
+        assert_eq!(distance("browser.tab", "browser.tabs.", 2), Some(2));
+        assert_eq!(distance("kitten", "sitting", 3), Some(3));
+        assert_eq!(distance("kitten", "sitting", 2), None);
+        assert_eq!(distance("a", "abcd", 2), None);
+    }
+
+    #[test]
+    fn test_suggestions_ranked_and_dynamic_first() {
+        let defaults = parse_prefs_js(
+            r#"
+            pref("browser.tabs.warnOnClose", true);
+            pref("browser.tabs.warnOnClosed", true);
+            pref("services.sync.engine.tabs", true);
+            "#,
+        )
+        .unwrap();
+        let user = parse_prefs_js(
+            r#"
+            user_pref("browser.tabs.warnOnClos", false);
+            user_pref("services.sync.engine.tab", false);
+            "#,
+        )
+        .unwrap();
+
+        let findings = lint_user_prefs(&user, &defaults);
+        assert_eq!(findings[0].kind, LintKind::Typo);
+        assert_eq!(
+            findings[0].suggestions,
+            vec!["browser.tabs.warnOnClose", "browser.tabs.warnOnClosed"]
+        );
+        assert_eq!(findings[1].kind, LintKind::Dynamic);
+        assert!(findings[1].suggestions.is_empty());
+    }
+}
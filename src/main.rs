@@ -9,6 +9,8 @@
 //!
 //! - `ffcv profile` - List all Firefox profiles on the system
 //! - `ffcv config` - View configuration for a profile
+//! - `ffcv tree` - Show preference namespaces with per-branch counts
+//! - `ffcv lint` - Find obsolete, dynamically created and misspelled user preferences
 //!
 //! For programmatic usage, see the [library documentation](../ffcv/index.html).
 //!
@@ -18,6 +20,23 @@
 //! # List all Firefox profiles
 //! ffcv profile
 //!
+//! # Include creation time, last-used version, size and installation of each profile
+//! ffcv profile --details
+//!
+//! # Show which installation owns each profile
+//! ffcv profile --with-install
+//!
+//! # Analyse a profile archive or disk image without touching this machine
+//! ffcv profile --root evidence/home.tar.gz
+//! ffcv config --root evidence/home.tar.gz --omni-ja evidence/omni.ja --all
+//!
+//! # Show which profiles belong to the same profile group
+//! ffcv profile --groups
+//!
+//! # List Thunderbird profiles, or profiles of every supported application
+//! ffcv profile --app thunderbird
+//! ffcv profile --app all
+//!
 //! # View all preferences for the default profile
 //! ffcv config
 //!
@@ -27,8 +46,49 @@
 //! # Query network-related preferences
 //! ffcv config --query "network.*"
 //!
+//! # Query with a regular expression, highlighting matches
+//! ffcv config --all --regex --query '^network\.(proxy|trr)\.' --output-type text
+//!
+//! # Aligned table with type and source columns
+//! ffcv config --all --query "network.trr.*" --output-type table
+//!
+//! # Save modified preferences as a user.js
+//! ffcv config --output-type prefs-js > user.js
+//!
 //! # Get a single preference
 //! ffcv config --get "network.proxy.type"
+//!
+//! # Reach into a JSON-valued preference
+//! ffcv config --get "browser.uiCustomization.state#/placements/nav-bar"
+//!
+//! # Filter by value, type, source and more
+//! ffcv config --all --where 'value > 100 AND NOT locked'
+//!
+//! # See where a profile diverges: modified prefs per namespace
+//! ffcv tree browser.newtabpage --depth 2
+//! ffcv tree --all --depth 1 --output-type json
+//!
+//! # Turn a tuned profile into an enterprise policies.json
+//! ffcv export --format policies-json --map-policies > policies.json
+//!
+//! # Or as an autoconfig file with its loader
+//! ffcv export --format mozilla-cfg --autoconfig autoconfig.js > mozilla.cfg
+//!
+//! # Migrate a hand-tuned profile to home-manager
+//! ffcv export --format nix --nix-module home-manager
+//!
+//! # Compare a broken profile with a working one
+//! ffcv diff default-release ~/backup/prefs.js
+//!
+//! # What changed in the defaults between ESR 115 and ESR 128, and which of
+//! # my modified prefs are now redundant
+//! ffcv defaults-diff --install-a /opt/firefox-115 --install-b /opt/firefox-128 -p default-esr
+//!
+//! # Find leftover, runtime-created and misspelled prefs in a profile
+//! ffcv lint -p default-release
+//!
+//! # View a LibreWolf profile including its built-in defaults
+//! ffcv config --app librewolf --all
 //! ```
 
 mod cli;
@@ -40,39 +100,187 @@ use cli::Cli;
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
 
+    let app = cli.app;
+
     match cli.command {
-        cli::Commands::Profile { profiles_dir } => commands::list_profiles(profiles_dir.as_deref()),
+        cli::Commands::Profile {
+            profiles_dir,
+            root,
+            details,
+            with_install,
+            groups,
+        } => {
+            if groups {
+                commands::list_profile_groups(app, profiles_dir.as_deref(), root.as_deref())
+            } else {
+                commands::list_profiles(
+                    app,
+                    profiles_dir.as_deref(),
+                    root.as_deref(),
+                    details,
+                    with_install,
+                )
+            }
+        }
         cli::Commands::Install {
-            profiles_dir: _,
+            profiles_dir,
             all,
-        } => commands::list_installations(all),
+            sections,
+        } => {
+            if sections {
+                commands::list_install_sections(app, profiles_dir.as_deref())
+            } else {
+                commands::list_installations(app, all)
+            }
+        }
         cli::Commands::Config {
-            profile,
-            profiles_dir,
-            install_dir,
-            stdin,
-            max_file_size,
+            source,
             query,
+            regex,
+            search,
+            ignore_case,
+            where_expr,
             get,
+            decode_json,
             output_type,
             show_only_modified,
-            all,
             unexplained_only,
+            strict_types,
         } => {
             // Convert Vec<String> to Vec<&str> for query_preferences
             let query_refs: Vec<&str> = query.iter().map(|s| s.as_str()).collect();
+            let query_options = ffcv::QueryOptions {
+                mode: if regex {
+                    ffcv::MatchMode::Regex
+                } else if search {
+                    ffcv::MatchMode::Search
+                } else {
+                    ffcv::MatchMode::Glob
+                },
+                case_insensitive: ignore_case,
+            };
             commands::view_config(commands::ViewConfigParams {
-                stdin,
-                profile_name: &profile,
-                profiles_dir_opt: profiles_dir.as_deref(),
-                install_dir_opt: install_dir.as_deref(),
-                max_file_size,
+                source: source.params(app),
                 query_patterns: &query_refs,
+                query_options,
+                where_expr: where_expr.as_deref(),
+                decode_json,
                 get,
                 output_type,
                 show_only_modified,
-                all,
                 unexplained_only,
+                strict_types,
+            })
+        }
+        cli::Commands::Tree {
+            prefix,
+            depth,
+            output_type,
+            source,
+        } => commands::view_tree(commands::TreeParams {
+            source: source.params(app),
+            prefix: prefix.as_deref(),
+            depth,
+            output_type,
+        }),
+        cli::Commands::Export {
+            format,
+            query,
+            status,
+            map_policies,
+            obscure,
+            autoconfig,
+            distribution_id,
+            distribution_version,
+            distribution_about,
+            nix_module,
+            nix_profile,
+            source,
+        } => {
+            let query_refs: Vec<&str> = query.iter().map(|s| s.as_str()).collect();
+            commands::export_preferences(commands::ExportParams {
+                source: source.params(app),
+                format,
+                query_patterns: &query_refs,
+                policy_options: ffcv::PolicyOptions {
+                    status,
+                    map_known: map_policies,
+                },
+                mozilla_cfg: ffcv::MozillaCfgFormatter {
+                    obscure_value: obscure.then_some(ffcv::DEFAULT_OBSCURE_VALUE),
+                },
+                autoconfig_path: autoconfig.as_deref(),
+                distribution: ffcv::DistributionIniFormatter {
+                    id: distribution_id,
+                    version: distribution_version,
+                    about: distribution_about,
+                },
+                nix: ffcv::NixFormatter {
+                    wrapper: match nix_module {
+                        cli::NixModule::None => ffcv::NixWrapper::AttrSet,
+                        cli::NixModule::HomeManager => ffcv::NixWrapper::HomeManager {
+                            profile: nix_profile.unwrap_or_else(|| source.profile.clone()),
+                        },
+                        cli::NixModule::NixOS => ffcv::NixWrapper::NixOS,
+                    },
+                    status,
+                },
+            })
+        }
+        cli::Commands::Diff {
+            a,
+            b,
+            output_type,
+            query,
+            all,
+            profiles_dir,
+            max_file_size,
+        } => {
+            let query_refs: Vec<&str> = query.iter().map(|s| s.as_str()).collect();
+            commands::diff_sources(commands::DiffParams {
+                app,
+                a: &a,
+                b: &b,
+                output_type,
+                query_patterns: &query_refs,
+                all,
+                profiles_dir_opt: profiles_dir.as_deref(),
+                max_file_size,
+            })
+        }
+        cli::Commands::DefaultsDiff {
+            install_a,
+            install_b,
+            omni_a,
+            omni_b,
+            profile,
+            profiles_dir,
+            query,
+            output_type,
+        } => {
+            let query_refs: Vec<&str> = query.iter().map(|s| s.as_str()).collect();
+            commands::diff_defaults(commands::DefaultsDiffParams {
+                app,
+                install_a: install_a.as_deref(),
+                install_b: install_b.as_deref(),
+                omni_a: omni_a.as_deref(),
+                omni_b: omni_b.as_deref(),
+                profile: profile.as_deref(),
+                profiles_dir_opt: profiles_dir.as_deref(),
+                query_patterns: &query_refs,
+                output_type,
+            })
+        }
+        cli::Commands::Lint {
+            query,
+            output_type,
+            source,
+        } => {
+            let query_refs: Vec<&str> = query.iter().map(|s| s.as_str()).collect();
+            commands::lint_preferences(commands::LintParams {
+                source: source.params(app),
+                query_patterns: &query_refs,
+                output_type,
             })
         }
     }
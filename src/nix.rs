@@ -0,0 +1,319 @@
+//! Export to Nix: home-manager and NixOS Firefox options
+//!
+//! Preferences become a Nix attribute set keyed by preference name, ready
+//! for home-manager's `programs.firefox.profiles.<name>.settings` or NixOS's
+//! `programs.firefox.preferences`. [`NixWrapper`] optionally wraps the set in
+//! the full module skeleton.
+//!
+//! The NixOS option sets preferences through the enterprise policy engine,
+//! so preferences the `Preferences` policy may not set
+//! ([`is_policy_settable`](crate::is_policy_settable)) are commented out, and
+//! one `preferencesStatus` applies to all of them: [`NixFormatter::status`],
+//! or the least restrictive status of the written preferences.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ffcv::{parse_prefs_js, NixFormatter, NixWrapper, PrefFormatter};
+//!
+//! let prefs = parse_prefs_js(r#"
+//!     user_pref("browser.startup.page", 3);
+//!     user_pref("browser.startup.homepage", "https://example.com/${path}");
+//! "#)?;
+//!
+//! let formatter = NixFormatter {
+//!     wrapper: NixWrapper::HomeManager { profile: "default".to_string() },
+//!     ..NixFormatter::default()
+//! };
+//! assert_eq!(
+//!     formatter.format_prefs(&prefs)?,
+//!     r#"{
+//!   programs.firefox = {
+//!     enable = true;
+//!     profiles.default = {
+//!       settings = {
+//!         "browser.startup.homepage" = "https://example.com/\${path}";
+//!         "browser.startup.page" = 3;
+//!       };
+//!     };
+//!   };
+//! }
+//! "#
+//! );
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::error::Result;
+use crate::formatter::{sorted, PrefFormatter, StoredValue};
+use crate::policies::{is_policy_settable, PolicyStatus};
+use crate::types::{PrefEntry, PrefValue};
+use std::io::Write;
+
+/// Nix keywords, which cannot be used as bare attribute names
+const NIX_KEYWORDS: &[&str] = &[
+    "assert", "else", "if", "in", "inherit", "let", "or", "rec", "then", "with",
+];
+
+/// What the attribute set is wrapped in
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum NixWrapper {
+    /// The bare attribute set
+    #[default]
+    AttrSet,
+    /// A home-manager module setting `programs.firefox.profiles.<profile>.settings`
+    HomeManager {
+        /// home-manager profile name
+        profile: String,
+    },
+    /// A NixOS module setting `programs.firefox.preferences`
+    NixOS,
+}
+
+/// Nix attribute set of preferences
+///
+/// Keys are quoted when they are not plain identifiers (any key with a dot);
+/// strings escape `"`, `\` and `${`. Floats are written as strings and null
+/// values are commented out, as Firefox has neither.
+#[derive(Debug, Clone, Default)]
+pub struct NixFormatter {
+    /// Module skeleton around the attribute set
+    pub wrapper: NixWrapper,
+    /// NixOS `preferencesStatus` instead of one derived from the preferences
+    pub status: Option<PolicyStatus>,
+}
+
+impl NixFormatter {
+    /// Nix string literal
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ffcv::NixFormatter;
+    ///
+    /// assert_eq!(NixFormatter::quote("a \"b\" ${c}\n"), r#""a \"b\" \${c}\n""#);
+    /// ```
+    pub fn quote(text: &str) -> String {
+        let mut quoted = String::with_capacity(text.len() + 2);
+        quoted.push('"');
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                '\r' => quoted.push_str("\\r"),
+                '\t' => quoted.push_str("\\t"),
+                '$' if chars.peek() == Some(&'{') => quoted.push_str("\\$"),
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+
+    /// Attribute name, quoted unless it is a plain identifier
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ffcv::NixFormatter;
+    ///
+    /// assert_eq!(NixFormatter::attr_name("default-release"), "default-release");
+    /// assert_eq!(NixFormatter::attr_name("network.trr.mode"), r#""network.trr.mode""#);
+    /// assert_eq!(NixFormatter::attr_name("with"), r#""with""#);
+    /// ```
+    pub fn attr_name(name: &str) -> String {
+        let is_identifier = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '\'' | '-'))
+            && !NIX_KEYWORDS.contains(&name);
+        if is_identifier {
+            name.to_string()
+        } else {
+            Self::quote(name)
+        }
+    }
+
+    /// Nix value of a preference, or `None` for null
+    fn value(value: &PrefValue) -> Option<String> {
+        Some(match StoredValue::of(value)? {
+            StoredValue::Bool(b) => b.to_string(),
+            StoredValue::Integer(i) => i.to_string(),
+            StoredValue::String(s) => Self::quote(&s),
+        })
+    }
+
+    /// Whether an entry is written as a setting rather than commented out
+    fn is_written(&self, entry: &PrefEntry) -> bool {
+        StoredValue::of(&entry.value).is_some()
+            && (self.wrapper != NixWrapper::NixOS || is_policy_settable(&entry.key))
+    }
+
+    /// Attribute lines for the preferences, at the given indentation
+    fn write_settings(
+        &self,
+        entries: &[&PrefEntry],
+        indent: &str,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        for entry in entries {
+            let name = Self::attr_name(&entry.key);
+            let Some(value) = Self::value(&entry.value) else {
+                writeln!(out, "{}# {} = null; # null cannot be set", indent, name)?;
+                continue;
+            };
+            if self.wrapper == NixWrapper::NixOS && !is_policy_settable(&entry.key) {
+                writeln!(
+                    out,
+                    "{}# {} = {}; # the Preferences policy cannot set this",
+                    indent, name, value
+                )?;
+                continue;
+            }
+            writeln!(out, "{}{} = {};", indent, name, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl PrefFormatter for NixFormatter {
+    fn write_prefs(&self, entries: &[PrefEntry], out: &mut dyn Write) -> Result<()> {
+        let entries = sorted(entries);
+        match &self.wrapper {
+            NixWrapper::AttrSet => {
+                writeln!(out, "{{")?;
+                self.write_settings(&entries, "  ", out)?;
+                writeln!(out, "}}")?;
+            }
+            NixWrapper::HomeManager { profile } => {
+                writeln!(out, "{{")?;
+                writeln!(out, "  programs.firefox = {{")?;
+                writeln!(out, "    enable = true;")?;
+                writeln!(out, "    profiles.{} = {{", Self::attr_name(profile))?;
+                writeln!(out, "      settings = {{")?;
+                self.write_settings(&entries, "        ", out)?;
+                writeln!(out, "      }};")?;
+                writeln!(out, "    }};")?;
+                writeln!(out, "  }};")?;
+                writeln!(out, "}}")?;
+            }
+            NixWrapper::NixOS => {
+                // Without an explicit status the module would lock everything
+                let statuses: Vec<PolicyStatus> = entries
+                    .iter()
+                    .filter(|entry| self.is_written(entry))
+                    .map(|entry| PolicyStatus::for_entry(entry))
+                    .collect();
+                let status = self.status.unwrap_or_else(|| {
+                    statuses
+                        .iter()
+                        .copied()
+                        .min_by_key(|status| match status {
+                            PolicyStatus::Default => 0,
+                            PolicyStatus::User => 1,
+                            PolicyStatus::Locked => 2,
+                            PolicyStatus::Clear => 3,
+                        })
+                        .unwrap_or(PolicyStatus::Default)
+                });
+                writeln!(out, "{{")?;
+                writeln!(out, "  programs.firefox = {{")?;
+                writeln!(out, "    enable = true;")?;
+                if self.status.is_none() && statuses.iter().any(|other| *other != status) {
+                    writeln!(
+                        out,
+                        "    # Preferences were set with different statuses; \
+                         the least restrictive applies to all"
+                    )?;
+                }
+                writeln!(out, "    preferencesStatus = \"{}\";", status)?;
+                writeln!(out, "    preferences = {{")?;
+                self.write_settings(&entries, "      ", out)?;
+                writeln!(out, "    }};")?;
+                writeln!(out, "  }};")?;
+                writeln!(out, "}}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_prefs_js;
+
+    #[test]
+    fn test_attr_set_types() {
+        let prefs = parse_prefs_js(
+            r#"
+            user_pref("a.bool", true);
+            user_pref("a.float", 0.5);
+            user_pref("a.int", -1);
+            user_pref("a.string", "C:\\Users\\me\t$HOME");
+            user_pref("plain_key", 1);
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            NixFormatter::default().format_prefs(&prefs).unwrap(),
+            "{\n  \
+             \"a.bool\" = true;\n  \
+             \"a.float\" = \"0.5\";\n  \
+             \"a.int\" = -1;\n  \
+             \"a.string\" = \"C:\\\\Users\\\\me\\t$HOME\";\n  \
+             plain_key = 1;\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_nixos_module() {
+        let prefs = parse_prefs_js(
+            r#"
+            lock_pref("browser.startup.page", 3);
+            lock_pref("security.sandbox.content.level", 2);
+            "#,
+        )
+        .unwrap();
+        let formatter = NixFormatter {
+            wrapper: NixWrapper::NixOS,
+            status: None,
+        };
+        assert_eq!(
+            formatter.format_prefs(&prefs).unwrap(),
+            "{\n  \
+             programs.firefox = {\n    \
+             enable = true;\n    \
+             preferencesStatus = \"locked\";\n    \
+             preferences = {\n      \
+             \"browser.startup.page\" = 3;\n      \
+             # \"security.sandbox.content.level\" = 2; # the Preferences policy cannot set this\n    \
+             };\n  \
+             };\n\
+             }\n"
+        );
+
+        // Only written preferences decide the status
+        let mixed = parse_prefs_js(
+            r#"
+            lock_pref("browser.a", 1);
+            pref("browser.b", 2);
+            user_pref("security.sandbox.content.level", 2);
+            "#,
+        )
+        .unwrap();
+        let output = formatter.format_prefs(&mixed).unwrap();
+        assert!(output.contains("# Preferences were set with different statuses"));
+        assert!(output.contains("preferencesStatus = \"default\";"));
+
+        let explicit = NixFormatter {
+            status: Some(PolicyStatus::User),
+            ..formatter
+        };
+        let output = explicit.format_prefs(&mixed).unwrap();
+        assert!(!output.contains("different statuses"));
+        assert!(output.contains("preferencesStatus = \"user\";"));
+    }
+}
@@ -0,0 +1,609 @@
+//! Offline profile analysis
+//!
+//! Profiles are often inspected away from the machine that used them: a
+//! zipped profile folder sent in by a user, a tarball of `~/.mozilla/firefox`,
+//! or a disk image mounted from another (possibly non-Linux) system. An
+//! [`OfflineSource`] confines profile lookup, listing and preference merging
+//! to such a root:
+//!
+//! - `.zip`, `.tar`, `.tar.gz` and `.tgz` archives are extracted to a
+//!   temporary directory that lives as long as the source
+//! - the profiles directory is found in the Linux, macOS and Windows layouts
+//!   under the root, or the root is used as a single profile
+//! - absolute paths in profiles.ini are mapped into the root
+//! - installations are never auto-detected; built-in defaults come only from
+//!   the installation or omni.ja supplied in [`OfflineOptions`]
+//!
+//! The host's HOME, `MOZ_PROFILES_DIR`, `FIREFOX_BIN` and installed
+//! applications are never consulted.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use ffcv::{MergeConfig, OfflineOptions, OfflineSource};
+//! use std::path::PathBuf;
+//!
+//! let source = OfflineSource::open(
+//!     "evidence/firefox-profile.zip",
+//!     OfflineOptions {
+//!         omni_ja: Some(PathBuf::from("evidence/firefox-128/browser/omni.ja")),
+//!         ..Default::default()
+//!     },
+//! )?;
+//!
+//! let profile = source.find_profile_path("default")?;
+//! let merged = source.merge_preferences(&profile, &MergeConfig::default())?;
+//! println!("{} preferences", merged.entries.len());
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::application::Application;
+use crate::error::{Error, Result};
+use crate::firefox_locator::validate_installation;
+use crate::pref_merger::{MergeConfig, PrefMerger};
+use crate::profile::{
+    find_profile_path_in, list_profiles_in, ProfileInfo, ProfileListConfig, ProfileScope,
+};
+use crate::profile_groups::{list_profile_groups_for_app, ProfileGroupList};
+use crate::profile_install::{InstallLinkSource, ProfileInstallation};
+use crate::profile_metadata::{
+    collect_profile_stats, read_profile_compatibility, read_profile_times,
+};
+use crate::types::{Installation, MergeReport, MergedPreferences};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Home directory locations inside a root, for each platform layout
+const HOME_BASES: &[&str] = &["", "home/*", "root", "Users/*", "Documents and Settings/*"];
+
+/// `%APPDATA%` locations relative to a Windows home directory
+const WINDOWS_APPDATA: &[&str] = &["AppData/Roaming", "Application Data"];
+
+/// Options for offline analysis
+///
+/// # Example
+///
+/// ```rust
+/// use ffcv::{Application, OfflineOptions};
+/// use std::path::PathBuf;
+///
+/// let options = OfflineOptions {
+///     application: Application::THUNDERBIRD,
+///     install_path: Some(PathBuf::from("/evidence/thunderbird")),
+///     omni_ja: None,
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OfflineOptions {
+    /// Application whose profiles and omni.ja layout to look for
+    pub application: Application,
+    /// Installation to pair with the profiles (never auto-detected)
+    pub install_path: Option<PathBuf>,
+    /// omni.ja to read defaults from, instead of the installation's
+    pub omni_ja: Option<PathBuf>,
+}
+
+/// What was found under the root
+#[derive(Debug, Clone)]
+enum Layout {
+    /// A profiles directory with profiles.ini
+    ProfilesDir(PathBuf),
+    /// A single profile directory without profiles.ini
+    Profile(PathBuf),
+}
+
+/// A directory tree or archive holding profiles from another machine
+#[derive(Debug)]
+pub struct OfflineSource {
+    root: PathBuf,
+    layout: Layout,
+    options: OfflineOptions,
+    installations: Vec<Installation>,
+    /// Extraction directory of an archive, removed on drop
+    _extracted: Option<TempDir>,
+}
+
+impl OfflineSource {
+    /// Open a directory or archive for offline analysis
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Extracted directory, mounted image, or `.zip`/`.tar`/`.tar.gz`/`.tgz` archive
+    /// * `options` - Application and separately supplied installation or omni.ja
+    pub fn open(path: impl AsRef<Path>, options: OfflineOptions) -> Result<Self> {
+        let path = path.as_ref();
+
+        let (root, extracted) = if path.is_dir() {
+            (path.to_path_buf(), None)
+        } else if path.is_file() {
+            let dir = tempfile::tempdir()?;
+            extract_archive(path, dir.path())?;
+            (dir.path().to_path_buf(), Some(dir))
+        } else {
+            return Err(Error::OfflineSource(format!(
+                "{} does not exist",
+                path.display()
+            )));
+        };
+
+        let layout = locate_layout(&root, &options.application)?;
+
+        let installations = match &options.install_path {
+            Some(install_path) => {
+                vec![validate_installation(install_path, &options.application)?.found_by("supplied")]
+            }
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            root,
+            layout,
+            options,
+            installations,
+            _extracted: extracted,
+        })
+    }
+
+    /// Directory all lookups are confined to (the extraction directory for archives)
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Profiles directory found under the root, if it holds more than a single profile
+    pub fn profiles_dir(&self) -> Option<&Path> {
+        match &self.layout {
+            Layout::ProfilesDir(dir) => Some(dir),
+            Layout::Profile(_) => None,
+        }
+    }
+
+    /// The supplied installation, if any
+    pub fn installation(&self) -> Option<&Installation> {
+        self.installations.first()
+    }
+
+    /// Find a profile by name, like [`crate::find_profile_path_for_app`]
+    ///
+    /// A root holding a single profile matches `"default"` and the profile's
+    /// directory name (with or without its random prefix).
+    pub fn find_profile_path(&self, profile_name: &str) -> Result<PathBuf> {
+        let path = match &self.layout {
+            Layout::ProfilesDir(dir) => {
+                find_profile_path_in(&self.options.application, profile_name, dir, self.scope())?
+            }
+            Layout::Profile(dir) => {
+                let dir_name = dir.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                let matches = profile_name == "default"
+                    || dir_name == profile_name
+                    || dir_name.ends_with(&format!(".{}", profile_name));
+                if !matches {
+                    return Err(Error::ProfileNotFound {
+                        name: profile_name.to_string(),
+                        directory: dir.clone(),
+                    });
+                }
+                dir.clone()
+            }
+        };
+
+        self.confine(path)
+    }
+
+    /// List profiles, like [`crate::list_profiles_for_app`]
+    ///
+    /// With [`ProfileListConfig::include_install`], every profile is linked
+    /// to the supplied installation.
+    pub fn list_profiles(&self, config: &ProfileListConfig) -> Result<Vec<ProfileInfo>> {
+        match &self.layout {
+            Layout::ProfilesDir(dir) => {
+                list_profiles_in(&self.options.application, dir, config, self.scope())
+            }
+            Layout::Profile(dir) => Ok(vec![self.single_profile_info(dir, config)]),
+        }
+    }
+
+    /// List profile groups, like [`crate::list_profile_groups_for_app`]
+    pub fn list_profile_groups(&self) -> Result<ProfileGroupList> {
+        match &self.layout {
+            Layout::ProfilesDir(dir) => {
+                list_profile_groups_for_app(&self.options.application, Some(dir))
+            }
+            Layout::Profile(_) => Ok(ProfileGroupList::default()),
+        }
+    }
+
+    /// Merge preferences of a profile, like [`crate::merge_all_preferences`]
+    ///
+    /// Defaults come from the supplied omni.ja or installation only.
+    pub fn merge_preferences(
+        &self,
+        profile_path: &Path,
+        config: &MergeConfig,
+    ) -> Result<MergedPreferences> {
+        Ok(self.merge_report(profile_path, config)?.merged)
+    }
+
+    /// Merge preferences of a profile, like [`PrefMerger::merge_report`]
+    pub fn merge_report(&self, profile_path: &Path, config: &MergeConfig) -> Result<MergeReport> {
+        let profile_path = self.confine(profile_path.to_path_buf())?;
+
+        let mut merger = PrefMerger::new(config.clone())
+            .application(self.options.application)
+            .detect_installation(false);
+        if let Some(omni) = &self.options.omni_ja {
+            merger = merger.omni_ja(omni);
+        }
+
+        let install_path = self.installation().map(|install| install.path.as_path());
+        merger.merge_report(&profile_path, install_path)
+    }
+
+    fn scope(&self) -> ProfileScope<'_> {
+        ProfileScope::Offline {
+            root: &self.root,
+            installations: &self.installations,
+        }
+    }
+
+    /// Reject paths that leave the root, e.g. through an absolute symlink
+    fn confine(&self, path: PathBuf) -> Result<PathBuf> {
+        if is_within(&self.root, &path) {
+            Ok(path)
+        } else {
+            Err(Error::OfflineSource(format!(
+                "{} is outside of {}",
+                path.display(),
+                self.root.display()
+            )))
+        }
+    }
+
+    /// Profile information for a root that is a single profile
+    fn single_profile_info(&self, dir: &Path, config: &ProfileListConfig) -> ProfileInfo {
+        let name = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "default".to_string());
+
+        ProfileInfo {
+            name,
+            path: dir.to_path_buf(),
+            is_default: true,
+            is_relative: false,
+            locked_to_install: None,
+            store_id: None,
+            group: None,
+            times: config
+                .include_times
+                .then(|| read_profile_times(dir).ok())
+                .flatten(),
+            compatibility: config
+                .include_compatibility
+                .then(|| read_profile_compatibility(dir).ok())
+                .flatten(),
+            stats: config
+                .include_stats
+                .then(|| collect_profile_stats(dir).ok())
+                .flatten(),
+            install_path: None,
+            installation: config
+                .include_install
+                .then(|| self.installation().cloned())
+                .flatten()
+                .map(|installation| ProfileInstallation {
+                    source: InstallLinkSource::Supplied,
+                    installation,
+                }),
+        }
+    }
+}
+
+/// Map a path recorded on another machine into `root`
+///
+/// Both `/home/alice/.mozilla/firefox/x.default` and
+/// `C:\Users\Bob\AppData\Roaming\Mozilla\Firefox\Profiles\x.default` are
+/// matched by their longest suffix that exists under the profiles directory
+/// or the root. Paths that cannot be found stay under the root.
+pub(crate) fn rebase_foreign_path(root: &Path, profiles_dir: &Path, path: &Path) -> PathBuf {
+    let path = path.to_string_lossy();
+    let components: Vec<&str> = path
+        .split(['/', '\\'])
+        .filter(|c| !c.is_empty() && !c.ends_with(':'))
+        .collect();
+
+    for start in 0..components.len() {
+        let suffix: PathBuf = components[start..].iter().collect();
+        for base in [profiles_dir, root] {
+            let candidate = base.join(&suffix);
+            if candidate.exists() && is_within(root, &candidate) {
+                return candidate;
+            }
+        }
+    }
+
+    root.join(components.iter().collect::<PathBuf>())
+}
+
+/// Whether `path` resolves to a location inside `root`
+fn is_within(root: &Path, path: &Path) -> bool {
+    match (root.canonicalize(), path.canonicalize()) {
+        (Ok(root), Ok(path)) => path.starts_with(root),
+        // Missing paths can't be read, so they can't leak anything either
+        (Ok(_), Err(_)) => !path.exists(),
+        _ => false,
+    }
+}
+
+/// Extract a profile archive into `dest`
+///
+/// Only regular files and directories are extracted; links could point
+/// outside the extraction directory.
+fn extract_archive(archive: &Path, dest: &Path) -> Result<()> {
+    let name = archive
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let file = std::fs::File::open(archive)?;
+    let failed = |e: &dyn std::fmt::Display| {
+        Error::ExtractionFailed(format!("{}: {}", archive.display(), e))
+    };
+
+    if name.ends_with(".zip") {
+        let mut zip = zip::ZipArchive::new(file).map_err(|e| failed(&e))?;
+        zip.extract(dest).map_err(|e| failed(&e))
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        unpack_tar(tar::Archive::new(flate2::read::GzDecoder::new(file)), dest)
+            .map_err(|e| failed(&e))
+    } else if name.ends_with(".tar") {
+        unpack_tar(tar::Archive::new(file), dest).map_err(|e| failed(&e))
+    } else {
+        Err(Error::OfflineSource(format!(
+            "{} is not a directory or a .zip, .tar, .tar.gz or .tgz archive",
+            archive.display()
+        )))
+    }
+}
+
+/// Unpack the regular files and directories of a tar archive
+fn unpack_tar<R: std::io::Read>(mut archive: tar::Archive<R>, dest: &Path) -> std::io::Result<()> {
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let kind = entry.header().entry_type();
+        if kind.is_file() || kind.is_dir() {
+            // unpack_in refuses entries that would land outside `dest`
+            entry.unpack_in(dest)?;
+        }
+    }
+    Ok(())
+}
+
+/// Find the profiles directory or single profile under `root`
+fn locate_layout(root: &Path, app: &Application) -> Result<Layout> {
+    // Archives of a single folder extract to one top-level directory
+    let mut chain = vec![root.to_path_buf()];
+    while let Some(only) = chain.last().and_then(|dir| single_subdirectory(dir)) {
+        chain.push(only);
+    }
+
+    for dir in &chain {
+        if dir.join(app.profiles_ini).is_file() {
+            return Ok(Layout::ProfilesDir(dir.clone()));
+        }
+        if dir.join("prefs.js").is_file() {
+            return Ok(Layout::Profile(dir.clone()));
+        }
+    }
+
+    for dir in &chain {
+        let found = find_profile_roots(dir, app);
+        match found.len() {
+            0 => continue,
+            1 => return Ok(Layout::ProfilesDir(found[0].clone())),
+            _ => {
+                let dirs: Vec<String> = found.iter().map(|d| d.display().to_string()).collect();
+                return Err(Error::OfflineSource(format!(
+                    "Found several {} profile directories; pass one of them as the root: {}",
+                    app.name,
+                    dirs.join(", ")
+                )));
+            }
+        }
+    }
+
+    Err(Error::OfflineSource(format!(
+        "No {} or prefs.js found under {}",
+        app.profiles_ini,
+        root.display()
+    )))
+}
+
+/// The only entry of a directory, if it is a directory itself
+fn single_subdirectory(dir: &Path) -> Option<PathBuf> {
+    let mut entries = std::fs::read_dir(dir).ok()?.filter_map(|e| e.ok());
+    let only = entries.next()?;
+    if entries.next().is_some() || !only.path().is_dir() {
+        return None;
+    }
+    Some(only.path())
+}
+
+/// Profile roots of every platform layout under `root` that contain profiles.ini
+fn find_profile_roots(root: &Path, app: &Application) -> Vec<PathBuf> {
+    let mut relative: Vec<String> = Vec::new();
+    for profile_root in app.profile_roots.linux {
+        relative.push(profile_root.to_string());
+        if let Some(flatpak_id) = app.flatpak_id {
+            relative.push(format!(".var/app/{}/{}", flatpak_id, profile_root));
+        }
+        if let Some(snap_name) = app.snap_name {
+            relative.push(format!("snap/{}/common/{}", snap_name, profile_root));
+        }
+    }
+    relative.extend(app.profile_roots.macos.iter().map(|r| r.to_string()));
+    for appdata in WINDOWS_APPDATA {
+        for profile_root in app.profile_roots.windows {
+            relative.push(format!("{}/{}", appdata, profile_root));
+        }
+    }
+
+    // Images of case-insensitive file systems keep whatever case Windows used
+    let options = glob::MatchOptions {
+        case_sensitive: false,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    let escaped_root = glob::Pattern::escape(&root.to_string_lossy());
+
+    let mut found: Vec<PathBuf> = Vec::new();
+    for base in HOME_BASES {
+        for rel in &relative {
+            let pattern = [
+                escaped_root.as_str(),
+                base,
+                &glob::Pattern::escape(rel),
+                app.profiles_ini,
+            ]
+            .iter()
+            .filter(|part| !part.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join("/");
+            let Ok(paths) = glob::glob_with(&pattern, options) else {
+                continue;
+            };
+            for ini in paths.flatten() {
+                if let Some(dir) = ini.parent() {
+                    if !found.iter().any(|f| f == dir) && is_within(root, dir) {
+                        found.push(dir.to_path_buf());
+                    }
+                }
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PrefSource;
+    use std::io::Write;
+
+    const PROFILES_INI: &str = "[Profile0]\nName=default-release\nIsRelative=1\n\
+                                Path=Profiles/abc.default-release\nDefault=1\n";
+
+    fn write_profile(dir: &Path) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(
+            dir.join("prefs.js"),
+            r#"user_pref("browser.startup.page", 3);"#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_open_zipped_profiles_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive = temp_dir.path().join("firefox.zip");
+
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+        let options = zip::write::FileOptions::default();
+        zip.start_file("firefox/profiles.ini", options).unwrap();
+        zip.write_all(PROFILES_INI.as_bytes()).unwrap();
+        zip.start_file("firefox/Profiles/abc.default-release/prefs.js", options)
+            .unwrap();
+        zip.write_all(br#"user_pref("browser.startup.page", 3);"#)
+            .unwrap();
+        zip.finish().unwrap();
+
+        let source = OfflineSource::open(&archive, OfflineOptions::default()).unwrap();
+        let profiles = source.list_profiles(&ProfileListConfig::default()).unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert!(profiles[0].path.starts_with(source.root()));
+
+        let profile = source.find_profile_path("default").unwrap();
+        let merged = source
+            .merge_preferences(&profile, &MergeConfig::default())
+            .unwrap();
+        assert_eq!(merged.entries.len(), 1);
+        assert_eq!(merged.loaded_sources, vec![PrefSource::User]);
+        assert!(merged.install_path.is_none());
+    }
+
+    #[test]
+    fn test_foreign_absolute_paths_are_rebased() {
+        let temp_dir = TempDir::new().unwrap();
+        let image = temp_dir.path();
+
+        let linux = image.join("home/alice/.mozilla/firefox");
+        write_profile(&linux.join("custom"));
+        std::fs::write(
+            linux.join("profiles.ini"),
+            "[Profile0]\nName=work\nIsRelative=0\nPath=/home/alice/.mozilla/firefox/custom\n",
+        )
+        .unwrap();
+
+        let source = OfflineSource::open(image, OfflineOptions::default()).unwrap();
+        assert_eq!(source.profiles_dir(), Some(linux.as_path()));
+        assert_eq!(
+            source.find_profile_path("work").unwrap(),
+            linux.join("custom")
+        );
+
+        let windows = image.join("win/Users/Bob/AppData/Roaming/Mozilla/Firefox");
+        write_profile(&windows.join("Profiles/xyz.default"));
+        std::fs::write(
+            windows.join("profiles.ini"),
+            "[Profile0]\nName=default\nIsRelative=0\n\
+             Path=C:\\Users\\Bob\\AppData\\Roaming\\Mozilla\\Firefox\\Profiles\\xyz.default\n",
+        )
+        .unwrap();
+
+        let source = OfflineSource::open(image.join("win"), OfflineOptions::default()).unwrap();
+        assert_eq!(
+            source.find_profile_path("default").unwrap(),
+            windows.join("Profiles/xyz.default")
+        );
+    }
+
+    #[test]
+    fn test_tarball_of_single_profile() {
+        let temp_dir = TempDir::new().unwrap();
+        let profile = temp_dir.path().join("abc.default-release");
+        write_profile(&profile);
+
+        let archive = temp_dir.path().join("profile.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(
+            std::fs::File::create(&archive).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        builder
+            .append_dir_all("abc.default-release", &profile)
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let source = OfflineSource::open(&archive, OfflineOptions::default()).unwrap();
+        assert!(source.profiles_dir().is_none());
+        let path = source.find_profile_path("default-release").unwrap();
+        assert!(path.join("prefs.js").is_file());
+        assert!(source.find_profile_path("work").is_err());
+    }
+
+    #[test]
+    fn test_multiple_profile_roots_are_ambiguous() {
+        let temp_dir = TempDir::new().unwrap();
+        for user in ["alice", "bob"] {
+            let dir = temp_dir
+                .path()
+                .join("home")
+                .join(user)
+                .join(".mozilla/firefox");
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("profiles.ini"), PROFILES_INI).unwrap();
+        }
+
+        let err = OfflineSource::open(temp_dir.path(), OfflineOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("several"));
+    }
+}
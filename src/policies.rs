@@ -0,0 +1,520 @@
+//! Export to Firefox enterprise policies (`policies.json`)
+//!
+//! The [`Preferences`](https://mozilla.github.io/policy-templates/#preferences)
+//! policy sets preferences with a status:
+//!
+//! - `default`: changes the default value; users can still change it
+//! - `user`: sets the value as if the user had, on every startup
+//! - `locked`: sets the default value and locks it
+//! - `clear`: removes the user value
+//!
+//! Firefox only lets the policy set preferences under a fixed list of
+//! prefixes plus a few security preferences ([`POLICY_PREF_PREFIXES`],
+//! [`POLICY_SECURITY_PREFS`]); other preferences are reported as warnings.
+//! With [`PolicyOptions::map_known`], documented preferences such as
+//! `network.trr.mode` become dedicated policies (`DNSOverHTTPS`) instead.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ffcv::{export_policies, parse_prefs_js, PolicyOptions};
+//!
+//! let prefs = parse_prefs_js(r#"
+//!     user_pref("browser.startup.page", 3);
+//!     lock_pref("network.trr.mode", 3);
+//!     user_pref("security.sandbox.content.level", 2);
+//! "#)?;
+//!
+//! let export = export_policies(&prefs, &PolicyOptions::default());
+//! let json = export.to_json();
+//! assert_eq!(
+//!     json["policies"]["Preferences"]["network.trr.mode"],
+//!     serde_json::json!({"Value": 3, "Status": "locked", "Type": "number"})
+//! );
+//! assert_eq!(
+//!     export.warnings,
+//!     ["security.sandbox.content.level: the Preferences policy cannot set this preference"]
+//! );
+//!
+//! let mapped = export_policies(&prefs, &PolicyOptions { map_known: true, ..Default::default() });
+//! assert_eq!(
+//!     mapped.to_json()["policies"]["DNSOverHTTPS"],
+//!     serde_json::json!({"Enabled": true, "Fallback": false, "Locked": true})
+//! );
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::error::{Error, Result};
+use crate::formatter::StoredValue;
+use crate::pref_set::{PrefLookup, PrefSet};
+use crate::typed_pref::{CookieBehavior, FromPrefValue, KnownPref, StartupPage, TrrMode};
+use crate::types::{PrefEntry, PrefType, PrefValueExt};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeSet;
+
+/// Prefixes the `Preferences` policy may set (from Firefox's policy engine)
+pub const POLICY_PREF_PREFIXES: &[&str] = &[
+    "accessibility.",
+    "alerts.",
+    "app.update.",
+    "browser.",
+    "datareporting.policy.",
+    "dom.",
+    "extensions.",
+    "general.autoScroll",
+    "general.smoothScroll",
+    "geo.",
+    "gfx.",
+    "identity.fxaccounts.toolbar",
+    "intl.",
+    "keyword.enabled",
+    "layers.",
+    "layout.",
+    "media.",
+    "network.",
+    "pdfjs.",
+    "places.",
+    "pref.",
+    "print.",
+    "privacy.globalprivacycontrol.enabled",
+    "privacy.userContext.enabled",
+    "privacy.userContext.ui.enabled",
+    "signon.",
+    "spellchecker.",
+    "toolkit.legacyUserProfileCustomizations.stylesheets",
+    "ui.",
+    "widget.",
+    "xpinstall.enabled",
+    "xpinstall.whitelist.required",
+];
+
+/// `security.` preferences the `Preferences` policy may set
+pub const POLICY_SECURITY_PREFS: &[&str] = &[
+    "security.block_fileuri_script_with_wrong_mime",
+    "security.default_personal_cert",
+    "security.disable_button.openCertManager",
+    "security.disable_button.openDeviceManager",
+    "security.insecure_connection_text.enabled",
+    "security.insecure_connection_text.pbmode.enabled",
+    "security.mixed_content.block_active_content",
+    "security.mixed_content.block_display_content",
+    "security.mixed_content.upgrade_display_content",
+    "security.osclientcerts.autoload",
+    "security.OCSP.enabled",
+    "security.OCSP.require",
+    "security.ssl.enable_ocsp_stapling",
+    "security.ssl.errorReporting.enabled",
+    "security.ssl.require_safe_negotiation",
+    "security.tls.enable_0rtt_data",
+    "security.tls.hello_downgrade_check",
+    "security.tls.version.enable-deprecated",
+    "security.warn_submit_secure_to_insecure",
+];
+
+/// Boolean preferences with a dedicated policy: `(pref, policy, inverted)`
+const BOOL_POLICIES: &[(&str, &str, bool)] = &[
+    ("app.update.auto", "AppAutoUpdate", false),
+    (
+        "browser.download.useDownloadDir",
+        "PromptForDownloadLocation",
+        true,
+    ),
+    ("browser.formfill.enable", "DisableFormHistory", true),
+    (
+        "browser.search.suggest.enabled",
+        "SearchSuggestEnabled",
+        false,
+    ),
+    (
+        "browser.shell.checkDefaultBrowser",
+        "DontCheckDefaultBrowser",
+        true,
+    ),
+    (
+        "datareporting.healthreport.uploadEnabled",
+        "DisableTelemetry",
+        true,
+    ),
+    ("extensions.pocket.enabled", "DisablePocket", true),
+    ("signon.rememberSignons", "PasswordManagerEnabled", false),
+];
+
+/// Whether the `Preferences` policy may set a preference
+///
+/// # Example
+///
+/// ```rust
+/// use ffcv::is_policy_settable;
+///
+/// assert!(is_policy_settable("browser.startup.homepage"));
+/// assert!(is_policy_settable("security.OCSP.require"));
+/// assert!(!is_policy_settable("security.sandbox.content.level"));
+/// ```
+pub fn is_policy_settable(key: &str) -> bool {
+    POLICY_PREF_PREFIXES
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+        || POLICY_SECURITY_PREFS.contains(&key)
+}
+
+/// Status of a preference set by the `Preferences` policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyStatus {
+    /// Change the default value
+    Default,
+    /// Set the user value
+    User,
+    /// Set and lock the default value
+    Locked,
+    /// Clear the user value
+    Clear,
+}
+
+impl PolicyStatus {
+    /// Status matching how a preference was set
+    ///
+    /// `lock_pref` (or a locked three-argument `pref`) becomes `locked`,
+    /// `pref` and `sticky_pref` become `default`, `user_pref` becomes `user`.
+    pub fn for_entry(entry: &PrefEntry) -> Self {
+        if entry.is_locked() {
+            PolicyStatus::Locked
+        } else if entry.pref_type == PrefType::User {
+            PolicyStatus::User
+        } else {
+            PolicyStatus::Default
+        }
+    }
+}
+
+impl std::fmt::Display for PolicyStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolicyStatus::Default => write!(f, "default"),
+            PolicyStatus::User => write!(f, "user"),
+            PolicyStatus::Locked => write!(f, "locked"),
+            PolicyStatus::Clear => write!(f, "clear"),
+        }
+    }
+}
+
+impl std::str::FromStr for PolicyStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "default" => Ok(PolicyStatus::Default),
+            "user" => Ok(PolicyStatus::User),
+            "locked" => Ok(PolicyStatus::Locked),
+            "clear" => Ok(PolicyStatus::Clear),
+            _ => Err(Error::UnknownPolicyStatus(format!(
+                "'{}'. Valid values: default, user, locked, clear",
+                s
+            ))),
+        }
+    }
+}
+
+/// Options for [`export_policies`]
+#[derive(Debug, Clone, Default)]
+pub struct PolicyOptions {
+    /// Status for every preference instead of one derived from its type
+    pub status: Option<PolicyStatus>,
+    /// Map documented preferences onto dedicated policies
+    /// (`DNSOverHTTPS`, `Cookies`, `Homepage`, `DisableTelemetry`, ...)
+    pub map_known: bool,
+}
+
+/// Result of [`export_policies`]
+#[derive(Debug, Clone, Default)]
+pub struct PoliciesExport {
+    /// Policies by name, including `Preferences` when any preference is set
+    pub policies: Map<String, Value>,
+    /// Preferences that were left out, with the reason
+    pub warnings: Vec<String>,
+}
+
+impl PoliciesExport {
+    /// The `policies.json` document
+    pub fn to_json(&self) -> Value {
+        json!({ "policies": self.policies })
+    }
+}
+
+/// Convert preferences into enterprise policies
+///
+/// Each preference becomes an entry of the `Preferences` policy. Preferences
+/// the policy may not set and null values are skipped with a warning.
+pub fn export_policies(entries: &[PrefEntry], options: &PolicyOptions) -> PoliciesExport {
+    let mut export = PoliciesExport::default();
+    let prefs = PrefSet::from(entries.to_vec());
+    let status_of = |entry: &PrefEntry| {
+        options
+            .status
+            .unwrap_or_else(|| PolicyStatus::for_entry(entry))
+    };
+
+    let mut mapped = BTreeSet::new();
+    if options.map_known {
+        map_known_policies(&prefs, &status_of, &mut export.policies, &mut mapped);
+    }
+
+    let mut preferences = Map::new();
+    for entry in prefs.iter_sorted() {
+        if mapped.contains(entry.key.as_str()) {
+            continue;
+        }
+        if !is_policy_settable(&entry.key) {
+            export.warnings.push(format!(
+                "{}: the Preferences policy cannot set this preference",
+                entry.key
+            ));
+            continue;
+        }
+
+        let status = status_of(entry);
+        if status == PolicyStatus::Clear {
+            preferences.insert(entry.key.clone(), json!({ "Status": status }));
+            continue;
+        }
+        let (value, pref_type) = match StoredValue::of(&entry.value) {
+            Some(StoredValue::Bool(b)) => (json!(b), "boolean"),
+            Some(StoredValue::Integer(i)) => (json!(i), "number"),
+            Some(StoredValue::String(s)) => (json!(s), "string"),
+            None => {
+                export
+                    .warnings
+                    .push(format!("{}: null values cannot be set", entry.key));
+                continue;
+            }
+        };
+        preferences.insert(
+            entry.key.clone(),
+            json!({ "Value": value, "Status": status, "Type": pref_type }),
+        );
+    }
+
+    if !preferences.is_empty() {
+        export
+            .policies
+            .insert("Preferences".to_string(), Value::Object(preferences));
+    }
+    export
+}
+
+/// Add dedicated policies for documented preferences, recording mapped keys
+///
+/// A preference whose value has no policy equivalent (e.g. TRR mode 0) is
+/// left for the `Preferences` policy.
+fn map_known_policies<'a>(
+    prefs: &'a PrefSet,
+    status_of: &dyn Fn(&PrefEntry) -> PolicyStatus,
+    policies: &mut Map<String, Value>,
+    mapped: &mut BTreeSet<&'a str>,
+) {
+    let locked = |entry: &PrefEntry| status_of(entry) == PolicyStatus::Locked;
+
+    for (key, policy, inverted) in BOOL_POLICIES {
+        if let Some((entry, value)) = prefs
+            .lookup(key)
+            .and_then(|entry| Some((entry, bool::from_pref_value(key, &entry.value).ok()?)))
+        {
+            policies.insert(policy.to_string(), json!(value != *inverted));
+            mapped.insert(entry.key.as_str());
+        }
+    }
+
+    // DNSOverHTTPS: network.trr.mode, with the resolver and exclusions
+    if let Some(entry) = prefs.lookup(TrrMode::KEY) {
+        let doh = match TrrMode::from_pref_value(&entry.key, &entry.value) {
+            Ok(TrrMode::First) => Some(json!({ "Enabled": true, "Fallback": true })),
+            Ok(TrrMode::Only) => Some(json!({ "Enabled": true, "Fallback": false })),
+            Ok(TrrMode::ExplicitlyOff) => Some(json!({ "Enabled": false })),
+            Ok(TrrMode::Off) | Err(_) => None,
+        };
+        if let Some(mut doh) = doh {
+            mapped.insert(entry.key.as_str());
+            if let Some(uri) = prefs.lookup("network.trr.uri") {
+                if let Some(uri) = uri.value.as_str() {
+                    doh["ProviderURL"] = json!(uri);
+                    mapped.insert("network.trr.uri");
+                }
+            }
+            if let Some(excluded) = prefs.lookup("network.trr.excluded-domains") {
+                if let Some(domains) = excluded.value.as_str() {
+                    let domains: Vec<&str> = domains
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|domain| !domain.is_empty())
+                        .collect();
+                    doh["ExcludedDomains"] = json!(domains);
+                    mapped.insert("network.trr.excluded-domains");
+                }
+            }
+            if locked(entry) {
+                doh["Locked"] = json!(true);
+            }
+            policies.insert("DNSOverHTTPS".to_string(), doh);
+        }
+    }
+
+    // Cookies: network.cookie.cookieBehavior
+    if let Some(entry) = prefs.lookup(CookieBehavior::KEY) {
+        if let Ok(behavior) = CookieBehavior::from_pref_value(&entry.key, &entry.value) {
+            let behavior = match behavior {
+                CookieBehavior::AcceptAll => "accept",
+                CookieBehavior::RejectThirdParty => "reject-foreign",
+                CookieBehavior::RejectAll => "reject",
+                CookieBehavior::LimitForeign => "limit-foreign",
+                CookieBehavior::RejectTrackers => "reject-tracker",
+                CookieBehavior::RejectTrackersAndPartitionForeign => {
+                    "reject-tracker-and-partition-foreign"
+                }
+            };
+            let mut cookies = json!({ "Behavior": behavior });
+            if locked(entry) {
+                cookies["Locked"] = json!(true);
+            }
+            policies.insert("Cookies".to_string(), cookies);
+            mapped.insert(entry.key.as_str());
+        }
+    }
+
+    // Homepage: browser.startup.homepage ("|"-separated) and browser.startup.page
+    let mut homepage = Map::new();
+    let mut homepage_locked = false;
+    if let Some(entry) = prefs.lookup("browser.startup.homepage") {
+        if let Some(urls) = entry.value.as_str() {
+            let mut urls = urls.split('|');
+            homepage.insert("URL".to_string(), json!(urls.next().unwrap_or_default()));
+            let additional: Vec<&str> = urls.collect();
+            if !additional.is_empty() {
+                homepage.insert("Additional".to_string(), json!(additional));
+            }
+            homepage_locked |= locked(entry);
+            mapped.insert(entry.key.as_str());
+        }
+    }
+    if let Some(entry) = prefs.lookup(StartupPage::KEY) {
+        let start_page = match StartupPage::from_pref_value(&entry.key, &entry.value) {
+            Ok(StartupPage::Blank) => Some("none"),
+            Ok(StartupPage::Home) => Some("homepage"),
+            Ok(StartupPage::RestoreSession) => Some("previous-session"),
+            Ok(StartupPage::LastVisited) | Err(_) => None,
+        };
+        if let Some(start_page) = start_page {
+            homepage.insert("StartPage".to_string(), json!(start_page));
+            homepage_locked |= locked(entry);
+            mapped.insert(entry.key.as_str());
+        }
+    }
+    if !homepage.is_empty() {
+        if homepage_locked {
+            homepage.insert("Locked".to_string(), json!(true));
+        }
+        policies.insert("Homepage".to_string(), Value::Object(homepage));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_prefs_js;
+
+    #[test]
+    fn test_status_and_allowlist() {
+        let prefs = parse_prefs_js(
+            r#"
+            pref("browser.tabs.warnOnClose", false);
+            sticky_pref("browser.display.use_system_colors", true);
+            pref("general.smoothScroll", true, true);
+            user_pref("layout.css.devPixelsPerPx", 1.25);
+            user_pref("security.OCSP.require", true);
+            user_pref("toolkit.telemetry.enabled", false);
+            "#,
+        )
+        .unwrap();
+
+        let export = export_policies(&prefs, &PolicyOptions::default());
+        let preferences = &export.to_json()["policies"]["Preferences"];
+        assert_eq!(preferences["browser.tabs.warnOnClose"]["Status"], "default");
+        assert_eq!(
+            preferences["browser.display.use_system_colors"]["Status"],
+            "default"
+        );
+        assert_eq!(preferences["general.smoothScroll"]["Status"], "locked");
+        assert_eq!(
+            preferences["layout.css.devPixelsPerPx"],
+            json!({"Value": "1.25", "Status": "user", "Type": "string"})
+        );
+        assert_eq!(preferences["security.OCSP.require"]["Type"], "boolean");
+        assert!(preferences.get("toolkit.telemetry.enabled").is_none());
+        assert_eq!(export.warnings.len(), 1);
+
+        let cleared = export_policies(
+            &prefs[..1],
+            &PolicyOptions {
+                status: Some(PolicyStatus::Clear),
+                map_known: false,
+            },
+        );
+        assert_eq!(
+            cleared.to_json()["policies"]["Preferences"]["browser.tabs.warnOnClose"],
+            json!({"Status": "clear"})
+        );
+        assert_eq!(
+            "Locked".parse::<PolicyStatus>().unwrap(),
+            PolicyStatus::Locked
+        );
+        assert!("sticky".parse::<PolicyStatus>().is_err());
+    }
+
+    #[test]
+    fn test_map_known_policies() {
+        let prefs = parse_prefs_js(
+            r#"
+            user_pref("browser.startup.homepage", "https://a.example|https://b.example");
+            user_pref("browser.startup.page", 2);
+            user_pref("datareporting.healthreport.uploadEnabled", false);
+            user_pref("network.cookie.cookieBehavior", 1);
+            user_pref("network.trr.mode", 2);
+            user_pref("network.trr.uri", "https://dns.example/dns-query");
+            user_pref("network.trr.excluded-domains", "lan, home.arpa");
+            user_pref("signon.rememberSignons", "yes");
+            "#,
+        )
+        .unwrap();
+        let export = export_policies(
+            &prefs,
+            &PolicyOptions {
+                map_known: true,
+                ..Default::default()
+            },
+        );
+        let policies = &export.to_json()["policies"];
+
+        assert_eq!(
+            policies["DNSOverHTTPS"],
+            json!({
+                "Enabled": true,
+                "Fallback": true,
+                "ProviderURL": "https://dns.example/dns-query",
+                "ExcludedDomains": ["lan", "home.arpa"]
+            })
+        );
+        assert_eq!(policies["Cookies"], json!({"Behavior": "reject-foreign"}));
+        assert_eq!(policies["DisableTelemetry"], json!(true));
+        // "Last visited page" has no StartPage equivalent
+        assert_eq!(
+            policies["Homepage"],
+            json!({"URL": "https://a.example", "Additional": ["https://b.example"]})
+        );
+
+        // Unmapped values stay in Preferences
+        let preferences = policies["Preferences"].as_object().unwrap();
+        let keys: Vec<&str> = preferences.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["browser.startup.page", "signon.rememberSignons"]);
+        assert!(export.warnings.is_empty());
+    }
+}
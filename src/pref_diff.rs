@@ -0,0 +1,304 @@
+//! Structural diff between two preference sets
+//!
+//! [`diff_preferences`] compares two lists of preferences, typically a
+//! broken profile and a working one, by key. Each list is first reduced to
+//! its effective entries (later entries for a key win, as in prefs.js).
+//!
+//! # Example
+//!
+//! ```rust
+//! use ffcv::{diff_preferences, parse_prefs_js, PrefValue};
+//!
+//! let working = parse_prefs_js(r#"
+//!     user_pref("network.trr.mode", 2);
+//!     user_pref("browser.startup.page", 3);
+//! "#)?;
+//! let broken = parse_prefs_js(r#"
+//!     user_pref("network.trr.mode", "3");
+//!     user_pref("network.proxy.type", 1);
+//! "#)?;
+//!
+//! let diff = diff_preferences(&working, &broken);
+//! assert_eq!(diff.added[0].key, "network.proxy.type");
+//! assert_eq!(diff.removed[0].key, "browser.startup.page");
+//! assert_eq!(diff.changed[0].new.value, PrefValue::String("3".into()));
+//! assert!(diff.changed[0].type_changed);
+//!
+//! // A user.js that turns the first set into the second
+//! assert_eq!(
+//!     diff.to_prefs_js(),
+//!     "user_pref(\"network.proxy.type\", 1);\n\
+//!      user_pref(\"network.trr.mode\", \"3\");\n\
+//!      // \"browser.startup.page\": removed; reset it in about:config\n"
+//! );
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+//!
+//! [`review_user_prefs`] checks a profile against a change of defaults, such
+//! as an upgrade between Firefox versions.
+
+use crate::formatter::{PrefFormatter, PrefsJsFormatter};
+use crate::pref_set::{PrefLookup, PrefSet};
+use crate::types::{PrefEntry, PrefValueExt};
+use serde::Serialize;
+
+/// A preference present in both sets with a different value or lock state
+#[derive(Debug, Clone, Serialize)]
+pub struct PrefChange {
+    /// Preference key
+    pub key: String,
+    /// Entry in the first set
+    pub old: PrefEntry,
+    /// Entry in the second set
+    pub new: PrefEntry,
+    /// Whether the value type changed (e.g. Integer to String)
+    pub type_changed: bool,
+}
+
+/// Differences between two preference sets, each sorted by key
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PrefDiff {
+    /// Preferences only in the second set
+    pub added: Vec<PrefEntry>,
+    /// Preferences only in the first set
+    pub removed: Vec<PrefEntry>,
+    /// Preferences in both sets that differ
+    pub changed: Vec<PrefChange>,
+}
+
+/// Compare two preference sets by key
+///
+/// A preference counts as changed when its value or its lock state differs;
+/// a different source alone (the same value from omni.ja and prefs.js) is not
+/// a change.
+pub fn diff_preferences(a: &[PrefEntry], b: &[PrefEntry]) -> PrefDiff {
+    let a = PrefSet::from(a.to_vec());
+    let b = PrefSet::from(b.to_vec());
+    let mut diff = PrefDiff::default();
+
+    for old in a.iter_sorted() {
+        match b.lookup(&old.key) {
+            None => diff.removed.push(old.clone()),
+            Some(new) if new.value != old.value || new.is_locked() != old.is_locked() => {
+                diff.changed.push(PrefChange {
+                    key: old.key.clone(),
+                    old: old.clone(),
+                    new: new.clone(),
+                    type_changed: new.value.type_name() != old.value.type_name(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    diff.added = b
+        .iter_sorted()
+        .filter(|new| !a.contains_key(&new.key))
+        .cloned()
+        .collect();
+    diff
+}
+
+impl PrefDiff {
+    /// Whether the sets are the same
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Number of differing preferences
+    pub fn len(&self) -> usize {
+        self.added.len() + self.removed.len() + self.changed.len()
+    }
+
+    /// Unified diff of `key = value` lines, in key order
+    ///
+    /// Changes of value type or lock state are noted after the new line.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ffcv::{diff_preferences, parse_prefs_js};
+    ///
+    /// let a = parse_prefs_js(r#"user_pref("a", 1); user_pref("b", true);"#)?;
+    /// let b = parse_prefs_js(r#"user_pref("a", "1"); user_pref("c", false);"#)?;
+    ///
+    /// assert_eq!(
+    ///     diff_preferences(&a, &b).to_unified("old", "new"),
+    ///     "--- old\n+++ new\n-a = 1\n+a = \"1\"  # type Integer -> String\n-b = true\n+c = false\n"
+    /// );
+    /// # Ok::<(), ffcv::Error>(())
+    /// ```
+    pub fn to_unified(&self, label_a: &str, label_b: &str) -> String {
+        let mut lines: Vec<(&str, String)> = Vec::with_capacity(self.len() * 2);
+        for entry in &self.removed {
+            lines.push((&entry.key, format!("-{} = {}", entry.key, entry.value)));
+        }
+        for entry in &self.added {
+            lines.push((&entry.key, format!("+{} = {}", entry.key, entry.value)));
+        }
+        for change in &self.changed {
+            let mut notes = Vec::new();
+            if change.type_changed {
+                notes.push(format!(
+                    "type {} -> {}",
+                    change.old.value.type_name(),
+                    change.new.value.type_name()
+                ));
+            }
+            match (change.old.is_locked(), change.new.is_locked()) {
+                (false, true) => notes.push("now locked".to_string()),
+                (true, false) => notes.push("no longer locked".to_string()),
+                _ => {}
+            }
+            let note = if notes.is_empty() {
+                String::new()
+            } else {
+                format!("  # {}", notes.join(", "))
+            };
+            lines.push((
+                &change.key,
+                format!(
+                    "-{} = {}\n+{} = {}{}",
+                    change.key, change.old.value, change.key, change.new.value, note
+                ),
+            ));
+        }
+        // Stable sort keeps "-" before "+" for the same key
+        lines.sort_by(|x, y| x.0.cmp(y.0));
+
+        let mut out = format!("--- {}\n+++ {}\n", label_a, label_b);
+        for (_, line) in lines {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// `user_pref(...)` lines that turn the first set into the second
+    ///
+    /// Added and changed preferences are set to their new value. A user.js
+    /// cannot remove a preference, so removed ones are listed as comments.
+    pub fn to_prefs_js(&self) -> String {
+        let updates: Vec<PrefEntry> = self
+            .added
+            .iter()
+            .cloned()
+            .chain(self.changed.iter().map(|change| change.new.clone()))
+            .collect();
+        // Formatting into a string cannot fail
+        let mut out = PrefsJsFormatter.format_prefs(&updates).unwrap_or_default();
+        for entry in &self.removed {
+            out.push_str(&format!(
+                "// {}: removed; reset it in about:config\n",
+                PrefsJsFormatter::quote(&entry.key)
+            ));
+        }
+        out
+    }
+}
+
+/// User preferences affected by a change of defaults, e.g. a Firefox upgrade
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UserPrefReview {
+    /// User values equal to the new default; they can be reset
+    pub redundant: Vec<PrefEntry>,
+    /// User values for keys the new defaults no longer define
+    pub removed: Vec<PrefEntry>,
+}
+
+/// Check user preferences against old and new defaults
+///
+/// A key counts as removed when the old defaults define it and the new ones
+/// do not; keys neither defines (e.g. from extensions) are not reported.
+///
+/// # Example
+///
+/// ```rust
+/// use ffcv::{parse_prefs_js, review_user_prefs};
+///
+/// let esr115 = parse_prefs_js(r#"
+///     pref("dom.ipc.plugins.enabled", true);
+///     pref("browser.cache.disk.capacity", 1048576);
+/// "#)?;
+/// let esr128 = parse_prefs_js(r#"pref("browser.cache.disk.capacity", 2097152);"#)?;
+/// let user = parse_prefs_js(r#"
+///     user_pref("dom.ipc.plugins.enabled", false);
+///     user_pref("browser.cache.disk.capacity", 2097152);
+/// "#)?;
+///
+/// let review = review_user_prefs(&user, &esr115, &esr128);
+/// assert_eq!(review.redundant[0].key, "browser.cache.disk.capacity");
+/// assert_eq!(review.removed[0].key, "dom.ipc.plugins.enabled");
+/// # Ok::<(), ffcv::Error>(())
+/// ```
+pub fn review_user_prefs(
+    user: &[PrefEntry],
+    old_defaults: &[PrefEntry],
+    new_defaults: &[PrefEntry],
+) -> UserPrefReview {
+    let user = PrefSet::from(user.to_vec());
+    let old_defaults = PrefSet::from(old_defaults.to_vec());
+    let new_defaults = PrefSet::from(new_defaults.to_vec());
+    let mut review = UserPrefReview::default();
+
+    for entry in user.iter_sorted() {
+        match new_defaults.lookup(&entry.key) {
+            Some(default) if default.value == entry.value => review.redundant.push(entry.clone()),
+            Some(_) => {}
+            None if old_defaults.contains_key(&entry.key) => review.removed.push(entry.clone()),
+            None => {}
+        }
+    }
+    review
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_prefs_js;
+    use crate::types::PrefSource;
+
+    #[test]
+    fn test_diff_categories_and_lock_changes() {
+        let a = parse_prefs_js(
+            r#"
+            pref("same", 1);
+            pref("locking", true);
+            user_pref("dup", 1);
+            user_pref("dup", 2);
+            "#,
+        )
+        .unwrap();
+        let mut b = parse_prefs_js(
+            r#"
+            user_pref("same", 1);
+            lock_pref("locking", true);
+            user_pref("dup", 2);
+            "#,
+        )
+        .unwrap();
+        b[0].source = Some(PrefSource::User);
+
+        let diff = diff_preferences(&a, &b);
+        // "same" differs only in type of pref and source; "dup" resolves to 2 in both
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff.changed[0].key, "locking");
+        assert!(!diff.changed[0].type_changed);
+        assert!(diff
+            .to_unified("a", "b")
+            .ends_with("+locking = true  # now locked\n"));
+
+        assert!(diff_preferences(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn test_json_shape() {
+        let a = parse_prefs_js(r#"user_pref("k", 1);"#).unwrap();
+        let b = parse_prefs_js(r#"user_pref("k", 2.5);"#).unwrap();
+        let json = serde_json::to_value(diff_preferences(&a, &b)).unwrap();
+        assert_eq!(json["added"], serde_json::json!([]));
+        assert_eq!(json["changed"][0]["key"], "k");
+        assert_eq!(json["changed"][0]["old"]["value"], 1);
+        assert_eq!(json["changed"][0]["new"]["type"], "Float");
+        assert_eq!(json["changed"][0]["type_changed"], true);
+    }
+}
@@ -4,13 +4,14 @@
 //! multiple sources (built-in defaults, global defaults, and user preferences)
 //! with proper precedence handling.
 
+use crate::application::Application;
 use crate::error::{Error, Result};
 use crate::firefox_locator;
 use crate::omni_extractor::{ExtractConfig, OmniExtractor};
 use crate::parser::parse_prefs_js_file;
-use crate::types::{MergedPreferences, PrefEntry, PrefSource};
-use std::collections::HashMap;
-use std::path::Path;
+use crate::pref_set::{PrefLookup, PrefSet};
+use crate::types::{MergeReport, MergedPreferences, PrefEntry, PrefSource, TypeMismatch};
+use std::path::{Path, PathBuf};
 
 /// Configuration for preference merging
 ///
@@ -51,6 +52,240 @@ impl Default for MergeConfig {
     }
 }
 
+/// Preference merger with options beyond [`MergeConfig`]
+///
+/// [`merge_all_preferences`] merges a Firefox profile with the defaults of
+/// an auto-detected Firefox installation. A `PrefMerger` can target another
+/// application, read the defaults from a specific omni.ja, or disable
+/// installation detection for offline analysis.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ffcv::{Application, MergeConfig, PrefMerger};
+/// use std::path::Path;
+///
+/// let merged = PrefMerger::new(MergeConfig::default())
+///     .application(Application::THUNDERBIRD)
+///     .omni_ja("/opt/thunderbird/omni.ja")
+///     .merge(Path::new("/home/user/.thunderbird/default"), None)?;
+/// # Ok::<(), ffcv::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct PrefMerger {
+    config: MergeConfig,
+    application: Application,
+    detect_installation: bool,
+    omni_ja_path: Option<PathBuf>,
+}
+
+impl PrefMerger {
+    /// Create a merger for Firefox with the given source selection
+    pub fn new(config: MergeConfig) -> Self {
+        Self {
+            config,
+            application: Application::FIREFOX,
+            detect_installation: true,
+            omni_ja_path: None,
+        }
+    }
+
+    /// Application whose installation is auto-detected and whose omni.ja
+    /// layout is used for built-in defaults
+    pub fn application(mut self, application: Application) -> Self {
+        self.application = application;
+        self
+    }
+
+    /// Auto-detect an installation when none is passed to [`PrefMerger::merge`]
+    /// (enabled by default); disable this to keep the local installation out
+    /// of offline analysis
+    pub fn detect_installation(mut self, detect: bool) -> Self {
+        self.detect_installation = detect;
+        self
+    }
+
+    /// Read built-in and global defaults from this omni.ja instead of the
+    /// installation's own
+    pub fn omni_ja(mut self, path: impl Into<PathBuf>) -> Self {
+        self.omni_ja_path = Some(path.into());
+        self
+    }
+
+    /// Merge the preferences of a profile, like [`merge_all_preferences`]
+    pub fn merge(
+        &self,
+        profile_path: &Path,
+        install_path: Option<&Path>,
+    ) -> Result<MergedPreferences> {
+        Ok(self.merge_report(profile_path, install_path)?.merged)
+    }
+
+    /// Merge the preferences of a profile and report the user values that
+    /// were ignored because their type differs from the default's
+    pub fn merge_report(
+        &self,
+        profile_path: &Path,
+        install_path: Option<&Path>,
+    ) -> Result<MergeReport> {
+        let mut warnings = Vec::new();
+        let mut loaded_sources = Vec::new();
+        let mut type_mismatches = Vec::new();
+        let mut pref_set = PrefSet::new();
+
+        // Auto-detect Firefox installation if not provided
+        let resolved_install_path = if let Some(path) = install_path {
+            Some(path.to_path_buf())
+        } else if self.omni_ja_path.is_some() || !self.detect_installation {
+            None
+        } else if self.config.include_builtins || self.config.include_globals {
+            match firefox_locator::find_installation_for_app(&self.application) {
+                Ok(Some(install)) => {
+                    loaded_sources.push(PrefSource::BuiltIn);
+                    Some(install.path)
+                }
+                Ok(None) => {
+                    warnings.push(format!("{} installation not found", self.application.name));
+                    None
+                }
+                Err(e) => {
+                    warnings.push(format!("Failed to locate {}: {}", self.application.name, e));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Load built-in defaults from omni.ja (lowest precedence)
+        let omni_path = match (&self.omni_ja_path, &resolved_install_path) {
+            (Some(omni), _) => Some(omni.clone()),
+            (None, Some(install)) => find_omni_ja(
+                install,
+                &self.application,
+                self.config.include_builtins,
+                &mut warnings,
+            ),
+            (None, None) => None,
+        };
+
+        if (self.config.include_builtins || self.config.include_globals)
+            && !self.detect_installation
+            && resolved_install_path.is_none()
+            && omni_path.is_none()
+        {
+            warnings.push(format!(
+                "No {} installation or omni.ja supplied; defaults not loaded",
+                self.application.name
+            ));
+        }
+
+        if self.config.include_builtins {
+            if resolved_install_path.is_some()
+                && omni_path.is_none()
+                && !self.config.continue_on_error
+            {
+                return Err(Error::OmniJaError(
+                    "Failed to load built-in preferences: omni.ja not found".to_string(),
+                ));
+            }
+            if let Some(ref omni) = omni_path {
+                match load_builtin_preferences(omni, &mut warnings) {
+                    Ok(builtins) => {
+                        pref_set.extend(builtins);
+                        loaded_sources.push(PrefSource::BuiltIn);
+                    }
+                    Err(e) => {
+                        let msg = format!("Failed to load built-in preferences: {}", e);
+                        warnings.push(msg.clone());
+                        if !self.config.continue_on_error {
+                            return Err(Error::OmniJaError(msg));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Load global defaults from greprefs.js (medium precedence)
+        if self.config.include_globals && (resolved_install_path.is_some() || omni_path.is_some()) {
+            match load_global_preferences(
+                resolved_install_path.as_deref(),
+                self.omni_ja_path.as_deref(),
+                &mut warnings,
+            ) {
+                Ok(globals) => {
+                    pref_set.extend(globals);
+                    loaded_sources.push(PrefSource::GlobalDefault);
+                }
+                Err(e) => {
+                    let msg = format!("Failed to load global preferences: {}", e);
+                    warnings.push(msg);
+                    if !self.config.continue_on_error {
+                        return Err(Error::PrefFileNotFound {
+                            file: "greprefs.js".to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        // Load user preferences from prefs.js (highest precedence)
+        if self.config.include_user {
+            let prefs_js_path = profile_path.join("prefs.js");
+
+            match load_user_preferences(&prefs_js_path, &mut warnings) {
+                Ok(user_prefs) => {
+                    // Firefox ignores a user value whose type differs from the
+                    // default's; keep the default in effect, as libpref does
+                    let mut kept = Vec::with_capacity(user_prefs.len());
+                    for entry in user_prefs {
+                        let mismatch = pref_set
+                            .lookup(&entry.key)
+                            .and_then(|default| TypeMismatch::check(&entry, default));
+                        match mismatch {
+                            Some(mismatch) => {
+                                warnings.push(format!("Type mismatch: {}", mismatch));
+                                type_mismatches.push(mismatch);
+                            }
+                            None => kept.push(entry),
+                        }
+                    }
+                    pref_set.extend(kept);
+                    loaded_sources.push(PrefSource::User);
+                }
+                Err(e) => {
+                    let msg = format!("Failed to load user preferences: {}", e);
+                    warnings.push(msg);
+                    if !self.config.continue_on_error {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        // Later sources override earlier ones; keep the effective entries
+        let entries = pref_set.to_sorted_vec();
+
+        Ok(MergeReport {
+            merged: MergedPreferences {
+                entries,
+                install_path: resolved_install_path,
+                profile_path: profile_path.to_path_buf(),
+                loaded_sources,
+                warnings,
+            },
+            type_mismatches,
+            layers: pref_set,
+        })
+    }
+}
+
+impl From<MergeConfig> for PrefMerger {
+    fn from(config: MergeConfig) -> Self {
+        Self::new(config)
+    }
+}
+
 /// Merge preferences from multiple sources
 ///
 /// This function loads preferences from built-in defaults (omni.ja),
@@ -89,117 +324,17 @@ pub fn merge_all_preferences(
     install_path: Option<&Path>,
     config: &MergeConfig,
 ) -> Result<MergedPreferences> {
-    let mut warnings = Vec::new();
-    let mut loaded_sources = Vec::new();
-    let mut pref_map: HashMap<String, PrefEntry> = HashMap::new();
-
-    // Auto-detect Firefox installation if not provided
-    let resolved_install_path = if let Some(path) = install_path {
-        Some(path.to_path_buf())
-    } else if config.include_builtins || config.include_globals {
-        match firefox_locator::find_firefox_installation() {
-            Ok(Some(install)) => {
-                loaded_sources.push(PrefSource::BuiltIn);
-                Some(install.path)
-            }
-            Ok(None) => {
-                warnings.push("Firefox installation not found".to_string());
-                None
-            }
-            Err(e) => {
-                warnings.push(format!("Failed to locate Firefox: {}", e));
-                None
-            }
-        }
-    } else {
-        None
-    };
-
-    // Load built-in defaults from omni.ja (lowest precedence)
-    if config.include_builtins {
-        if let Some(ref install) = resolved_install_path {
-            match load_builtin_preferences(install, &mut warnings) {
-                Ok(builtins) => {
-                    for pref in builtins {
-                        pref_map.insert(pref.key.clone(), pref);
-                    }
-                    loaded_sources.push(PrefSource::BuiltIn);
-                }
-                Err(e) => {
-                    let msg = format!("Failed to load built-in preferences: {}", e);
-                    warnings.push(msg.clone());
-                    if !config.continue_on_error {
-                        return Err(Error::OmniJaError(msg));
-                    }
-                }
-            }
-        }
-    }
-
-    // Load global defaults from greprefs.js (medium precedence)
-    if config.include_globals {
-        if let Some(ref install) = resolved_install_path {
-            match load_global_preferences(install, &mut warnings) {
-                Ok(globals) => {
-                    for pref in globals {
-                        pref_map.insert(pref.key.clone(), pref);
-                    }
-                    loaded_sources.push(PrefSource::GlobalDefault);
-                }
-                Err(e) => {
-                    let msg = format!("Failed to load global preferences: {}", e);
-                    warnings.push(msg);
-                    if !config.continue_on_error {
-                        return Err(Error::PrefFileNotFound {
-                            file: "greprefs.js".to_string(),
-                        });
-                    }
-                }
-            }
-        }
-    }
-
-    // Load user preferences from prefs.js (highest precedence)
-    if config.include_user {
-        let prefs_js_path = profile_path.join("prefs.js");
-
-        match load_user_preferences(&prefs_js_path, &mut warnings) {
-            Ok(user_prefs) => {
-                for pref in user_prefs {
-                    pref_map.insert(pref.key.clone(), pref);
-                }
-                loaded_sources.push(PrefSource::User);
-            }
-            Err(e) => {
-                let msg = format!("Failed to load user preferences: {}", e);
-                warnings.push(msg);
-                if !config.continue_on_error {
-                    return Err(e);
-                }
-            }
-        }
-    }
-
-    // Convert HashMap to Vec
-    let mut entries: Vec<PrefEntry> = pref_map.into_values().collect();
-    entries.sort_by(|a, b| a.key.cmp(&b.key));
-
-    Ok(MergedPreferences {
-        entries,
-        install_path: resolved_install_path,
-        profile_path: profile_path.to_path_buf(),
-        loaded_sources,
-        warnings,
-    })
+    PrefMerger::new(config.clone()).merge(profile_path, install_path)
 }
 
 /// Get the effective value for a preference key
 ///
 /// Returns the highest-precedence preference entry matching the given key.
+/// Slices are scanned linearly; a [`PrefSet`] is looked up in O(1).
 ///
 /// # Arguments
 ///
-/// * `prefs` - Slice of preference entries
+/// * `prefs` - Preference entries (slice, vector or [`PrefSet`])
 /// * `key` - Preference key to look up
 ///
 /// # Returns
@@ -220,27 +355,42 @@ pub fn merge_all_preferences(
 ///     println!("Found: {:?}", entry.value);
 /// }
 /// ```
-pub fn get_effective_pref<'a>(prefs: &'a [PrefEntry], key: &str) -> Option<&'a PrefEntry> {
-    prefs.iter().find(|e| e.key == key)
+pub fn get_effective_pref<'a, P>(prefs: &'a P, key: &str) -> Option<&'a PrefEntry>
+where
+    P: PrefLookup + ?Sized,
+{
+    prefs.lookup(key)
+}
+
+/// Find the omni.ja holding the default preferences of an installation
+///
+/// Tries the application subdirectory first, then the installation root.
+fn find_omni_ja(
+    install_path: &Path,
+    app: &Application,
+    warn: bool,
+    warnings: &mut Vec<String>,
+) -> Option<PathBuf> {
+    let found = app
+        .omni_ja_paths(install_path)
+        .into_iter()
+        .find(|p| p.exists());
+    if found.is_none() && warn {
+        warnings.push(format!("omni.ja not found in {} installation", app.name));
+    }
+    found
 }
 
 /// Load built-in preferences from omni.ja
 fn load_builtin_preferences(
-    install_path: &Path,
+    omni_path: &Path,
     warnings: &mut Vec<String>,
 ) -> Result<Vec<PrefEntry>> {
-    // Find omni.ja (try browser/ subdirectory first, then root)
-    let omni_paths = [
-        install_path.join("browser/omni.ja"),
-        install_path.join("omni.ja"),
-    ];
-
-    let omni_path = omni_paths.iter().find(|p| p.exists()).ok_or_else(|| {
-        warnings.push("omni.ja not found in Firefox installation".to_string());
-        Error::PrefFileNotFound {
-            file: "omni.ja".to_string(),
-        }
-    })?;
+    if !omni_path.is_file() {
+        return Err(Error::PrefFileNotFound {
+            file: omni_path.display().to_string(),
+        });
+    }
 
     // Only extract preference files, not all JavaScript files
     let config = ExtractConfig {
@@ -250,7 +400,7 @@ fn load_builtin_preferences(
         ],
         ..Default::default()
     };
-    let extractor = OmniExtractor::with_config(omni_path.clone(), config)?;
+    let extractor = OmniExtractor::with_config(omni_path.to_path_buf(), config)?;
     let extracted_files = extractor.extract_prefs()?;
 
     let mut all_prefs = Vec::new();
@@ -261,7 +411,10 @@ fn load_builtin_preferences(
                 // Update source information for each preference
                 for pref in &mut prefs {
                     pref.source = Some(PrefSource::BuiltIn);
-                    if let Ok(file_name) = file_path.strip_prefix(install_path) {
+                    if let Some(file_name) = omni_path
+                        .parent()
+                        .and_then(|dir| file_path.strip_prefix(dir).ok())
+                    {
                         pref.source_file = Some(format!("omni.ja:{}", file_name.display()));
                     }
                 }
@@ -285,24 +438,31 @@ fn load_builtin_preferences(
 }
 
 /// Load global preferences from greprefs.js
+///
+/// A supplied omni.ja replaces the installation as the only place searched.
 fn load_global_preferences(
-    install_path: &Path,
+    install_path: Option<&Path>,
+    omni_override: Option<&Path>,
     warnings: &mut Vec<String>,
 ) -> Result<Vec<PrefEntry>> {
     // First try to find greprefs.js directly in the filesystem
-    let greprefs_paths = [
-        install_path.join("greprefs.js"),
-        install_path.join("browser/greprefs.js"),
-    ];
+    let greprefs_paths: Vec<PathBuf> = match (omni_override, install_path) {
+        (None, Some(install)) => vec![
+            install.join("greprefs.js"),
+            install.join("browser/greprefs.js"),
+        ],
+        _ => Vec::new(),
+    };
 
     let greprefs_path = if let Some(path) = greprefs_paths.iter().find(|p| p.exists()) {
         path.clone()
     } else {
         // If not found, try to extract from omni.ja
-        let omni_paths = [
-            install_path.join("omni.ja"),
-            install_path.join("browser/omni.ja"),
-        ];
+        let omni_paths: Vec<PathBuf> = match (omni_override, install_path) {
+            (Some(omni), _) => vec![omni.to_path_buf()],
+            (None, Some(install)) => vec![install.join("omni.ja"), install.join("browser/omni.ja")],
+            (None, None) => Vec::new(),
+        };
 
         let omni_path = omni_paths.iter().find(|p| p.exists()).ok_or_else(|| {
             warnings.push("greprefs.js not found and omni.ja not found".to_string());
@@ -441,4 +601,52 @@ mod tests {
         assert!(result.is_err());
         assert!(!warnings.is_empty());
     }
+
+    #[test]
+    fn test_type_mismatch_keeps_default() {
+        let install = TempDir::new().unwrap();
+        let profile = TempDir::new().unwrap();
+        write(
+            install.path().join("greprefs.js"),
+            r#"
+            pref("network.trr.mode", 0);
+            pref("layout.css.devPixelsPerPx", "-1.0");
+            "#,
+        )
+        .unwrap();
+        write(
+            profile.path().join("prefs.js"),
+            r#"
+            user_pref("network.trr.mode", "2");
+            user_pref("layout.css.devPixelsPerPx", 1.25);
+            user_pref("extension.only", true);
+            "#,
+        )
+        .unwrap();
+
+        let config = MergeConfig {
+            include_builtins: false,
+            ..Default::default()
+        };
+        let report = PrefMerger::new(config)
+            .detect_installation(false)
+            .merge_report(profile.path(), Some(install.path()))
+            .unwrap();
+        let merged = &report.merged;
+
+        // Floats are strings to Firefox, so only the quoted number conflicts
+        assert_eq!(report.type_mismatches.len(), 1);
+        assert_eq!(report.type_mismatches[0].key, "network.trr.mode");
+        let trr = merged.lookup("network.trr.mode").unwrap();
+        assert_eq!(trr.value, PrefValue::Integer(0));
+        assert_eq!(trr.source, Some(PrefSource::GlobalDefault));
+        assert!(merged
+            .warnings
+            .iter()
+            .any(|w| w.starts_with("Type mismatch: network.trr.mode")));
+        assert_eq!(
+            merged.lookup("layout.css.devPixelsPerPx").unwrap().value,
+            PrefValue::Float(1.25)
+        );
+    }
 }
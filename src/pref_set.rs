@@ -0,0 +1,478 @@
+//! Indexed preference collection
+//!
+//! Most functions in this crate take `&[PrefEntry]` and scan it linearly.
+//! [`PrefSet`] holds the same entries with a hash index for O(1) key lookup
+//! and a sorted index for prefix iteration, which pays off when many keys are
+//! looked up in one merged profile.
+//!
+//! Inserting a key that is already present overrides it, but the previous
+//! entry is kept as a lower layer, so a set built from built-in defaults,
+//! global defaults and prefs.js (in that order) still knows every value a
+//! preference had before the effective one.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ffcv::{get_effective_pref, parse_prefs_js, PrefSet, PrefValue};
+//!
+//! let defaults = parse_prefs_js(r#"
+//!     pref("network.trr.mode", 0);
+//!     pref("network.proxy.type", 5);
+//! "#)?;
+//! let user = parse_prefs_js(r#"user_pref("network.trr.mode", 3);"#)?;
+//!
+//! let set: PrefSet = defaults.into_iter().chain(user).collect();
+//! assert_eq!(set.len(), 2);
+//! assert_eq!(set.get("network.trr.mode").unwrap().value, PrefValue::Integer(3));
+//! assert_eq!(set.layers("network.trr.mode").len(), 2);
+//!
+//! // Existing lookup functions accept a PrefSet
+//! assert!(get_effective_pref(&set, "network.proxy.type").is_some());
+//!
+//! let network: Vec<_> = set.prefix("network.proxy.").map(|e| &e.key).collect();
+//! assert_eq!(network, ["network.proxy.type"]);
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::error::{Error, Result};
+use crate::json_pref::JsonPrefPath;
+use crate::typed_pref::{FromPrefValue, KnownPref};
+use crate::types::{PrefEntry, PrefSource};
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Key lookup over a preference collection
+///
+/// Implemented for slices and vectors (linear scan, first match) and for
+/// [`PrefSet`] (hash lookup of the effective entry), so lookup functions such
+/// as [`get_effective_pref`](crate::get_effective_pref) accept either. References,
+/// `Box`, `Rc` and `Arc` forward to what they point to, since generic
+/// arguments don't get deref coercion.
+pub trait PrefLookup {
+    /// Find the entry for `key`
+    fn lookup(&self, key: &str) -> Option<&PrefEntry>;
+
+    /// Value of `key` converted to `T`
+    ///
+    /// Fails with [`Error::PrefNotFound`] when the key is absent and
+    /// [`Error::InvalidPrefValue`] when the value cannot be converted.
+    fn get_as<T: FromPrefValue>(&self, key: &str) -> Result<T> {
+        let entry = self
+            .lookup(key)
+            .ok_or_else(|| Error::PrefNotFound(key.to_string()))?;
+        T::from_pref_value(key, &entry.value)
+    }
+
+    /// Value of `key` converted to `T`, or `default` when the key is absent
+    ///
+    /// A value that is present but cannot be converted is still an error.
+    fn get_as_or<T: FromPrefValue>(&self, key: &str, default: T) -> Result<T> {
+        match self.lookup(key) {
+            Some(entry) => T::from_pref_value(key, &entry.value),
+            None => Ok(default),
+        }
+    }
+
+    /// Value of a [`KnownPref`], or Firefox's default when it is not set
+    fn get_known<T: KnownPref>(&self) -> Result<T> {
+        self.get_as_or(T::KEY, T::default())
+    }
+
+    /// Value at `key#/json/pointer` inside a JSON-valued string preference
+    ///
+    /// Without `#`, the whole value is returned, decoded if it holds JSON.
+    /// See [`JsonPrefPath`].
+    fn get_json(&self, path: &str) -> Result<serde_json::Value> {
+        let path = JsonPrefPath::parse(path);
+        let entry = self
+            .lookup(path.key)
+            .ok_or_else(|| Error::PrefNotFound(path.key.to_string()))?;
+        path.resolve(entry)
+    }
+}
+
+/// Whether `entry` is a modified user value
+///
+/// Compares with the defaults in `layers` as [`PrefSet::is_modified`] does;
+/// without layers no default is known and every user value counts.
+pub(crate) fn is_modified_in(entry: &PrefEntry, layers: Option<&PrefSet>) -> bool {
+    match layers {
+        Some(layers) => layers.is_modified(&entry.key),
+        None => entry.source == Some(PrefSource::User),
+    }
+}
+
+impl PrefLookup for [PrefEntry] {
+    fn lookup(&self, key: &str) -> Option<&PrefEntry> {
+        self.iter().find(|e| e.key == key)
+    }
+}
+
+impl<const N: usize> PrefLookup for [PrefEntry; N] {
+    fn lookup(&self, key: &str) -> Option<&PrefEntry> {
+        self.as_slice().lookup(key)
+    }
+}
+
+impl PrefLookup for Vec<PrefEntry> {
+    fn lookup(&self, key: &str) -> Option<&PrefEntry> {
+        self.as_slice().lookup(key)
+    }
+}
+
+impl PrefLookup for PrefSet {
+    fn lookup(&self, key: &str) -> Option<&PrefEntry> {
+        self.get(key)
+    }
+}
+
+impl<T: PrefLookup + ?Sized> PrefLookup for &T {
+    fn lookup(&self, key: &str) -> Option<&PrefEntry> {
+        (**self).lookup(key)
+    }
+}
+
+impl<T: PrefLookup + ?Sized> PrefLookup for Box<T> {
+    fn lookup(&self, key: &str) -> Option<&PrefEntry> {
+        (**self).lookup(key)
+    }
+}
+
+impl<T: PrefLookup + ?Sized> PrefLookup for Rc<T> {
+    fn lookup(&self, key: &str) -> Option<&PrefEntry> {
+        (**self).lookup(key)
+    }
+}
+
+impl<T: PrefLookup + ?Sized> PrefLookup for Arc<T> {
+    fn lookup(&self, key: &str) -> Option<&PrefEntry> {
+        (**self).lookup(key)
+    }
+}
+
+/// Preference collection with O(1) key lookup and source layers
+///
+/// Keys keep the position of their first insertion. Each key holds a stack
+/// of entries, lowest precedence first; the last one is the effective entry
+/// returned by [`get`](PrefSet::get) and iteration.
+#[derive(Debug, Clone, Default)]
+pub struct PrefSet {
+    /// Entry stacks in insertion order; never empty
+    slots: Vec<Vec<PrefEntry>>,
+    /// Key to slot position
+    index: HashMap<String, usize>,
+    /// Key to slot position, sorted for prefix iteration
+    sorted: BTreeMap<String, usize>,
+}
+
+impl PrefSet {
+    /// Create an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert an entry, overriding any entry with the same key
+    ///
+    /// The overridden entry is kept as a lower layer.
+    pub fn insert(&mut self, entry: PrefEntry) {
+        match self.index.get(&entry.key) {
+            Some(&slot) => self.slots[slot].push(entry),
+            None => {
+                let slot = self.slots.len();
+                self.index.insert(entry.key.clone(), slot);
+                self.sorted.insert(entry.key.clone(), slot);
+                self.slots.push(vec![entry]);
+            }
+        }
+    }
+
+    /// Effective entry for `key`
+    pub fn get(&self, key: &str) -> Option<&PrefEntry> {
+        self.index
+            .get(key)
+            .and_then(|&slot| self.slots[slot].last())
+    }
+
+    /// Whether the set holds `key`
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// All entries inserted for `key`, lowest precedence first
+    ///
+    /// The last entry is the effective one. Empty when the key is absent.
+    pub fn layers(&self, key: &str) -> &[PrefEntry] {
+        self.index
+            .get(key)
+            .map(|&slot| self.slots[slot].as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Highest layer of `key` that doesn't come from prefs.js
+    ///
+    /// This is the value Firefox falls back to when the user value is reset.
+    pub fn default_entry(&self, key: &str) -> Option<&PrefEntry> {
+        self.layers(key)
+            .iter()
+            .rev()
+            .find(|entry| entry.source != Some(PrefSource::User))
+    }
+
+    /// Whether `key` has a user value that differs from its default
+    ///
+    /// A user value equal to the default changes nothing and doesn't count;
+    /// a user value without any default does.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ffcv::{PrefEntry, PrefSet, PrefSource, PrefType, PrefValue};
+    ///
+    /// let entry = |value: i64, source| PrefEntry {
+    ///     key: "network.trr.mode".to_string(),
+    ///     value: PrefValue::Integer(value),
+    ///     pref_type: PrefType::User,
+    ///     explanation: None,
+    ///     source: Some(source),
+    ///     source_file: None,
+    ///     locked: None,
+    /// };
+    ///
+    /// let mut set = PrefSet::new();
+    /// set.insert(entry(0, PrefSource::BuiltIn));
+    /// set.insert(entry(0, PrefSource::User));
+    /// assert!(!set.is_modified("network.trr.mode"));
+    ///
+    /// set.insert(entry(3, PrefSource::User));
+    /// assert!(set.is_modified("network.trr.mode"));
+    /// ```
+    pub fn is_modified(&self, key: &str) -> bool {
+        self.get(key).is_some_and(|entry| {
+            entry.source == Some(PrefSource::User)
+                && self
+                    .default_entry(key)
+                    .is_none_or(|default| default.value != entry.value)
+        })
+    }
+
+    /// Number of distinct keys
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Whether the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Effective entries in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = &PrefEntry> + '_ {
+        self.slots.iter().filter_map(|layers| layers.last())
+    }
+
+    /// Effective entries sorted by key
+    pub fn iter_sorted(&self) -> impl Iterator<Item = &PrefEntry> + '_ {
+        self.sorted
+            .values()
+            .filter_map(|&slot| self.slots[slot].last())
+    }
+
+    /// Effective entries whose key starts with `prefix`, sorted by key
+    ///
+    /// `prefix` is matched literally: `"network.proxy"` also matches
+    /// `network.proxyDNS`; use `"network.proxy."` for the namespace only.
+    pub fn prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a PrefEntry> + 'a {
+        self.sorted
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(move |(key, _)| key.starts_with(prefix))
+            .filter_map(|(_, &slot)| self.slots[slot].last())
+    }
+
+    /// Effective entries as a vector sorted by key
+    pub fn to_sorted_vec(&self) -> Vec<PrefEntry> {
+        self.iter_sorted().cloned().collect()
+    }
+
+    /// Entries of both sets; `other` takes precedence
+    ///
+    /// Equivalent to inserting every layer of `other` after `self`, so keys
+    /// present in both keep the layers of each.
+    pub fn union(&self, other: &PrefSet) -> PrefSet {
+        let mut result = self.clone();
+        for layers in &other.slots {
+            for entry in layers {
+                result.insert(entry.clone());
+            }
+        }
+        result
+    }
+
+    /// Entries of `self` whose key is also in `other`
+    pub fn intersection(&self, other: &PrefSet) -> PrefSet {
+        self.filter_slots(|key| other.contains_key(key))
+    }
+
+    /// Entries of `self` whose key is not in `other`
+    pub fn difference(&self, other: &PrefSet) -> PrefSet {
+        self.filter_slots(|key| !other.contains_key(key))
+    }
+
+    /// Copy the slots (with all their layers) whose key passes `keep`
+    fn filter_slots(&self, keep: impl Fn(&str) -> bool) -> PrefSet {
+        let mut result = PrefSet::new();
+        for layers in &self.slots {
+            if keep(&layers[0].key) {
+                for entry in layers {
+                    result.insert(entry.clone());
+                }
+            }
+        }
+        result
+    }
+}
+
+impl FromIterator<PrefEntry> for PrefSet {
+    fn from_iter<I: IntoIterator<Item = PrefEntry>>(iter: I) -> Self {
+        let mut set = PrefSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<PrefEntry> for PrefSet {
+    fn extend<I: IntoIterator<Item = PrefEntry>>(&mut self, iter: I) {
+        for entry in iter {
+            self.insert(entry);
+        }
+    }
+}
+
+impl From<Vec<PrefEntry>> for PrefSet {
+    fn from(entries: Vec<PrefEntry>) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl IntoIterator for PrefSet {
+    type Item = PrefEntry;
+    type IntoIter = std::vec::IntoIter<PrefEntry>;
+
+    /// Effective entries in insertion order
+    fn into_iter(self) -> Self::IntoIter {
+        self.slots
+            .into_iter()
+            .filter_map(|mut layers| layers.pop())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a PrefSet {
+    type Item = &'a PrefEntry;
+    type IntoIter = Box<dyn Iterator<Item = &'a PrefEntry> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+/// Serializes the effective entries in insertion order
+impl Serialize for PrefSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_prefs_js;
+    use crate::types::PrefValue;
+
+    fn set(content: &str) -> PrefSet {
+        parse_prefs_js(content).unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn test_insert_keeps_order_and_layers() {
+        let mut prefs = set(r#"
+            pref("b.second", 1);
+            pref("a.first", 1);
+            "#);
+        prefs.extend(parse_prefs_js(r#"user_pref("b.second", 2);"#).unwrap());
+
+        assert_eq!(prefs.len(), 2);
+        let keys: Vec<_> = prefs.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["b.second", "a.first"]);
+        let sorted: Vec<_> = prefs.iter_sorted().map(|e| e.key.as_str()).collect();
+        assert_eq!(sorted, ["a.first", "b.second"]);
+
+        assert_eq!(prefs.get("b.second").unwrap().value, PrefValue::Integer(2));
+        let layers: Vec<_> = prefs.layers("b.second").iter().map(|e| &e.value).collect();
+        assert_eq!(layers, [&PrefValue::Integer(1), &PrefValue::Integer(2)]);
+        assert!(prefs.layers("missing").is_empty());
+
+        let owned: Vec<PrefEntry> = prefs.into_iter().collect();
+        assert_eq!(owned[0].value, PrefValue::Integer(2));
+    }
+
+    #[test]
+    fn test_prefix_iteration() {
+        let prefs = set(r#"
+            pref("network.proxy.type", 1);
+            pref("network.proxyDNS", true);
+            pref("network.trr.mode", 2);
+            pref("networking", 0);
+            "#);
+        let keys: Vec<_> = prefs.prefix("network.").map(|e| e.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            ["network.proxy.type", "network.proxyDNS", "network.trr.mode"]
+        );
+        assert_eq!(prefs.prefix("network.proxy.").count(), 1);
+        assert_eq!(prefs.prefix("zzz").count(), 0);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let left = set(r#"
+            pref("a", 1);
+            pref("b", 1);
+            "#);
+        let right = set(r#"
+            pref("b", 2);
+            pref("c", 2);
+            "#);
+
+        let union = left.union(&right);
+        assert_eq!(union.len(), 3);
+        assert_eq!(union.get("b").unwrap().value, PrefValue::Integer(2));
+        assert_eq!(union.layers("b").len(), 2);
+
+        let both = left.intersection(&right);
+        assert_eq!(both.len(), 1);
+        assert_eq!(both.get("b").unwrap().value, PrefValue::Integer(1));
+
+        let only_left = left.difference(&right);
+        assert!(only_left.contains_key("a"));
+        assert!(!only_left.contains_key("b"));
+    }
+
+    #[test]
+    fn test_lookup_through_pointers() {
+        use crate::get_effective_pref;
+
+        let prefs = parse_prefs_js(r#"pref("a", 1);"#).unwrap();
+        let boxed: Box<[PrefEntry]> = prefs.clone().into_boxed_slice();
+        let shared = Arc::new(set(r#"pref("a", 2);"#));
+
+        assert!(get_effective_pref(&&prefs, "a").is_some());
+        assert!(get_effective_pref(&boxed, "a").is_some());
+        assert!(get_effective_pref(&Rc::new(prefs), "a").is_some());
+        assert_eq!(shared.get_as::<i64>("a").unwrap(), 2);
+        assert!(PrefEntry::find_by_key(&shared, "a").is_some());
+    }
+}
@@ -0,0 +1,315 @@
+//! Preference namespace tree
+//!
+//! Preference keys are dot-separated namespaces (`browser.newtabpage.enabled`).
+//! [`PrefTree`] groups a flat list of entries by namespace and counts the
+//! preferences and modified preferences below every branch, which shows at a
+//! glance where a profile diverges from the defaults.
+//!
+//! A preference is modified when its user value differs from the default, as
+//! with the `modified` flag of [`PrefQuery`](crate::PrefQuery). Use
+//! [`PrefTree::build_in`] with the merged layers to compare with the
+//! defaults; [`PrefTree::build`] has none and counts every user value.
+//!
+//! A key can be both a preference and a namespace (`a.b` next to `a.b.c`);
+//! such nodes carry a value and children.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ffcv::{parse_prefs_js, PrefTree};
+//!
+//! let prefs = parse_prefs_js(r#"
+//!     user_pref("browser.newtabpage.enabled", false);
+//!     user_pref("browser.newtabpage.activity-stream.feeds.topsites", false);
+//!     user_pref("network.trr.mode", 3);
+//! "#)?;
+//!
+//! let tree = PrefTree::build(&prefs);
+//! assert_eq!(tree.count, 3);
+//!
+//! let newtab = tree.find("browser.newtabpage").unwrap();
+//! assert_eq!(newtab.count, 2);
+//! assert_eq!(newtab.modified, 2);
+//!
+//! print!("{}", newtab.render(Some(1)));
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::pref_set::{is_modified_in, PrefSet};
+use crate::types::{PrefEntry, PrefValue};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A namespace node with its preference counts
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PrefTree {
+    /// Last key segment (empty for the root)
+    pub name: String,
+    /// Full dotted path of this node (empty for the root)
+    pub path: String,
+    /// Number of preferences at or below this node
+    pub count: usize,
+    /// Number of modified preferences at or below this node
+    pub modified: usize,
+    /// Value when this node is itself a preference
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<PrefValue>,
+    /// Child namespaces, sorted by name
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<PrefTree>,
+}
+
+/// Builder node keeping children in a sorted map
+#[derive(Default)]
+struct Branch {
+    count: usize,
+    modified: usize,
+    value: Option<PrefValue>,
+    children: BTreeMap<String, Branch>,
+}
+
+impl PrefTree {
+    /// Build the namespace tree of the given preferences
+    ///
+    /// No defaults are known, so every user value counts as modified.
+    pub fn build(preferences: &[PrefEntry]) -> Self {
+        Self::build_from(preferences, None)
+    }
+
+    /// Build the namespace tree of preferences taken from `layers`
+    ///
+    /// A preference counts as modified when [`PrefSet::is_modified`] holds,
+    /// so user values equal to their default don't.
+    pub fn build_in(preferences: &[PrefEntry], layers: &PrefSet) -> Self {
+        Self::build_from(preferences, Some(layers))
+    }
+
+    fn build_from(preferences: &[PrefEntry], layers: Option<&PrefSet>) -> Self {
+        let mut root = Branch::default();
+
+        for entry in preferences {
+            let modified = usize::from(is_modified_in(entry, layers));
+            let mut node = &mut root;
+            node.count += 1;
+            node.modified += modified;
+            for segment in entry.key.split('.') {
+                node = node.children.entry(segment.to_string()).or_default();
+                node.count += 1;
+                node.modified += modified;
+            }
+            node.value = Some(entry.value.clone());
+        }
+
+        Self::from_branch(String::new(), String::new(), root)
+    }
+
+    fn from_branch(name: String, path: String, branch: Branch) -> Self {
+        let children = branch
+            .children
+            .into_iter()
+            .map(|(child_name, child)| {
+                let child_path = if path.is_empty() {
+                    child_name.clone()
+                } else {
+                    format!("{}.{}", path, child_name)
+                };
+                Self::from_branch(child_name, child_path, child)
+            })
+            .collect();
+
+        Self {
+            name,
+            path,
+            count: branch.count,
+            modified: branch.modified,
+            value: branch.value,
+            children,
+        }
+    }
+
+    /// Find the node for a namespace prefix
+    ///
+    /// A trailing `.` or `.*` is ignored, so `browser.newtabpage.*` finds
+    /// the same node as `browser.newtabpage`. An empty prefix is the root.
+    pub fn find(&self, prefix: &str) -> Option<&PrefTree> {
+        let prefix = prefix.trim_end_matches('*').trim_end_matches('.');
+        if prefix.is_empty() {
+            return Some(self);
+        }
+
+        prefix.split('.').try_fold(self, |node, segment| {
+            node.children.iter().find(|child| child.name == segment)
+        })
+    }
+
+    /// Copy of this tree expanded to at most `depth` levels below this node
+    ///
+    /// Collapsed branches keep their counts but drop their children.
+    pub fn truncated(&self, depth: usize) -> PrefTree {
+        PrefTree {
+            children: if depth == 0 {
+                Vec::new()
+            } else {
+                self.children
+                    .iter()
+                    .map(|child| child.truncated(depth - 1))
+                    .collect()
+            },
+            ..self.clone()
+        }
+    }
+
+    /// Render the tree as indented text, expanding at most `depth` levels
+    ///
+    /// # Example output
+    ///
+    /// ```text
+    /// browser.newtabpage (2 prefs, 1 modified)
+    /// ├── activity-stream (1 pref)
+    /// └── enabled = false (modified)
+    /// ```
+    pub fn render(&self, depth: Option<usize>) -> String {
+        let mut out = String::new();
+        let label = if self.path.is_empty() {
+            "(all)"
+        } else {
+            &self.path
+        };
+        out.push_str(&self.label(label));
+        out.push('\n');
+        self.render_children(&mut out, "", depth);
+        out
+    }
+
+    fn render_children(&self, out: &mut String, indent: &str, depth: Option<usize>) {
+        if depth == Some(0) {
+            return;
+        }
+        let depth = depth.map(|d| d - 1);
+
+        for (i, child) in self.children.iter().enumerate() {
+            let last = i + 1 == self.children.len();
+            out.push_str(indent);
+            out.push_str(if last { "└── " } else { "├── " });
+            out.push_str(&child.label(&child.name));
+            out.push('\n');
+
+            let child_indent = format!("{}{}", indent, if last { "    " } else { "│   " });
+            child.render_children(out, &child_indent, depth);
+        }
+    }
+
+    /// One line describing this node
+    fn label(&self, name: &str) -> String {
+        let modified = if self.modified > 0 {
+            format!(", {} modified", self.modified)
+        } else {
+            String::new()
+        };
+
+        match &self.value {
+            // A plain preference
+            Some(value) if self.children.is_empty() => {
+                let flag = if self.modified > 0 { " (modified)" } else { "" };
+                format!("{} = {}{}", name, value, flag)
+            }
+            Some(value) => format!("{} = {} ({} prefs{})", name, value, self.count, modified),
+            None => format!(
+                "{} ({} pref{}{})",
+                name,
+                self.count,
+                if self.count == 1 { "" } else { "s" },
+                modified
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_prefs_js;
+    use crate::types::PrefSource;
+
+    fn sample_layers() -> PrefSet {
+        let defaults = parse_prefs_js(
+            r#"
+            pref("browser.newtabpage.activity-stream.feeds.topsites", false);
+            pref("browser.startup.page", 1);
+            pref("browser.startup.page.extra", 1);
+            "#,
+        )
+        .unwrap();
+        let user = parse_prefs_js(
+            r#"
+            user_pref("browser.newtabpage.enabled", false);
+            user_pref("browser.newtabpage.activity-stream.feeds.topsites", false);
+            user_pref("browser.startup.page", 3);
+            "#,
+        )
+        .unwrap();
+        defaults
+            .into_iter()
+            .map(|mut entry| {
+                entry.source = Some(PrefSource::BuiltIn);
+                entry
+            })
+            .chain(user)
+            .collect()
+    }
+
+    fn sample_tree() -> PrefTree {
+        let layers = sample_layers();
+        PrefTree::build_in(&layers.to_sorted_vec(), &layers)
+    }
+
+    #[test]
+    fn test_build_counts_branches_and_modified() {
+        let tree = sample_tree();
+        assert_eq!(tree.count, 4);
+        // The topsites user value equals its default
+        assert_eq!(tree.modified, 2);
+
+        let browser = tree.find("browser").unwrap();
+        assert_eq!(
+            browser
+                .children
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["newtabpage", "startup"]
+        );
+
+        // A key that is also a namespace keeps its value and children
+        let page = tree.find("browser.startup.page").unwrap();
+        assert_eq!(page.value, Some(PrefValue::Integer(3)));
+        assert_eq!(page.count, 2);
+        assert_eq!(page.modified, 1);
+        assert_eq!(page.children[0].path, "browser.startup.page.extra");
+
+        assert!(tree.find("browser.newtabpage.*").is_some());
+        assert!(tree.find("browser.missing").is_none());
+
+        // Without layers every user value counts
+        let layers = sample_layers();
+        assert_eq!(PrefTree::build(&layers.to_sorted_vec()).modified, 3);
+    }
+
+    #[test]
+    fn test_truncate_and_render() {
+        let tree = sample_tree();
+        let newtab = tree.find("browser.newtabpage").unwrap();
+
+        let truncated = newtab.truncated(1);
+        assert_eq!(truncated.children.len(), 2);
+        assert!(truncated.children[0].children.is_empty());
+        assert_eq!(truncated.children[0].count, 1);
+
+        assert_eq!(
+            newtab.render(Some(1)),
+            "browser.newtabpage (2 prefs, 1 modified)\n\
+             ├── activity-stream (1 pref)\n\
+             └── enabled = false (modified)\n"
+        );
+    }
+}
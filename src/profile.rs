@@ -21,9 +21,20 @@
 //! # Ok::<(), ffcv::Error>(())
 //! ```
 
+use crate::application::Application;
 use crate::error::{Error, Result};
+use crate::firefox_locator::find_all_installations_for_app;
+use crate::install_section::{
+    compute_install_hash, match_installations, read_install_sections, InstallSection,
+};
+use crate::profile_groups::{list_profile_groups, GroupProfile, ProfileGroup, STORE_ID_PREF};
+use crate::profile_install::{link_profile_installation, InstallLinkSource, ProfileInstallation};
+use crate::profile_metadata::{
+    collect_profile_stats, read_profile_compatibility, read_profile_times, ProfileCompatibility,
+    ProfileStats, ProfileTimes,
+};
+use crate::types::Installation;
 use serde::Serialize;
-use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 /// Firefox profile information parsed from profiles.ini
@@ -33,16 +44,156 @@ struct FirefoxProfile {
     path: PathBuf,
     is_relative: bool,
     is_default: bool,
+    store_id: Option<String>,
+}
+
+/// What profile lookups may consult besides the profiles directory
+///
+/// On the host, installations are detected to pick the default profile and
+/// link profiles to their installation. An offline scope only knows the
+/// installations it was given, and maps absolute paths from profiles.ini into
+/// its root so nothing outside the root is read.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ProfileScope<'a> {
+    /// The machine ffcv runs on
+    Host,
+    /// A directory tree or archive copied from another machine
+    Offline {
+        root: &'a Path,
+        installations: &'a [Installation],
+    },
+}
+
+impl ProfileScope<'_> {
+    /// Installations that may own profiles in this scope
+    fn installations(&self, app: &Application) -> Result<Vec<Installation>> {
+        match self {
+            ProfileScope::Host => find_all_installations_for_app(app),
+            ProfileScope::Offline { installations, .. } => Ok(installations.to_vec()),
+        }
+    }
+
+    /// Resolve a profile path from profiles.ini
+    fn resolve(&self, profiles_dir: &Path, path: &Path, is_relative: bool) -> PathBuf {
+        match self {
+            _ if is_relative => profiles_dir.join(path),
+            ProfileScope::Host => path.to_path_buf(),
+            ProfileScope::Offline { root, .. } => {
+                crate::offline::rebase_foreign_path(root, profiles_dir, path)
+            }
+        }
+    }
+
+    /// Link a profile to the installation that owns it
+    ///
+    /// Paths recorded by the profile refer to another machine offline, so
+    /// profiles are linked to the supplied installation instead.
+    fn link(
+        &self,
+        app: &Application,
+        profile_path: &Path,
+        profiles_dir: &Path,
+        sections: &[InstallSection],
+        installations: &[Installation],
+    ) -> Option<ProfileInstallation> {
+        match self {
+            ProfileScope::Host => link_profile_installation(
+                app,
+                profile_path,
+                Some(profiles_dir),
+                sections,
+                installations,
+            ),
+            ProfileScope::Offline { installations, .. } => {
+                installations
+                    .first()
+                    .map(|installation| ProfileInstallation {
+                        source: InstallLinkSource::Supplied,
+                        installation: installation.clone(),
+                    })
+            }
+        }
+    }
 }
 
 /// Public profile information for listing
+///
+/// More profile details may be added in minor releases, so values are only
+/// obtained from the listing functions.
 #[derive(Debug, Serialize)]
+#[non_exhaustive]
 pub struct ProfileInfo {
     pub name: String,
     pub path: PathBuf,
     pub is_default: bool,
     pub is_relative: bool,
     pub locked_to_install: Option<String>,
+    /// Profile group store this profile belongs to (`StoreID` in profiles.ini)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store_id: Option<String>,
+    /// Name, avatar and theme from the profile group store
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<GroupProfile>,
+    /// Timestamps from times.json (only with [`ProfileListConfig::include_times`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub times: Option<ProfileTimes>,
+    /// Last-used build from compatibility.ini (only with [`ProfileListConfig::include_compatibility`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compatibility: Option<ProfileCompatibility>,
+    /// Size and prefs.js statistics (only with [`ProfileListConfig::include_stats`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ProfileStats>,
+    /// Installation whose `[Install…]` section refers to this profile
+    /// (only with [`ProfileListConfig::include_install`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub install_path: Option<PathBuf>,
+    /// Installation that owns this profile, from compatibility.ini or the
+    /// install sections (only with [`ProfileListConfig::include_install`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub installation: Option<ProfileInstallation>,
+}
+
+/// Configuration for listing profiles
+///
+/// Controls which optional metadata is collected for each profile. All
+/// options are disabled by default so that plain listing only reads
+/// profiles.ini.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ffcv::{list_profiles_with_config, ProfileListConfig};
+///
+/// let profiles = list_profiles_with_config(None, &ProfileListConfig::all())?;
+/// for profile in profiles {
+///     if let Some(stats) = &profile.stats {
+///         println!("{}: {} bytes", profile.name, stats.size_bytes);
+///     }
+/// }
+/// # Ok::<(), ffcv::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ProfileListConfig {
+    /// Read creation and first-use timestamps from times.json
+    pub include_times: bool,
+    /// Read the last Firefox version and directories from compatibility.ini
+    pub include_compatibility: bool,
+    /// Collect prefs.js modification time, user pref count and on-disk size
+    pub include_stats: bool,
+    /// Detect installations and link each profile to the one that owns it
+    pub include_install: bool,
+}
+
+impl ProfileListConfig {
+    /// Configuration that collects all available metadata
+    pub fn all() -> Self {
+        Self {
+            include_times: true,
+            include_compatibility: true,
+            include_stats: true,
+            include_install: true,
+        }
+    }
 }
 
 /// Find the Firefox profile directory based on the profile name
@@ -68,19 +219,48 @@ pub fn find_profile_path(
     profile_name: &str,
     profiles_dir_opt: Option<&std::path::Path>,
 ) -> Result<PathBuf> {
-    let profiles_dir = get_profiles_directory(profiles_dir_opt)?;
-    let profiles_ini = profiles_dir.join("profiles.ini");
+    find_profile_path_for_app(&Application::FIREFOX, profile_name, profiles_dir_opt)
+}
+
+/// Find a profile directory of the given application based on the profile name
+///
+/// Works like [`find_profile_path`], but uses the application's profile root
+/// and ini file names.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ffcv::{find_profile_path_for_app, Application};
+///
+/// let profile_path = find_profile_path_for_app(&Application::THUNDERBIRD, "default", None)?;
+/// println!("Thunderbird profile: {}", profile_path.display());
+/// # Ok::<(), ffcv::Error>(())
+/// ```
+pub fn find_profile_path_for_app(
+    app: &Application,
+    profile_name: &str,
+    profiles_dir_opt: Option<&std::path::Path>,
+) -> Result<PathBuf> {
+    let profiles_dir = get_profiles_directory_for_app(app, profiles_dir_opt)?;
+    find_profile_path_in(app, profile_name, &profiles_dir, ProfileScope::Host)
+}
+
+/// Find a profile by name within a resolved profiles directory
+pub(crate) fn find_profile_path_in(
+    app: &Application,
+    profile_name: &str,
+    profiles_dir: &Path,
+    scope: ProfileScope,
+) -> Result<PathBuf> {
+    let profiles_dir = profiles_dir.to_path_buf();
+    let profiles_ini = profiles_dir.join(app.profiles_ini);
 
     // Try parsing profiles.ini first (primary method)
     if profiles_ini.exists() {
         if let Ok(profiles) = parse_profiles_ini(&profiles_ini) {
             // First try exact name match
             if let Some(profile) = profiles.iter().find(|p| p.name == profile_name) {
-                let full_path = if profile.is_relative {
-                    profiles_dir.join(&profile.path)
-                } else {
-                    profile.path.clone()
-                };
+                let full_path = scope.resolve(&profiles_dir, &profile.path, profile.is_relative);
 
                 if full_path.exists() {
                     return Ok(full_path);
@@ -90,21 +270,16 @@ pub fn find_profile_path(
             // If no exact match and profile_name is "default", try to find the default
             // profile for the current Firefox installation (Firefox 67+)
             if profile_name == "default" {
-                if let Ok(default_profile) = get_default_profile_for_install(
-                    &profiles_ini,
-                    profiles_dir.as_path(),
-                    &profiles,
-                ) {
+                if let Ok(default_profile) =
+                    get_default_profile_for_install(app, profiles_dir.as_path(), &profiles, scope)
+                {
                     return Ok(default_profile);
                 }
 
                 // Fallback: use the profile marked as default in profiles.ini
                 if let Some(profile) = profiles.iter().find(|p| p.is_default) {
-                    let full_path = if profile.is_relative {
-                        profiles_dir.join(&profile.path)
-                    } else {
-                        profile.path.clone()
-                    };
+                    let full_path =
+                        scope.resolve(&profiles_dir, &profile.path, profile.is_relative);
 
                     if full_path.exists() {
                         return Ok(full_path);
@@ -162,6 +337,7 @@ fn parse_profiles_ini(ini_path: &PathBuf) -> Result<Vec<FirefoxProfile>> {
                 .flatten()
                 .unwrap_or(0)
                 == 1;
+            let store_id = ini.get(&sec_name, "StoreID").filter(|id| !id.is_empty());
 
             if !name.is_empty() && !path_str.is_empty() {
                 profiles.push(FirefoxProfile {
@@ -169,6 +345,7 @@ fn parse_profiles_ini(ini_path: &PathBuf) -> Result<Vec<FirefoxProfile>> {
                     path: PathBuf::from(path_str),
                     is_relative,
                     is_default,
+                    store_id,
                 });
             }
         }
@@ -177,226 +354,84 @@ fn parse_profiles_ini(ini_path: &PathBuf) -> Result<Vec<FirefoxProfile>> {
     Ok(profiles)
 }
 
-/// Parse install sections from profiles.ini (Firefox 67+)
-/// Returns HashMap<install_hash, default_profile_path>
-fn parse_installs_ini(ini_path: &PathBuf) -> Result<HashMap<String, String>> {
-    use configparser::ini::Ini;
-
-    let mut ini = Ini::new();
-    let content = std::fs::read_to_string(ini_path)?;
-
-    if let Err(e) = ini.read(content) {
-        return Err(Error::ProfilesIniParse(format!(
-            "Failed to parse profiles.ini: {}",
-            e
-        )));
-    }
-
-    let mut installs = HashMap::new();
-
-    // Get all section names
-    let sections = ini.sections();
-
-    for sec_name in sections {
-        // Install sections are named with hash (e.g., [308046B0AF4A39CB])
-        // They don't start with "profile" or "general"
-        // Note: configparser converts section names to lowercase
-        let sec_lower = sec_name.to_lowercase();
-        if !sec_lower.starts_with("profile") && sec_lower != "general" {
-            if let Some(default_profile) = ini.get(&sec_name, "Default") {
-                installs.insert(sec_name, default_profile);
-            }
-        }
-    }
-
-    Ok(installs)
-}
-
 /// Get the default profile for the current Firefox installation (Firefox 67+)
+///
+/// The current installation is the one whose binary is named by the
+/// `FIREFOX_BIN` environment variable (host scope only), or otherwise the
+/// first installation in scope that has an install section in profiles.ini.
 fn get_default_profile_for_install(
-    ini_path: &PathBuf,
+    app: &Application,
     profiles_dir: &Path,
     profiles: &[FirefoxProfile],
+    scope: ProfileScope,
 ) -> Result<PathBuf> {
-    use configparser::ini::Ini;
-
-    // Try to find the Firefox installation path
-    let firefox_path = get_firefox_install_path()?;
-
-    // Hash the installation path to get the install section name
-    // Firefox uses a simple hash of the installation path
-    let install_hash = hash_install_path(&firefox_path);
-
-    // Parse profiles.ini to get the install mapping
-    let mut ini = Ini::new();
-    let content = std::fs::read_to_string(ini_path)?;
-
-    if let Err(e) = ini.read(content) {
-        return Err(Error::ProfilesIniParse(format!(
-            "Failed to parse profiles.ini: {}",
-            e
-        )));
-    }
+    let mut sections = read_install_sections(profiles_dir, app)?;
+    let firefox_bin = match scope {
+        ProfileScope::Host => std::env::var("FIREFOX_BIN").ok(),
+        ProfileScope::Offline { .. } => None,
+    };
+
+    let section = if let Some(firefox_bin) = firefox_bin {
+        // Firefox hashes the directory containing its binary
+        let binary = std::fs::canonicalize(&firefox_bin).unwrap_or_else(|_| firefox_bin.into());
+        let install_dir = binary.parent().unwrap_or(&binary);
+        let hash = compute_install_hash(install_dir);
+        sections.into_iter().find(|s| s.hash == hash)
+    } else {
+        let installations = scope.installations(app)?;
+        match_installations(&mut sections, &installations);
+        installations.iter().find_map(|install| {
+            sections
+                .iter()
+                .find(|s| {
+                    s.installation
+                        .as_ref()
+                        .map(|i| i.path == install.path)
+                        .unwrap_or(false)
+                })
+                .cloned()
+        })
+    };
 
-    // Look for the install section with the matching hash
-    let sections = ini.sections();
-    for sec_name in sections {
-        let sec_lower = sec_name.to_lowercase();
-        // Check if this is an install section and if the hash matches
-        if !sec_lower.starts_with("profile")
-            && sec_lower != "general"
-            && sec_lower == install_hash.to_lowercase()
+    if let Some(default_profile_path) = section.and_then(|s| s.default) {
+        if let Some(full_path) =
+            resolve_install_default(profiles_dir, profiles, &default_profile_path, scope)
         {
-            // Found the install section! Get the default profile
-            if let Some(default_profile_path) = ini.get(&sec_name, "Default") {
-                // Find the profile with this path
-                if let Some(profile) = profiles.iter().find(|p| {
-                    p.path.to_string_lossy() == default_profile_path
-                        || p.path.to_string_lossy() == format!("Profiles/{}", default_profile_path)
-                }) {
-                    let full_path = if profile.is_relative {
-                        profiles_dir.join(&profile.path)
-                    } else {
-                        profile.path.clone()
-                    };
-
-                    if full_path.exists() {
-                        return Ok(full_path);
-                    }
-                }
-
-                // If profile not found in list, try to construct path directly
-                let profile_path = PathBuf::from(&default_profile_path);
-                let full_path = if profile_path.is_absolute() {
-                    profile_path
-                } else {
-                    profiles_dir.join(&profile_path)
-                };
-
-                if full_path.exists() {
-                    return Ok(full_path);
-                }
-            }
+            return Ok(full_path);
         }
     }
 
     // No install section found for this installation
     Err(Error::ProfileNotFound {
         name: "default".to_string(),
-        directory: firefox_path,
+        directory: profiles_dir.to_path_buf(),
     })
 }
 
-/// Get the path to the Firefox executable
-fn get_firefox_install_path() -> Result<PathBuf> {
-    use std::env;
-
-    // Try to find Firefox in PATH
-    if let Ok(path) = env::var("FIREFOX_BIN") {
-        return Ok(PathBuf::from(path));
-    }
-
-    // Try common locations
-    #[cfg(target_os = "linux")]
-    {
-        let common_paths = [
-            "/usr/bin/firefox",
-            "/usr/lib/firefox/firefox",
-            "/snap/bin/firefox",
-            "/opt/firefox/firefox",
-        ];
-
-        for path in &common_paths {
-            if PathBuf::from(path).exists() {
-                return Ok(PathBuf::from(path));
-            }
-        }
-    }
-
-    #[cfg(target_os = "macos")]
-    {
-        let common_paths = [
-            "/Applications/Firefox.app/Contents/MacOS/firefox",
-            "/Applications/Firefox Developer Edition.app/Contents/MacOS/firefox",
-        ];
-
-        for path in &common_paths {
-            if PathBuf::from(path).exists() {
-                return Ok(PathBuf::from(path));
-            }
-        }
-    }
-
-    #[cfg(target_os = "windows")]
-    {
-        if let Ok(program_files) = env::var("PROGRAMFILES") {
-            let common_paths = [
-                format!("{}/Mozilla Firefox/firefox.exe", program_files),
-                format!("{}\\Mozilla Firefox\\firefox.exe", program_files),
-            ];
-
-            for path in &common_paths {
-                if PathBuf::from(path).exists() {
-                    return Ok(PathBuf::from(path));
-                }
-            }
-        }
-    }
-
-    // Fallback: try to use 'which' or 'where' command
-    #[cfg(target_os = "linux")]
-    {
-        if let Ok(output) = std::process::Command::new("which").arg("firefox").output() {
-            if output.status.success() {
-                let path_str = String::from_utf8_lossy(&output.stdout);
-                let path = path_str.trim();
-                if !path.is_empty() {
-                    return Ok(PathBuf::from(path));
-                }
-            }
-        }
-    }
-
-    #[cfg(target_os = "windows")]
-    {
-        if let Ok(output) = std::process::Command::new("where")
-            .arg("firefox.exe")
-            .output()
-        {
-            if output.status.success() {
-                let path_str = String::from_utf8_lossy(&output.stdout);
-                let path = path_str.lines().next().unwrap_or("").trim();
-                if !path.is_empty() {
-                    return Ok(PathBuf::from(path));
-                }
-            }
+/// Resolve the `Default` value of an install section to a profile directory
+fn resolve_install_default(
+    profiles_dir: &Path,
+    profiles: &[FirefoxProfile],
+    default_profile_path: &str,
+    scope: ProfileScope,
+) -> Option<PathBuf> {
+    // Find the profile with this path
+    if let Some(profile) = profiles.iter().find(|p| {
+        p.path.to_string_lossy() == default_profile_path
+            || p.path.to_string_lossy() == format!("Profiles/{}", default_profile_path)
+    }) {
+        let full_path = scope.resolve(profiles_dir, &profile.path, profile.is_relative);
+
+        if full_path.exists() {
+            return Some(full_path);
         }
     }
 
-    Err(Error::ProfileNotFound {
-        name: "Firefox".to_string(),
-        directory: PathBuf::from("unknown"),
-    })
-}
-
-/// Hash the installation path to get the install section name
-/// Firefox uses CityHash64 to hash the installation directory path
-fn hash_install_path(path: &Path) -> String {
-    use cityhasher::hash;
-
-    // Resolve symlinks first (critical for NixOS and similar systems)
-    let resolved = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
-
-    // Get parent directory (installation directory)
-    // Firefox hashes the installation directory, not the binary path
-    let install_dir = resolved.parent().unwrap_or(&resolved);
+    // If profile not found in list, try to construct path directly
+    let profile_path = PathBuf::from(default_profile_path);
+    let full_path = scope.resolve(profiles_dir, &profile_path, !profile_path.is_absolute());
 
-    // Normalize path string
-    let path_str = install_dir.to_string_lossy();
-
-    // Hash with CityHash64 and format as 16 uppercase hex chars
-    let hash: u64 = hash(path_str.as_bytes());
-    format!("{:016X}", hash)
+    full_path.exists().then_some(full_path)
 }
 
 /// Improved fallback: Scan profiles directory with better matching strategies
@@ -454,36 +489,121 @@ fn scan_profiles_directory(profiles_dir: &PathBuf, profile_name: &str) -> Result
 
 /// List all available Firefox profiles
 pub fn list_profiles(profiles_dir_opt: Option<&std::path::Path>) -> Result<Vec<ProfileInfo>> {
-    let profiles_dir = get_profiles_directory(profiles_dir_opt)?;
-    let profiles_ini = profiles_dir.join("profiles.ini");
+    list_profiles_with_config(profiles_dir_opt, &ProfileListConfig::default())
+}
+
+/// List all available Firefox profiles, optionally collecting extra metadata
+///
+/// Metadata that cannot be read (e.g., a profile without times.json) is left
+/// as `None` rather than failing the whole listing.
+pub fn list_profiles_with_config(
+    profiles_dir_opt: Option<&std::path::Path>,
+    config: &ProfileListConfig,
+) -> Result<Vec<ProfileInfo>> {
+    list_profiles_for_app(&Application::FIREFOX, profiles_dir_opt, config)
+}
+
+/// List all profiles of the given application
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ffcv::{list_profiles_for_app, Application, ProfileListConfig};
+///
+/// let config = ProfileListConfig::default();
+/// for profile in list_profiles_for_app(&Application::LIBREWOLF, None, &config)? {
+///     println!("{}: {}", profile.name, profile.path.display());
+/// }
+/// # Ok::<(), ffcv::Error>(())
+/// ```
+pub fn list_profiles_for_app(
+    app: &Application,
+    profiles_dir_opt: Option<&std::path::Path>,
+    config: &ProfileListConfig,
+) -> Result<Vec<ProfileInfo>> {
+    let profiles_dir = get_profiles_directory_for_app(app, profiles_dir_opt)?;
+    list_profiles_in(app, &profiles_dir, config, ProfileScope::Host)
+}
+
+/// List the profiles of a resolved profiles directory
+pub(crate) fn list_profiles_in(
+    app: &Application,
+    profiles_dir: &Path,
+    config: &ProfileListConfig,
+    scope: ProfileScope,
+) -> Result<Vec<ProfileInfo>> {
+    let profiles_dir = profiles_dir.to_path_buf();
+    let profiles_ini = profiles_dir.join(app.profiles_ini);
 
     if !profiles_ini.exists() {
         return Err(Error::ProfilesIniParse(format!(
-            "profiles.ini not found at {}. \
-             Firefox may not be installed or this is not a standard Firefox setup.",
-            profiles_ini.display()
+            "{} not found at {}. \
+             {} may not be installed or this is not a standard {} setup.",
+            app.profiles_ini,
+            profiles_ini.display(),
+            app.name,
+            app.name
         )));
     }
 
     let profiles = parse_profiles_ini(&profiles_ini)?;
-    let installs = parse_installs_ini(&profiles_ini)?;
+    let mut installs = read_install_sections(&profiles_dir, app)?;
+
+    // Detecting installations runs every probe, so only do it on request
+    let installations = if config.include_install {
+        scope.installations(app).unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    match_installations(&mut installs, &installations);
+
+    // Profile group stores are optional; unreadable stores are skipped and
+    // their profiles listed without group details
+    let groups = list_profile_groups(Some(&profiles_dir))
+        .map(|list| list.groups)
+        .unwrap_or_default();
 
     // Merge profile info with install locks
     let profile_infos: Vec<ProfileInfo> = profiles
         .into_iter()
         .map(|p| {
             // Resolve relative paths to absolute paths
-            let full_path = if p.is_relative {
-                profiles_dir.join(&p.path)
-            } else {
-                p.path.clone()
-            };
+            let full_path = scope.resolve(&profiles_dir, &p.path, p.is_relative);
 
             let path_string = p.path.to_string_lossy().to_string();
-            let locked_to = installs
+            let section = installs
                 .iter()
-                .find(|(_, default_path)| *default_path == &path_string)
-                .map(|(hash, _)| hash.clone());
+                .find(|section| section.default.as_deref() == Some(path_string.as_str()));
+            let locked_to = section.map(|section| section.hash.clone());
+            let install_path = section.and_then(|section| {
+                section
+                    .installation
+                    .as_ref()
+                    .map(|install| install.path.clone())
+            });
+
+            let (store_id, group) = resolve_profile_group(&p, &full_path, &groups);
+
+            let times = if config.include_times {
+                read_profile_times(&full_path).ok()
+            } else {
+                None
+            };
+            let compatibility = if config.include_compatibility {
+                read_profile_compatibility(&full_path).ok()
+            } else {
+                None
+            };
+            let stats = if config.include_stats {
+                collect_profile_stats(&full_path).ok()
+            } else {
+                None
+            };
+            let installation = if config.include_install {
+                scope.link(app, &full_path, &profiles_dir, &installs, &installations)
+            } else {
+                None
+            };
 
             ProfileInfo {
                 name: p.name,
@@ -491,6 +611,13 @@ pub fn list_profiles(profiles_dir_opt: Option<&std::path::Path>) -> Result<Vec<P
                 is_default: p.is_default,
                 is_relative: p.is_relative,
                 locked_to_install: locked_to,
+                store_id,
+                group,
+                times,
+                compatibility,
+                stats,
+                install_path,
+                installation,
             }
         })
         .collect();
@@ -498,13 +625,66 @@ pub fn list_profiles(profiles_dir_opt: Option<&std::path::Path>) -> Result<Vec<P
     Ok(profile_infos)
 }
 
-/// Get the profiles directory path from CLI, env var, or auto-detection
+/// Find the profile group store and group entry for a profile
+///
+/// The store ID comes from profiles.ini when present. Otherwise the profile is
+/// looked up by path in every known group, and finally the
+/// `toolkit.profiles.storeID` pref in prefs.js is consulted.
+fn resolve_profile_group(
+    profile: &FirefoxProfile,
+    full_path: &Path,
+    groups: &[ProfileGroup],
+) -> (Option<String>, Option<GroupProfile>) {
+    let find_member = |group: &ProfileGroup| {
+        group
+            .profiles
+            .iter()
+            .find(|member| member.path == full_path)
+            .cloned()
+    };
+
+    let mut store_id = profile.store_id.clone();
+
+    if store_id.is_none() && !groups.is_empty() {
+        store_id = groups
+            .iter()
+            .find(|group| find_member(group).is_some())
+            .map(|group| group.store_id.clone())
+            .or_else(|| read_store_id_pref(full_path));
+    }
+
+    let group = store_id.as_ref().and_then(|id| {
+        groups
+            .iter()
+            .find(|group| &group.store_id == id)
+            .and_then(find_member)
+    });
+
+    (store_id, group)
+}
+
+/// Read the profile group store ID from a profile's prefs.js
+fn read_store_id_pref(profile_path: &Path) -> Option<String> {
+    let prefs = crate::parser::parse_prefs_js_file(&get_prefs_path(profile_path)).ok()?;
+    prefs
+        .into_iter()
+        .find(|p| p.key == STORE_ID_PREF)
+        .and_then(|p| match p.value {
+            crate::types::PrefValue::String(id) if !id.is_empty() => Some(id),
+            _ => None,
+        })
+}
+
+/// Get the profiles directory of an application from CLI, env var, or auto-detection
 ///
 /// Priority:
 /// 1. Manual path provided via CLI or parameter
 /// 2. MOZ_PROFILES_DIR environment variable
-/// 3. Auto-detection based on OS
-pub fn get_profiles_directory(manual_path: Option<&std::path::Path>) -> Result<PathBuf> {
+/// 3. Auto-detection based on OS (first of the application's profile roots that exists)
+pub fn get_profiles_directory_for_app(
+    app: &Application,
+    manual_path: Option<&std::path::Path>,
+) -> Result<PathBuf> {
     // Priority 1: Use manually specified path (from CLI or direct parameter)
     if let Some(path) = manual_path {
         return validate_and_use_profiles_dir(path);
@@ -517,7 +697,7 @@ pub fn get_profiles_directory(manual_path: Option<&std::path::Path>) -> Result<P
     }
 
     // Priority 3: Auto-detect based on OS
-    auto_detect_profiles_directory()
+    auto_detect_profiles_directory(app)
 }
 
 /// Validate and return the profiles directory path
@@ -544,37 +724,22 @@ fn validate_and_use_profiles_dir(path: &std::path::Path) -> Result<PathBuf> {
 }
 
 /// Auto-detect profiles directory based on operating system
-fn auto_detect_profiles_directory() -> Result<PathBuf> {
-    #[cfg(target_os = "linux")]
-    {
-        let home = std::env::var("HOME").map_err(|_| {
-            Error::ProfilesIniParse("HOME environment variable not set".to_string())
-        })?;
-        Ok(PathBuf::from(home).join(".mozilla/firefox"))
-    }
-
-    #[cfg(target_os = "macos")]
-    {
-        let home = std::env::var("HOME").map_err(|_| {
-            Error::ProfilesIniParse("HOME environment variable not set".to_string())
-        })?;
-        Ok(PathBuf::from(home).join("Library/Application Support/Firefox"))
-    }
+///
+/// Falls back to the application's primary profile root when none of the
+/// candidates exist, so error messages point at the expected location.
+fn auto_detect_profiles_directory(app: &Application) -> Result<PathBuf> {
+    let candidates = app.profile_root_candidates()?;
 
-    #[cfg(target_os = "windows")]
-    {
-        let appdata = std::env::var("APPDATA").map_err(|_| {
-            Error::ProfilesIniParse("APPDATA environment variable not set".to_string())
-        })?;
-        Ok(PathBuf::from(appdata).join("Mozilla/Firefox"))
+    if let Some(existing) = candidates.iter().find(|path| path.is_dir()) {
+        return Ok(existing.clone());
     }
 
-    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
-    {
-        Err(Error::ProfilesIniParse(
-            "Unsupported operating system".to_string(),
+    candidates.into_iter().next().ok_or_else(|| {
+        Error::ProfilesIniParse(format!(
+            "{} has no known profiles directory on this platform",
+            app.name
         ))
-    }
+    })
 }
 
 /// Get the path to prefs.js for a given profile
@@ -691,10 +856,51 @@ Locked=1
         assert_eq!(ini.get("308046B0AF4A39CB", "Locked"), Some("1".to_string()));
     }
 
+    #[test]
+    fn test_list_profiles_with_store_id() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let profiles_dir = temp_dir.path();
+        std::fs::create_dir_all(profiles_dir.join("Profiles/a.work")).unwrap();
+        std::fs::create_dir_all(profiles_dir.join("Profiles/b.home")).unwrap();
+        std::fs::create_dir_all(profiles_dir.join("Profiles/c.solo")).unwrap();
+        std::fs::write(
+            profiles_dir.join("Profiles/b.home/prefs.js"),
+            r#"user_pref("toolkit.profiles.storeID", "abc123");"#,
+        )
+        .unwrap();
+        std::fs::write(
+            profiles_dir.join("profiles.ini"),
+            "[Profile0]\nName=work\nIsRelative=1\nPath=Profiles/a.work\nStoreID=abc123\n\n\
+             [Profile1]\nName=home\nIsRelative=1\nPath=Profiles/b.home\n\n\
+             [Profile2]\nName=solo\nIsRelative=1\nPath=Profiles/c.solo\n",
+        )
+        .unwrap();
+        crate::profile_groups::tests::create_group_db(
+            profiles_dir,
+            "abc123",
+            &[("Profiles/a.work", "Work")],
+        );
+
+        let profiles = list_profiles(Some(profiles_dir)).unwrap();
+        let work = profiles.iter().find(|p| p.name == "work").unwrap();
+        let home = profiles.iter().find(|p| p.name == "home").unwrap();
+        let solo = profiles.iter().find(|p| p.name == "solo").unwrap();
+
+        assert_eq!(work.store_id.as_deref(), Some("abc123"));
+        assert_eq!(work.group.as_ref().unwrap().name, "Work");
+        // Store ID taken from prefs.js when profiles.ini has none
+        assert_eq!(home.store_id.as_deref(), Some("abc123"));
+        assert!(home.group.is_none());
+        assert!(solo.store_id.is_none());
+    }
+
     #[test]
     fn test_profiles_dir_validation_nonexistent() {
         // Test validation with non-existent path
-        let result = get_profiles_directory(Some(std::path::Path::new("/nonexistent/path")));
+        let result = get_profiles_directory_for_app(
+            &Application::FIREFOX,
+            Some(std::path::Path::new("/nonexistent/path")),
+        );
         assert!(result.is_err());
         let error_msg = result.unwrap_err().to_string();
         assert!(error_msg.contains("does not exist"));
@@ -705,7 +911,7 @@ Locked=1
     fn test_profiles_dir_validation_file_not_directory() {
         // Test validation when path is a file, not a directory
         let temp_file = tempfile::NamedTempFile::new().unwrap();
-        let result = get_profiles_directory(Some(temp_file.path()));
+        let result = get_profiles_directory_for_app(&Application::FIREFOX, Some(temp_file.path()));
         assert!(result.is_err());
         let error_msg = result.unwrap_err().to_string();
         assert!(error_msg.contains("not a directory"));
@@ -715,7 +921,7 @@ Locked=1
     fn test_profiles_dir_validation_valid_directory() {
         // Test validation with a valid temporary directory
         let temp_dir = tempfile::TempDir::new().unwrap();
-        let result = get_profiles_directory(Some(temp_dir.path()));
+        let result = get_profiles_directory_for_app(&Application::FIREFOX, Some(temp_dir.path()));
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), temp_dir.path());
     }
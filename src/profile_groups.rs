@@ -0,0 +1,270 @@
+//! Firefox profile groups
+//!
+//! Newer Firefox releases can group several profiles together and manage them
+//! from a shared SQLite store. Each grouped profile references the store via
+//! the `StoreID` key in its profiles.ini section (and the
+//! `toolkit.profiles.storeID` preference in its prefs.js). The store itself
+//! lives at `<profiles dir>/Profile Groups/<StoreID>.sqlite` and records the
+//! display name, avatar and theme for every profile in the group.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use ffcv::list_profile_groups;
+//!
+//! for group in list_profile_groups(None)?.groups {
+//!     println!("Group {}:", group.store_id);
+//!     for profile in &group.profiles {
+//!         println!("  {} ({})", profile.name, profile.avatar);
+//!     }
+//! }
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::application::Application;
+use crate::error::{Error, Result};
+use crate::profile::get_profiles_directory_for_app;
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Name of the directory holding profile group databases
+pub(crate) const PROFILE_GROUPS_DIR: &str = "Profile Groups";
+
+/// Preference that records the profile group store ID inside prefs.js
+pub(crate) const STORE_ID_PREF: &str = "toolkit.profiles.storeID";
+
+/// A group of profiles sharing one profile management store
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileGroup {
+    /// Store identifier referenced by `StoreID` in profiles.ini
+    pub store_id: String,
+    /// Path to the SQLite database backing this group
+    pub database_path: PathBuf,
+    /// Profiles registered in this group
+    pub profiles: Vec<GroupProfile>,
+}
+
+/// Profile groups of a profiles directory
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProfileGroupList {
+    /// Groups whose store could be read, sorted by store ID
+    pub groups: Vec<ProfileGroup>,
+    /// Stores that could not be read and were skipped
+    pub warnings: Vec<String>,
+}
+
+/// A profile entry from a profile group database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GroupProfile {
+    /// Row identifier within the group database
+    pub id: i64,
+    /// Absolute path to the profile directory
+    pub path: PathBuf,
+    /// Display name shown in the profile selector
+    pub name: String,
+    /// Avatar identifier (e.g., "book", "briefcase")
+    pub avatar: String,
+    /// Theme identifier (add-on ID of the selected theme)
+    pub theme_id: String,
+    /// Theme foreground color
+    pub theme_fg: String,
+    /// Theme background color
+    pub theme_bg: String,
+}
+
+/// List all profile groups found in the profiles directory
+///
+/// Returns an empty list if the profiles directory has no `Profile Groups`
+/// folder, which is the case for Firefox versions without profile
+/// management.
+///
+/// # Arguments
+///
+/// * `profiles_dir_opt` - Optional custom profiles directory path
+pub fn list_profile_groups(profiles_dir_opt: Option<&Path>) -> Result<ProfileGroupList> {
+    list_profile_groups_for_app(&Application::FIREFOX, profiles_dir_opt)
+}
+
+/// List all profile groups of the given application
+///
+/// A store that cannot be read is skipped with a warning rather than
+/// hiding the other groups.
+pub fn list_profile_groups_for_app(
+    app: &Application,
+    profiles_dir_opt: Option<&Path>,
+) -> Result<ProfileGroupList> {
+    let profiles_dir = get_profiles_directory_for_app(app, profiles_dir_opt)?;
+    let groups_dir = profiles_dir.join(PROFILE_GROUPS_DIR);
+
+    let mut list = ProfileGroupList::default();
+    if !groups_dir.is_dir() {
+        return Ok(list);
+    }
+
+    for entry in std::fs::read_dir(&groups_dir)? {
+        let path = entry?.path();
+        if path.extension().map(|e| e == "sqlite").unwrap_or(false) {
+            match read_profile_group(&path, &profiles_dir) {
+                Ok(group) => list.groups.push(group),
+                Err(e) => list.warnings.push(format!(
+                    "Skipped profile group store {}: {}",
+                    path.display(),
+                    e
+                )),
+            }
+        }
+    }
+
+    list.groups.sort_by(|a, b| a.store_id.cmp(&b.store_id));
+    list.warnings.sort();
+    Ok(list)
+}
+
+/// Read a single profile group database
+///
+/// The database is opened read-only, so this is safe to call while Firefox
+/// is running. Relative profile paths in the store are resolved against
+/// `profiles_dir`.
+///
+/// # Arguments
+///
+/// * `database_path` - Path to the `<StoreID>.sqlite` file
+/// * `profiles_dir` - Profiles directory the store belongs to
+pub fn read_profile_group(database_path: &Path, profiles_dir: &Path) -> Result<ProfileGroup> {
+    let store_id = database_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let conn = Connection::open_with_flags(
+        database_path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| store_error(database_path, e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, path, name, avatar, themeId, themeFg, themeBg \
+             FROM Profiles ORDER BY id",
+        )
+        .map_err(|e| store_error(database_path, e))?;
+
+    let profiles = stmt
+        .query_map([], |row| {
+            let path: String = row.get(1)?;
+            Ok(GroupProfile {
+                id: row.get(0)?,
+                path: resolve_group_path(profiles_dir, &path),
+                name: row.get(2)?,
+                avatar: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                theme_id: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                theme_fg: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                theme_bg: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+            })
+        })
+        .map_err(|e| store_error(database_path, e))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| store_error(database_path, e))?;
+
+    Ok(ProfileGroup {
+        store_id,
+        database_path: database_path.to_path_buf(),
+        profiles,
+    })
+}
+
+/// Resolve a profile path stored in a group database
+fn resolve_group_path(profiles_dir: &Path, stored: &str) -> PathBuf {
+    let path = PathBuf::from(stored);
+    if path.is_absolute() {
+        path
+    } else {
+        profiles_dir.join(path)
+    }
+}
+
+/// Wrap a SQLite error with the database path
+fn store_error(database_path: &Path, e: rusqlite::Error) -> Error {
+    Error::ProfileGroupStore(format!("{}: {}", database_path.display(), e))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Create a profile group database with the given (path, name) profiles
+    pub(crate) fn create_group_db(profiles_dir: &Path, store_id: &str, profiles: &[(&str, &str)]) {
+        let groups_dir = profiles_dir.join(PROFILE_GROUPS_DIR);
+        std::fs::create_dir_all(&groups_dir).unwrap();
+
+        let conn = Connection::open(groups_dir.join(format!("{}.sqlite", store_id))).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE Profiles (
+                id INTEGER NOT NULL PRIMARY KEY,
+                path TEXT NOT NULL UNIQUE,
+                name TEXT NOT NULL,
+                avatar TEXT NOT NULL,
+                themeId TEXT NOT NULL,
+                themeFg TEXT NOT NULL,
+                themeBg TEXT NOT NULL
+            );",
+        )
+        .unwrap();
+
+        for (path, name) in profiles {
+            conn.execute(
+                "INSERT INTO Profiles (path, name, avatar, themeId, themeFg, themeBg) \
+                 VALUES (?1, ?2, 'book', 'default-theme@mozilla.org', '#000000', '#ffffff')",
+                [path, name],
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_list_profile_groups_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        let list = list_profile_groups(Some(temp_dir.path())).unwrap();
+        assert!(list.groups.is_empty());
+        assert!(list.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_read_profile_group() {
+        let temp_dir = TempDir::new().unwrap();
+        create_group_db(
+            temp_dir.path(),
+            "abc123",
+            &[("Profiles/a.work", "Work"), ("Profiles/b.home", "Home")],
+        );
+
+        // A corrupt store is skipped without hiding the readable one
+        std::fs::write(
+            temp_dir
+                .path()
+                .join(PROFILE_GROUPS_DIR)
+                .join("broken.sqlite"),
+            "not a database",
+        )
+        .unwrap();
+
+        let list = list_profile_groups(Some(temp_dir.path())).unwrap();
+        assert_eq!(list.groups.len(), 1);
+        assert_eq!(list.warnings.len(), 1);
+        assert!(list.warnings[0].contains("broken.sqlite"));
+
+        let group = &list.groups[0];
+        assert_eq!(group.store_id, "abc123");
+        assert_eq!(group.profiles.len(), 2);
+        assert_eq!(group.profiles[0].name, "Work");
+        assert_eq!(group.profiles[0].avatar, "book");
+        assert_eq!(
+            group.profiles[0].path,
+            temp_dir.path().join("Profiles/a.work")
+        );
+        assert_eq!(group.profiles[1].name, "Home");
+    }
+}
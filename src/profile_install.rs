@@ -0,0 +1,272 @@
+//! Profile to installation mapping
+//!
+//! Several installations of the same application (e.g. Firefox ESR and
+//! Release) usually share one profiles directory, but each profile is owned
+//! by the installation that last ran it. Firefox records that installation in
+//! two places:
+//!
+//! - `compatibility.ini` in the profile (`LastPlatformDir`, `LastAppDir`),
+//!   written on every startup
+//! - `[Install<HASH>]` sections in profiles.ini, naming the default profile
+//!   of each installation
+//!
+//! The compatibility data reflects the most recent use, so it takes
+//! precedence; install sections are used for profiles without it.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use ffcv::{find_profile_installation, find_profile_path, Application};
+//!
+//! let profile = find_profile_path("default-release", None)?;
+//! if let Some(link) = find_profile_installation(&Application::FIREFOX, &profile, None)? {
+//!     println!(
+//!         "{} belongs to Firefox {} at {} (via {:?})",
+//!         profile.display(),
+//!         link.installation.version,
+//!         link.installation.path.display(),
+//!         link.source
+//!     );
+//! }
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::application::Application;
+use crate::error::Result;
+use crate::firefox_locator::{find_all_installations_for_app, validate_installation};
+use crate::install_section::{match_installations, read_install_sections, InstallSection};
+use crate::profile::get_profiles_directory_for_app;
+use crate::profile_metadata::read_profile_compatibility;
+use crate::types::Installation;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// How a profile was linked to its installation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum InstallLinkSource {
+    /// `LastPlatformDir`/`LastAppDir` in the profile's compatibility.ini
+    CompatibilityIni,
+    /// An `[Install…]` section naming the profile as its default
+    InstallSection,
+    /// The installation supplied for offline analysis
+    Supplied,
+}
+
+/// The installation that owns a profile
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileInstallation {
+    /// Where the link between profile and installation came from
+    pub source: InstallLinkSource,
+    /// The owning installation
+    pub installation: Installation,
+}
+
+/// Find the installation that owns a profile
+///
+/// Installations of `app` are detected with the default probes. An
+/// installation named by compatibility.ini is also accepted when no probe
+/// found it, as long as the directory still holds a valid installation.
+///
+/// # Arguments
+///
+/// * `app` - Application the profile belongs to
+/// * `profile_path` - Path to the profile directory
+/// * `profiles_dir_opt` - Optional custom profiles directory (for install sections)
+pub fn find_profile_installation(
+    app: &Application,
+    profile_path: &Path,
+    profiles_dir_opt: Option<&Path>,
+) -> Result<Option<ProfileInstallation>> {
+    let installations = find_all_installations_for_app(app)?;
+
+    // Install sections are optional; a profile outside any profiles
+    // directory can still be linked through compatibility.ini
+    let mut sections = Vec::new();
+    let mut profiles_dir = None;
+    if let Ok(dir) = get_profiles_directory_for_app(app, profiles_dir_opt) {
+        sections = read_install_sections(&dir, app).unwrap_or_default();
+        match_installations(&mut sections, &installations);
+        profiles_dir = Some(dir);
+    }
+
+    Ok(link_profile_installation(
+        app,
+        profile_path,
+        profiles_dir.as_deref(),
+        &sections,
+        &installations,
+    ))
+}
+
+/// Link a profile to one of the given installations
+///
+/// `sections` must already be matched against `installations`.
+pub(crate) fn link_profile_installation(
+    app: &Application,
+    profile_path: &Path,
+    profiles_dir: Option<&Path>,
+    sections: &[InstallSection],
+    installations: &[Installation],
+) -> Option<ProfileInstallation> {
+    if let Some(installation) = installation_from_compatibility(app, profile_path, installations) {
+        return Some(ProfileInstallation {
+            source: InstallLinkSource::CompatibilityIni,
+            installation,
+        });
+    }
+
+    let profile = canonical(profile_path);
+    sections
+        .iter()
+        .filter(|section| {
+            section.default.as_deref().is_some_and(|default| {
+                let default = Path::new(default);
+                let resolved = match profiles_dir {
+                    Some(dir) if default.is_relative() => dir.join(default),
+                    _ => default.to_path_buf(),
+                };
+                canonical(&resolved) == profile
+            })
+        })
+        .find_map(|section| section.installation.clone())
+        .map(|installation| ProfileInstallation {
+            source: InstallLinkSource::InstallSection,
+            installation,
+        })
+}
+
+/// Resolve the installation recorded in a profile's compatibility.ini
+fn installation_from_compatibility(
+    app: &Application,
+    profile_path: &Path,
+    installations: &[Installation],
+) -> Option<Installation> {
+    let compat = read_profile_compatibility(profile_path).ok()?;
+
+    // LastAppDir is the "browser" subdirectory for Firefox-based browsers
+    let mut candidates: Vec<PathBuf> = compat.last_platform_dir.into_iter().collect();
+    if let Some(app_dir) = compat.last_app_dir {
+        if let Some(parent) = app_dir.parent() {
+            candidates.push(parent.to_path_buf());
+        }
+        candidates.push(app_dir);
+    }
+
+    for candidate in &candidates {
+        let candidate = canonical(candidate);
+        let known = installations
+            .iter()
+            .find(|install| install.all_paths().any(|path| canonical(path) == candidate));
+        if let Some(install) = known {
+            return Some(install.clone());
+        }
+    }
+
+    // Not found by any probe, e.g. an unpacked tarball in a custom location
+    candidates
+        .first()
+        .and_then(|dir| validate_installation(dir, app).ok())
+        .map(|install| install.found_by("compatibility-ini"))
+}
+
+/// Resolve symlinks where possible
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn create_install(dir: &Path, version: &str) -> Installation {
+        std::fs::create_dir_all(dir.join("browser")).unwrap();
+        std::fs::write(dir.join("browser/omni.ja"), version).unwrap();
+        std::fs::write(
+            dir.join("application.ini"),
+            format!("[App]\nVersion={}\n", version),
+        )
+        .unwrap();
+        validate_installation(dir, &Application::FIREFOX).unwrap()
+    }
+
+    #[test]
+    fn test_compatibility_ini_wins_over_install_section() {
+        let temp_dir = TempDir::new().unwrap();
+        let esr = create_install(&temp_dir.path().join("firefox-esr"), "115.12.0");
+        let release = create_install(&temp_dir.path().join("firefox"), "128.0");
+
+        let profiles_dir = temp_dir.path().join("profiles");
+        let profile = profiles_dir.join("Profiles/abc.default-esr");
+        std::fs::create_dir_all(&profile).unwrap();
+        std::fs::write(
+            profile.join("compatibility.ini"),
+            format!(
+                "[Compatibility]\nLastVersion=115.12.0_20240604163519/20240604163519\n\
+                 LastPlatformDir={}\nLastAppDir={}/browser\n",
+                esr.path.display(),
+                esr.path.display()
+            ),
+        )
+        .unwrap();
+
+        let sections = vec![InstallSection {
+            hash: "0000000000000000".to_string(),
+            default: Some("Profiles/abc.default-esr".to_string()),
+            locked: true,
+            installation: Some(release.clone()),
+            matched_path: Some(release.path.clone()),
+        }];
+        let installations = vec![release, esr.clone()];
+
+        let link = link_profile_installation(
+            &Application::FIREFOX,
+            &profile,
+            Some(&profiles_dir),
+            &sections,
+            &installations,
+        )
+        .unwrap();
+        assert_eq!(link.source, InstallLinkSource::CompatibilityIni);
+        assert_eq!(link.installation.path, esr.path);
+
+        // Without compatibility.ini the install section decides
+        std::fs::remove_file(profile.join("compatibility.ini")).unwrap();
+        let link = link_profile_installation(
+            &Application::FIREFOX,
+            &profile,
+            Some(&profiles_dir),
+            &sections,
+            &installations,
+        )
+        .unwrap();
+        assert_eq!(link.source, InstallLinkSource::InstallSection);
+        assert_eq!(link.installation.version, "128.0");
+    }
+
+    #[test]
+    fn test_compatibility_ini_outside_detected_installations() {
+        let temp_dir = TempDir::new().unwrap();
+        let custom = create_install(&temp_dir.path().join("custom"), "129.0b1");
+
+        let profile = temp_dir.path().join("profile");
+        std::fs::create_dir_all(&profile).unwrap();
+        std::fs::write(
+            profile.join("compatibility.ini"),
+            format!(
+                "[Compatibility]\nLastPlatformDir={}\n",
+                custom.path.display()
+            ),
+        )
+        .unwrap();
+
+        let link =
+            link_profile_installation(&Application::FIREFOX, &profile, None, &[], &[]).unwrap();
+        assert_eq!(link.installation.version, "129.0b1");
+        assert_eq!(
+            link.installation.probe.as_deref(),
+            Some("compatibility-ini")
+        );
+    }
+}
@@ -0,0 +1,298 @@
+//! Firefox profile metadata
+//!
+//! This module reads optional metadata that Firefox keeps inside each profile
+//! directory: creation timestamps from `times.json`, the last Firefox build
+//! that opened the profile from `compatibility.ini`, and simple statistics
+//! such as the number of user preferences and the on-disk size.
+//!
+//! Collecting this information requires extra file I/O per profile, so it is
+//! opt-in via [`ProfileListConfig`](crate::ProfileListConfig).
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use ffcv::{list_profiles_with_config, ProfileListConfig};
+//!
+//! let config = ProfileListConfig {
+//!     include_compatibility: true,
+//!     ..Default::default()
+//! };
+//!
+//! for profile in list_profiles_with_config(None, &config)? {
+//!     if let Some(compat) = &profile.compatibility {
+//!         println!("{} last used by Firefox {:?}", profile.name, compat.last_version);
+//!     }
+//! }
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::error::{Error, Result};
+use crate::parser::parse_prefs_js_file;
+use crate::types::PrefType;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Profile creation timestamps from `times.json`
+///
+/// All timestamps are milliseconds since the Unix epoch, matching the
+/// representation Firefox uses in the file itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ProfileTimes {
+    /// When the profile was created
+    pub created: Option<u64>,
+    /// When the profile was first used by Firefox
+    pub first_use: Option<u64>,
+}
+
+/// Information about the Firefox build that last used a profile
+///
+/// Parsed from the `[Compatibility]` section of `compatibility.ini`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ProfileCompatibility {
+    /// Application version that last opened the profile (e.g., "115.12.0esr")
+    pub last_version: Option<String>,
+    /// Build ID of that application (e.g., "20240604163519")
+    pub last_build_id: Option<String>,
+    /// Target OS and ABI (e.g., "Linux_x86_64-gcc3")
+    pub last_os_abi: Option<String>,
+    /// Installation directory of the platform (GRE) that last opened the profile
+    pub last_platform_dir: Option<PathBuf>,
+    /// Application directory that last opened the profile
+    pub last_app_dir: Option<PathBuf>,
+}
+
+/// Size and usage statistics for a profile directory
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ProfileStats {
+    /// Modification time of prefs.js in milliseconds since the Unix epoch
+    pub prefs_modified: Option<u64>,
+    /// Number of `user_pref()` entries in prefs.js
+    pub user_pref_count: Option<usize>,
+    /// Total size of all files in the profile directory, in bytes
+    pub size_bytes: u64,
+}
+
+/// Read profile timestamps from `times.json`
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ffcv::read_profile_times;
+/// use std::path::Path;
+///
+/// let times = read_profile_times(Path::new("/home/user/.mozilla/firefox/abc.default"))?;
+/// println!("Created at {:?}", times.created);
+/// # Ok::<(), ffcv::Error>(())
+/// ```
+pub fn read_profile_times(profile_path: &Path) -> Result<ProfileTimes> {
+    let times_path = profile_path.join("times.json");
+    let content = std::fs::read_to_string(&times_path)?;
+
+    let json: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| Error::ProfileFileParse {
+            path: times_path.clone(),
+            message: e.to_string(),
+        })?;
+
+    Ok(ProfileTimes {
+        created: json.get("created").and_then(|v| v.as_u64()),
+        first_use: json.get("firstUse").and_then(|v| v.as_u64()),
+    })
+}
+
+/// Read the last-used build information from `compatibility.ini`
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ffcv::read_profile_compatibility;
+/// use std::path::Path;
+///
+/// let compat = read_profile_compatibility(Path::new("/home/user/.mozilla/firefox/abc.default"))?;
+/// println!("Last opened by {:?}", compat.last_version);
+/// # Ok::<(), ffcv::Error>(())
+/// ```
+pub fn read_profile_compatibility(profile_path: &Path) -> Result<ProfileCompatibility> {
+    use configparser::ini::Ini;
+
+    let ini_path = profile_path.join("compatibility.ini");
+    let content = std::fs::read_to_string(&ini_path)?;
+
+    let mut ini = Ini::new();
+    if let Err(message) = ini.read(content) {
+        return Err(Error::ProfileFileParse {
+            path: ini_path,
+            message,
+        });
+    }
+
+    // LastVersion has the form "<app version>_<app build id>/<platform build id>"
+    let (last_version, last_build_id) = match ini.get("Compatibility", "LastVersion") {
+        Some(raw) => {
+            let app_part = raw.split('/').next().unwrap_or(&raw);
+            match app_part.split_once('_') {
+                Some((version, build_id)) => {
+                    (Some(version.to_string()), Some(build_id.to_string()))
+                }
+                None => (Some(app_part.to_string()), None),
+            }
+        }
+        None => (None, None),
+    };
+
+    Ok(ProfileCompatibility {
+        last_version,
+        last_build_id,
+        last_os_abi: ini.get("Compatibility", "LastOSABI"),
+        last_platform_dir: ini
+            .get("Compatibility", "LastPlatformDir")
+            .map(PathBuf::from),
+        last_app_dir: ini.get("Compatibility", "LastAppDir").map(PathBuf::from),
+    })
+}
+
+/// Collect size and usage statistics for a profile directory
+///
+/// The directory size is computed by walking the whole profile, which can be
+/// slow for large profiles with big caches or IndexedDB stores.
+pub fn collect_profile_stats(profile_path: &Path) -> Result<ProfileStats> {
+    if !profile_path.is_dir() {
+        return Err(Error::InvalidProfileDirectory(profile_path.to_path_buf()));
+    }
+
+    let prefs_path = profile_path.join("prefs.js");
+
+    let prefs_modified = std::fs::metadata(&prefs_path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(system_time_to_millis);
+
+    let user_pref_count = parse_prefs_js_file(&prefs_path).ok().map(|prefs| {
+        prefs
+            .iter()
+            .filter(|p| p.pref_type == PrefType::User)
+            .count()
+    });
+
+    let size_bytes = walkdir::WalkDir::new(profile_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.metadata().ok())
+        .map(|m| m.len())
+        .sum();
+
+    Ok(ProfileStats {
+        prefs_modified,
+        user_pref_count,
+        size_bytes,
+    })
+}
+
+/// Convert a SystemTime to milliseconds since the Unix epoch
+fn system_time_to_millis(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_read_profile_times() {
+        let temp_dir = TempDir::new().unwrap();
+        write(
+            temp_dir.path().join("times.json"),
+            r#"{"created":1700000000000,"firstUse":1700000100000}"#,
+        )
+        .unwrap();
+
+        let times = read_profile_times(temp_dir.path()).unwrap();
+        assert_eq!(times.created, Some(1700000000000));
+        assert_eq!(times.first_use, Some(1700000100000));
+    }
+
+    #[test]
+    fn test_read_profile_times_null_first_use() {
+        let temp_dir = TempDir::new().unwrap();
+        write(
+            temp_dir.path().join("times.json"),
+            r#"{"created":1700000000000,"firstUse":null}"#,
+        )
+        .unwrap();
+
+        let times = read_profile_times(temp_dir.path()).unwrap();
+        assert_eq!(times.created, Some(1700000000000));
+        assert_eq!(times.first_use, None);
+    }
+
+    #[test]
+    fn test_read_profile_compatibility() {
+        let temp_dir = TempDir::new().unwrap();
+        write(
+            temp_dir.path().join("compatibility.ini"),
+            "[Compatibility]\n\
+             LastVersion=115.12.0esr_20240604163519/20240604163519\n\
+             LastOSABI=Linux_x86_64-gcc3\n\
+             LastPlatformDir=/usr/lib/firefox-esr\n\
+             LastAppDir=/usr/lib/firefox-esr/browser\n",
+        )
+        .unwrap();
+
+        let compat = read_profile_compatibility(temp_dir.path()).unwrap();
+        assert_eq!(compat.last_version.as_deref(), Some("115.12.0esr"));
+        assert_eq!(compat.last_build_id.as_deref(), Some("20240604163519"));
+        assert_eq!(compat.last_os_abi.as_deref(), Some("Linux_x86_64-gcc3"));
+        assert_eq!(
+            compat.last_platform_dir,
+            Some(PathBuf::from("/usr/lib/firefox-esr"))
+        );
+        assert_eq!(
+            compat.last_app_dir,
+            Some(PathBuf::from("/usr/lib/firefox-esr/browser"))
+        );
+    }
+
+    #[test]
+    fn test_collect_profile_stats() {
+        let temp_dir = TempDir::new().unwrap();
+        write(
+            temp_dir.path().join("prefs.js"),
+            r#"
+                user_pref("a.b", true);
+                user_pref("c.d", 1);
+                pref("e.f", "x");
+            "#,
+        )
+        .unwrap();
+
+        let stats = collect_profile_stats(temp_dir.path()).unwrap();
+        assert_eq!(stats.user_pref_count, Some(2));
+        assert!(stats.prefs_modified.is_some());
+        assert!(stats.size_bytes > 0);
+    }
+
+    #[test]
+    fn test_missing_metadata_files() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(read_profile_times(temp_dir.path()).is_err());
+        assert!(read_profile_compatibility(temp_dir.path()).is_err());
+
+        std::fs::write(temp_dir.path().join("times.json"), "{not json").unwrap();
+        match read_profile_times(temp_dir.path()) {
+            Err(Error::ProfileFileParse { path, .. }) => {
+                assert_eq!(path, temp_dir.path().join("times.json"))
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+
+        let stats = collect_profile_stats(temp_dir.path()).unwrap();
+        assert_eq!(stats.user_pref_count, None);
+        assert_eq!(stats.prefs_modified, None);
+    }
+}
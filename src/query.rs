@@ -1,6 +1,8 @@
 //! Query and filtering operations for Firefox preferences
 //!
-//! This module provides functionality for filtering preferences using glob patterns.
+//! This module provides functionality for filtering preferences by key, using
+//! glob patterns by default. [`QueryOptions`] switches to regular expressions
+//! or plain-text search and makes matching case-insensitive.
 //!
 //! # Example
 //!
@@ -21,7 +23,211 @@
 
 use crate::error::{Error, Result};
 use crate::types::PrefEntry;
-use glob::Pattern;
+use glob::{MatchOptions, Pattern};
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+/// How query patterns are matched against preference keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchMode {
+    /// Glob pattern matching the whole key (e.g., `network.*`)
+    #[default]
+    Glob,
+    /// Regular expression matching anywhere in the key
+    /// (e.g., `^network\.(proxy|trr)\.`); anchor it to match the whole key
+    Regex,
+    /// Plain-text search: every whitespace-separated term must occur in the key
+    /// (e.g., `proxy port` matches `network.proxy.http_port`)
+    Search,
+}
+
+/// Options for matching query patterns
+///
+/// # Example
+///
+/// ```rust
+/// use ffcv::{parse_prefs_js, query_preferences_with_options, MatchMode, QueryOptions};
+///
+/// let prefs = parse_prefs_js(r#"
+///     user_pref("network.proxy.type", 1);
+///     user_pref("network.trr.mode", 2);
+///     user_pref("privacy.bounceTrackingProtection.mode", 1);
+/// "#)?;
+///
+/// let options = QueryOptions {
+///     mode: MatchMode::Regex,
+///     case_insensitive: false,
+/// };
+/// let matched = query_preferences_with_options(&prefs, &[r"^network\.(proxy|trr)\."], &options)?;
+/// assert_eq!(matched.len(), 2);
+///
+/// let options = QueryOptions {
+///     mode: MatchMode::Glob,
+///     case_insensitive: true,
+/// };
+/// let matched = query_preferences_with_options(&prefs, &["*bouncetracking*"], &options)?;
+/// assert_eq!(matched.len(), 1);
+/// # Ok::<(), ffcv::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QueryOptions {
+    /// How patterns are interpreted
+    pub mode: MatchMode,
+    /// Ignore ASCII case when matching
+    pub case_insensitive: bool,
+}
+
+/// Compiled set of query patterns (OR logic)
+///
+/// Besides filtering, a `KeyQuery` reports which parts of a key matched,
+/// for highlighting in text output.
+#[derive(Debug, Clone)]
+pub struct KeyQuery {
+    matchers: Vec<KeyMatcher>,
+}
+
+#[derive(Debug, Clone)]
+enum KeyMatcher {
+    Glob(Pattern, MatchOptions),
+    Regex(Regex),
+    Search {
+        terms: Vec<String>,
+        case_insensitive: bool,
+    },
+}
+
+impl KeyQuery {
+    /// Compile patterns, failing on the first invalid one
+    pub fn new(patterns: &[&str], options: &QueryOptions) -> Result<Self> {
+        let matchers = patterns
+            .iter()
+            .map(|p| KeyMatcher::compile(p, options))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { matchers })
+    }
+
+    /// Whether any pattern matches the key
+    pub fn matches(&self, key: &str) -> bool {
+        self.matchers.iter().any(|m| m.matches(key))
+    }
+
+    /// Byte ranges of the key matched by any pattern, sorted and merged
+    ///
+    /// Glob patterns always match the whole key.
+    pub fn match_ranges(&self, key: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = self
+            .matchers
+            .iter()
+            .filter(|m| m.matches(key))
+            .flat_map(|m| m.ranges(key))
+            .collect();
+        ranges.sort_by_key(|r| (r.start, r.end));
+
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+}
+
+impl KeyMatcher {
+    fn compile(pattern: &str, options: &QueryOptions) -> Result<Self> {
+        match options.mode {
+            MatchMode::Glob => {
+                let compiled = Pattern::new(pattern).map_err(|e| {
+                    Error::InvalidGlobPattern(format!("Invalid query pattern '{}': {}", pattern, e))
+                })?;
+                let match_options = MatchOptions {
+                    case_sensitive: !options.case_insensitive,
+                    ..MatchOptions::new()
+                };
+                Ok(KeyMatcher::Glob(compiled, match_options))
+            }
+            MatchMode::Regex => RegexBuilder::new(pattern)
+                .case_insensitive(options.case_insensitive)
+                .build()
+                .map(KeyMatcher::Regex)
+                .map_err(|e| {
+                    Error::InvalidQuery(format!("Invalid regular expression '{}': {}", pattern, e))
+                }),
+            MatchMode::Search => {
+                let terms: Vec<String> = pattern
+                    .split_whitespace()
+                    .map(|term| {
+                        if options.case_insensitive {
+                            term.to_ascii_lowercase()
+                        } else {
+                            term.to_string()
+                        }
+                    })
+                    .collect();
+                if terms.is_empty() {
+                    return Err(Error::InvalidQuery("Empty search term".to_string()));
+                }
+                Ok(KeyMatcher::Search {
+                    terms,
+                    case_insensitive: options.case_insensitive,
+                })
+            }
+        }
+    }
+
+    fn matches(&self, key: &str) -> bool {
+        match self {
+            KeyMatcher::Glob(pattern, options) => pattern.matches_with(key, *options),
+            KeyMatcher::Regex(regex) => regex.is_match(key),
+            KeyMatcher::Search {
+                terms,
+                case_insensitive,
+            } => {
+                let haystack = search_haystack(key, *case_insensitive);
+                terms.iter().all(|term| haystack.contains(term.as_str()))
+            }
+        }
+    }
+
+    fn ranges(&self, key: &str) -> Vec<Range<usize>> {
+        match self {
+            KeyMatcher::Glob(..) => vec![Range {
+                start: 0,
+                end: key.len(),
+            }],
+            KeyMatcher::Regex(regex) => regex
+                .find_iter(key)
+                .map(|m| m.range())
+                .filter(|r| !r.is_empty())
+                .collect(),
+            KeyMatcher::Search {
+                terms,
+                case_insensitive,
+            } => {
+                // ASCII lowercasing keeps byte offsets valid for the original key
+                let haystack = search_haystack(key, *case_insensitive);
+                terms
+                    .iter()
+                    .flat_map(|term| {
+                        haystack
+                            .match_indices(term.as_str())
+                            .map(|(start, m)| start..start + m.len())
+                            .collect::<Vec<_>>()
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+fn search_haystack(key: &str, case_insensitive: bool) -> std::borrow::Cow<'_, str> {
+    if case_insensitive {
+        std::borrow::Cow::Owned(key.to_ascii_lowercase())
+    } else {
+        std::borrow::Cow::Borrowed(key)
+    }
+}
 
 /// Query configuration preferences by glob patterns (OR logic)
 ///
@@ -64,24 +270,25 @@ use glob::Pattern;
 /// # Ok::<(), ffcv::Error>(())
 /// ```
 pub fn query_preferences(preferences: &[PrefEntry], patterns: &[&str]) -> Result<Vec<PrefEntry>> {
+    query_preferences_with_options(preferences, patterns, &QueryOptions::default())
+}
+
+/// Query preferences by key with the given matching options (OR logic)
+///
+/// Works like [`query_preferences`], but patterns are interpreted according
+/// to [`QueryOptions::mode`].
+pub fn query_preferences_with_options(
+    preferences: &[PrefEntry],
+    patterns: &[&str],
+    options: &QueryOptions,
+) -> Result<Vec<PrefEntry>> {
     // Compile all patterns first to fail fast on invalid patterns
-    let compiled_patterns: Vec<Pattern> = patterns
-        .iter()
-        .map(|p| {
-            Pattern::new(p).map_err(|e| {
-                Error::InvalidGlobPattern(format!("Invalid query pattern '{}': {}", p, e))
-            })
-        })
-        .collect::<Result<Vec<_>>>()?;
+    let query = KeyQuery::new(patterns, options)?;
 
     // Filter preferences: keep if ANY pattern matches
     let queried: Vec<PrefEntry> = preferences
         .iter()
-        .filter(|entry| {
-            compiled_patterns
-                .iter()
-                .any(|pattern| pattern.matches(&entry.key))
-        })
+        .filter(|entry| query.matches(&entry.key))
         .cloned()
         .collect();
 
@@ -189,4 +396,54 @@ mod tests {
         assert_eq!(queried.len(), 1);
         assert!(queried.iter().any(|e| e.key == "javascript.enabled"));
     }
+
+    #[test]
+    fn test_query_case_insensitive_glob() {
+        let config = create_test_config();
+        let options = QueryOptions {
+            case_insensitive: true,
+            ..Default::default()
+        };
+        let queried =
+            query_preferences_with_options(&config, &["*COOKIEBEHAVIOR"], &options).unwrap();
+        assert_eq!(queried.len(), 1);
+        assert!(query_preferences(&config, &["*COOKIEBEHAVIOR"])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_query_regex_alternatives() {
+        let config = create_test_config();
+        let options = QueryOptions {
+            mode: MatchMode::Regex,
+            ..Default::default()
+        };
+        let queried =
+            query_preferences_with_options(&config, &[r"^(network\.proxy|javascript)\."], &options)
+                .unwrap();
+        assert_eq!(queried.len(), 2);
+        assert!(query_preferences_with_options(&config, &["(unclosed"], &options).is_err());
+    }
+
+    #[test]
+    fn test_search_terms_and_highlight_ranges() {
+        let options = QueryOptions {
+            mode: MatchMode::Search,
+            case_insensitive: true,
+        };
+        let query = KeyQuery::new(&["cookie BEHAVIOR"], &options).unwrap();
+        assert!(query.matches("network.cookie.cookieBehavior"));
+        assert!(!query.matches("network.cookie.lifetimePolicy"));
+        assert_eq!(
+            query.match_ranges("network.cookie.cookieBehavior"),
+            vec![8..14, 15..29]
+        );
+
+        let glob = KeyQuery::new(&["network.*"], &QueryOptions::default()).unwrap();
+        assert_eq!(
+            glob.match_ranges("network.proxy.type"),
+            vec![Range { start: 0, end: 18 }]
+        );
+    }
 }
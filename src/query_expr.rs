@@ -0,0 +1,782 @@
+//! Value-aware query expressions
+//!
+//! [`query_preferences`](crate::query_preferences) only matches keys. A
+//! [`PrefQuery`] filters on everything a [`PrefEntry`] carries, so audits can
+//! be expressed without post-processing JSON output:
+//!
+//! ```text
+//! key ~ "network.*" AND value > 100
+//! value ~ /^https?:/i OR type == String
+//! source == builtin AND NOT explanation
+//! locked OR (user AND pref_type != user)
+//! modified AND key ~ "privacy.*"
+//! ```
+//!
+//! # Syntax
+//!
+//! - Fields: `key`, `value`, `type` (`Bool`, `Integer`, `Float`, `String`,
+//!   `Null`), `pref_type` (`user`, `default`, `locked`, `sticky`), `source`
+//!   (`builtin`, `global`, `user`, `policy`), `source_file`, `explanation`
+//! - Operators: `==`, `!=`, `<`, `<=`, `>`, `>=`, `~` and `!~`. `~` matches a
+//!   `/regex/` (with an optional `i` flag) or a glob in a quoted string
+//! - Literals: numbers, `true`, `false`, `null`, `"strings"` or `'strings'`,
+//!   and bare words such as `String` or `builtin`
+//! - Flags: `locked` (locked by `lock_pref()` or the three-argument syntax),
+//!   `sticky`, `user` (has a user value from prefs.js, as with
+//!   `--show-only-modified`, even if it equals the default), `modified` (has a
+//!   user value that differs from the default, see [`PrefSet::is_modified`])
+//!   and `explanation` (has a documented explanation)
+//! - `AND`/`&&`, `OR`/`||`, `NOT`/`!` and parentheses; `AND` binds tighter
+//!   than `OR`. Keywords and field names are case-insensitive
+//!
+//! # Example
+//!
+//! ```rust
+//! use ffcv::{parse_prefs_js, PrefQuery};
+//!
+//! let prefs = parse_prefs_js(r#"
+//!     user_pref("browser.cache.disk.capacity", 1048576);
+//!     user_pref("browser.startup.homepage", "https://example.com");
+//!     lock_pref("app.update.auto", false);
+//! "#)?;
+//!
+//! let query: PrefQuery = "value > 1000 OR (locked AND value == false)".parse()?;
+//! let matched = query.filter(&prefs);
+//! assert_eq!(matched.len(), 2);
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::error::{Error, Result};
+use crate::pref_set::{is_modified_in, PrefSet};
+use crate::types::{PrefEntry, PrefSource, PrefType, PrefValue, PrefValueExt};
+use glob::Pattern;
+use regex::{Regex, RegexBuilder};
+use std::cmp::Ordering;
+
+/// A parsed query expression
+#[derive(Debug, Clone)]
+pub struct PrefQuery {
+    expr: Expr,
+}
+
+impl PrefQuery {
+    /// Parse a query expression
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidQuery`] with the offending position for syntax
+    /// errors, unknown fields or values, and invalid regular expressions.
+    pub fn parse(expression: &str) -> Result<Self> {
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            len: expression.len(),
+        };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(query_error(token.pos, "unexpected input after expression"));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Whether a preference matches this query
+    ///
+    /// A lone entry carries no default, so `modified` matches every user
+    /// value; use [`matches_in`](Self::matches_in) to compare with defaults.
+    pub fn matches(&self, entry: &PrefEntry) -> bool {
+        self.expr.eval(entry, None)
+    }
+
+    /// Whether a preference of `prefs` matches this query
+    ///
+    /// `modified` compares the user value with the default layers of `prefs`.
+    pub fn matches_in(&self, entry: &PrefEntry, prefs: &PrefSet) -> bool {
+        self.expr.eval(entry, Some(prefs))
+    }
+
+    /// Preferences matching this query, in their original order
+    pub fn filter(&self, preferences: &[PrefEntry]) -> Vec<PrefEntry> {
+        preferences
+            .iter()
+            .filter(|entry| self.matches(entry))
+            .cloned()
+            .collect()
+    }
+
+    /// Effective preferences of `prefs` matching this query, in insertion order
+    pub fn filter_set(&self, prefs: &PrefSet) -> Vec<PrefEntry> {
+        prefs
+            .iter()
+            .filter(|entry| self.matches_in(entry, prefs))
+            .cloned()
+            .collect()
+    }
+}
+
+impl std::str::FromStr for PrefQuery {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        PrefQuery::parse(s)
+    }
+}
+
+/// Filter preferences with a query expression
+///
+/// Shorthand for parsing a [`PrefQuery`] and calling [`PrefQuery::filter`].
+///
+/// # Example
+///
+/// ```rust
+/// use ffcv::{filter_preferences, parse_prefs_js};
+///
+/// let prefs = parse_prefs_js(r#"user_pref("network.proxy.type", 1);"#)?;
+/// let matched = filter_preferences(&prefs, r#"key ~ "network.*" && value != 0"#)?;
+/// assert_eq!(matched.len(), 1);
+/// # Ok::<(), ffcv::Error>(())
+/// ```
+pub fn filter_preferences(preferences: &[PrefEntry], expression: &str) -> Result<Vec<PrefEntry>> {
+    Ok(PrefQuery::parse(expression)?.filter(preferences))
+}
+
+fn query_error(pos: usize, message: impl std::fmt::Display) -> Error {
+    Error::InvalidQuery(format!("{} (at position {})", message, pos))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match,
+    NotMatch,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Op(CmpOp),
+    Word(String),
+    Str(String),
+    Number(f64),
+    Regex {
+        source: String,
+        case_insensitive: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    pos: usize,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '*' | '?' | '[' | ']' | '{' | '}')
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<(usize, char)> = input.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (pos, c) = chars[i];
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+        let mut push = |kind, width| {
+            tokens.push(Token { kind, pos });
+            width
+        };
+
+        i += match (c, next) {
+            (c, _) if c.is_whitespace() => 1,
+            ('(', _) => push(TokenKind::LParen, 1),
+            (')', _) => push(TokenKind::RParen, 1),
+            ('&', Some('&')) => push(TokenKind::And, 2),
+            ('|', Some('|')) => push(TokenKind::Or, 2),
+            ('=', Some('=')) => push(TokenKind::Op(CmpOp::Eq), 2),
+            ('!', Some('=')) => push(TokenKind::Op(CmpOp::Ne), 2),
+            ('!', Some('~')) => push(TokenKind::Op(CmpOp::NotMatch), 2),
+            ('!', _) => push(TokenKind::Not, 1),
+            ('<', Some('=')) => push(TokenKind::Op(CmpOp::Le), 2),
+            ('>', Some('=')) => push(TokenKind::Op(CmpOp::Ge), 2),
+            ('<', _) => push(TokenKind::Op(CmpOp::Lt), 1),
+            ('>', _) => push(TokenKind::Op(CmpOp::Gt), 1),
+            ('~', _) => push(TokenKind::Op(CmpOp::Match), 1),
+            ('"' | '\'', _) => {
+                let (text, width) = read_delimited(&chars[i..], c)
+                    .ok_or_else(|| query_error(pos, "unterminated string"))?;
+                push(TokenKind::Str(text), width)
+            }
+            ('/', _) => {
+                let (source, mut width) = read_delimited(&chars[i..], '/')
+                    .ok_or_else(|| query_error(pos, "unterminated regular expression"))?;
+                let mut case_insensitive = false;
+                while let Some(&(_, flag)) = chars.get(i + width) {
+                    match flag {
+                        'i' => case_insensitive = true,
+                        c if c.is_alphanumeric() => {
+                            return Err(query_error(
+                                pos,
+                                format!("unsupported regular expression flag '{}'", c),
+                            ))
+                        }
+                        _ => break,
+                    }
+                    width += 1;
+                }
+                push(
+                    TokenKind::Regex {
+                        source,
+                        case_insensitive,
+                    },
+                    width,
+                )
+            }
+            (c, _)
+                if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) =>
+            {
+                let len = chars[i + 1..]
+                    .iter()
+                    .take_while(|&&(_, c)| c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E')
+                    .count()
+                    + 1;
+                let end = chars.get(i + len).map(|&(p, _)| p).unwrap_or(input.len());
+                let text = &input[pos..end];
+                let number = text
+                    .parse::<f64>()
+                    .map_err(|_| query_error(pos, format!("invalid number '{}'", text)))?;
+                push(TokenKind::Number(number), len)
+            }
+            (c, _) if is_word_char(c) => {
+                let len = chars[i..]
+                    .iter()
+                    .take_while(|&&(_, c)| is_word_char(c))
+                    .count();
+                let end = chars.get(i + len).map(|&(p, _)| p).unwrap_or(input.len());
+                let word = &input[pos..end];
+                let kind = match word.to_ascii_lowercase().as_str() {
+                    "and" => TokenKind::And,
+                    "or" => TokenKind::Or,
+                    "not" => TokenKind::Not,
+                    _ => TokenKind::Word(word.to_string()),
+                };
+                push(kind, len)
+            }
+            (c, _) => return Err(query_error(pos, format!("unexpected character '{}'", c))),
+        };
+    }
+
+    Ok(tokens)
+}
+
+/// Read a delimited literal starting at `chars[0]`, returning its text and width
+///
+/// A backslash escapes the delimiter; other escapes are kept as written so
+/// regular expressions like `/\d+/` reach the regex engine unchanged.
+fn read_delimited(chars: &[(usize, char)], delimiter: char) -> Option<(String, usize)> {
+    let mut text = String::new();
+    let mut i = 1;
+    while let Some(&(_, c)) = chars.get(i) {
+        match c {
+            '\\' if chars.get(i + 1).map(|&(_, c)| c) == Some(delimiter) => {
+                text.push(delimiter);
+                i += 2;
+            }
+            '\\' if delimiter != '/' && chars.get(i + 1).map(|&(_, c)| c) == Some('\\') => {
+                text.push('\\');
+                i += 2;
+            }
+            c if c == delimiter => return Some((text, i + 1)),
+            c => {
+                text.push(c);
+                i += 1;
+            }
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Flag(Flag),
+    Compare(Field, CmpOp, Operand),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flag {
+    Locked,
+    Sticky,
+    User,
+    Modified,
+    Explained,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Key,
+    Value,
+    Type,
+    PrefType,
+    Source,
+    SourceFile,
+    Explanation,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "key" | "name" => Field::Key,
+            "value" => Field::Value,
+            "type" => Field::Type,
+            "pref_type" => Field::PrefType,
+            "source" => Field::Source,
+            "source_file" => Field::SourceFile,
+            "explanation" => Field::Explanation,
+            _ => return None,
+        })
+    }
+
+    /// Allowed values for fields with a fixed vocabulary
+    fn vocabulary(self) -> Option<&'static [&'static str]> {
+        match self {
+            Field::Type => Some(&["Bool", "Integer", "Float", "String", "Null"]),
+            Field::PrefType => Some(&["user", "default", "locked", "sticky"]),
+            Field::Source => Some(&["builtin", "global", "user", "policy"]),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Bool(bool),
+    Null,
+    Number(f64),
+    Text(String),
+    Pattern(Matcher),
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Regex(Regex),
+    Glob(Pattern),
+}
+
+impl Matcher {
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            Matcher::Regex(regex) => regex.is_match(text),
+            Matcher::Glob(pattern) => pattern.matches(text),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect_something(&mut self, what: &str) -> Result<Token> {
+        let len = self.len;
+        self.next()
+            .ok_or_else(|| query_error(len, format!("expected {}", what)))
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut left = self.parse_and()?;
+        while self.peek().is_some_and(|t| t.kind == TokenKind::Or) {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut left = self.parse_not()?;
+        while self.peek().is_some_and(|t| t.kind == TokenKind::And) {
+            self.pos += 1;
+            left = Expr::And(Box::new(left), Box::new(self.parse_not()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr> {
+        if self.peek().is_some_and(|t| t.kind == TokenKind::Not) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        let token = self.expect_something("a condition")?;
+        match token.kind {
+            TokenKind::LParen => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token {
+                        kind: TokenKind::RParen,
+                        ..
+                    }) => Ok(expr),
+                    Some(other) => Err(query_error(other.pos, "expected ')'")),
+                    None => Err(query_error(self.len, "missing ')'")),
+                }
+            }
+            TokenKind::Word(name) => self.parse_predicate(&name, token.pos),
+            _ => Err(query_error(token.pos, "expected a field name, flag or '('")),
+        }
+    }
+
+    fn parse_predicate(&mut self, name: &str, pos: usize) -> Result<Expr> {
+        let op = match self.peek() {
+            Some(Token {
+                kind: TokenKind::Op(op),
+                ..
+            }) => Some(*op),
+            _ => None,
+        };
+
+        let Some(op) = op else {
+            return match name.to_ascii_lowercase().as_str() {
+                "locked" => Ok(Expr::Flag(Flag::Locked)),
+                "sticky" => Ok(Expr::Flag(Flag::Sticky)),
+                "user" => Ok(Expr::Flag(Flag::User)),
+                "modified" => Ok(Expr::Flag(Flag::Modified)),
+                "explanation" | "explained" => Ok(Expr::Flag(Flag::Explained)),
+                _ => Err(query_error(
+                    pos,
+                    format!(
+                        "'{}' is not a flag; expected locked, sticky, user, modified, \
+                         explanation or a comparison",
+                        name
+                    ),
+                )),
+            };
+        };
+        self.pos += 1;
+
+        let field = Field::from_name(name).ok_or_else(|| {
+            query_error(
+                pos,
+                format!(
+                    "unknown field '{}'; expected key, value, type, pref_type, source, \
+                     source_file or explanation",
+                    name
+                ),
+            )
+        })?;
+
+        let token = self.expect_something("a value")?;
+        let operand = operand_for(field, op, token)?;
+        Ok(Expr::Compare(field, op, operand))
+    }
+}
+
+/// Build and validate the right-hand side of a comparison
+fn operand_for(field: Field, op: CmpOp, token: Token) -> Result<Operand> {
+    let pos = token.pos;
+    let is_match = matches!(op, CmpOp::Match | CmpOp::NotMatch);
+
+    let operand = match token.kind {
+        TokenKind::Regex {
+            source,
+            case_insensitive,
+        } => {
+            if !is_match {
+                return Err(query_error(pos, "regular expressions need '~' or '!~'"));
+            }
+            let regex = RegexBuilder::new(&source)
+                .case_insensitive(case_insensitive)
+                .build()
+                .map_err(|e| query_error(pos, format!("invalid regular expression: {}", e)))?;
+            return Ok(Operand::Pattern(Matcher::Regex(regex)));
+        }
+        TokenKind::Str(text) | TokenKind::Word(text) if is_match => {
+            let pattern = Pattern::new(&text)
+                .map_err(|e| query_error(pos, format!("invalid glob pattern: {}", e)))?;
+            return Ok(Operand::Pattern(Matcher::Glob(pattern)));
+        }
+        _ if is_match => return Err(query_error(pos, "'~' needs a /regex/ or a quoted glob")),
+        TokenKind::Str(text) => Operand::Text(text),
+        TokenKind::Number(n) => Operand::Number(n),
+        TokenKind::Word(word) => match word.to_ascii_lowercase().as_str() {
+            "true" => Operand::Bool(true),
+            "false" => Operand::Bool(false),
+            "null" => Operand::Null,
+            _ => Operand::Text(word),
+        },
+        _ => return Err(query_error(pos, "expected a value")),
+    };
+
+    // Fields with a fixed vocabulary only compare for equality
+    if let Some(vocabulary) = field.vocabulary() {
+        if !matches!(op, CmpOp::Eq | CmpOp::Ne) {
+            return Err(query_error(
+                pos,
+                "only ==, !=, ~ and !~ apply to this field",
+            ));
+        }
+        let text = match &operand {
+            Operand::Text(text) => text.clone(),
+            Operand::Null => "null".to_string(),
+            Operand::Bool(b) => b.to_string(),
+            Operand::Number(n) => n.to_string(),
+            Operand::Pattern(_) => unreachable!("patterns returned above"),
+        };
+        let known = vocabulary
+            .iter()
+            .find(|known| known.eq_ignore_ascii_case(&text))
+            .ok_or_else(|| {
+                query_error(
+                    pos,
+                    format!(
+                        "unknown value '{}'; expected one of {}",
+                        text,
+                        vocabulary.join(", ")
+                    ),
+                )
+            })?;
+        return Ok(Operand::Text(known.to_string()));
+    }
+
+    Ok(operand)
+}
+
+impl Expr {
+    fn eval(&self, entry: &PrefEntry, prefs: Option<&PrefSet>) -> bool {
+        match self {
+            Expr::And(left, right) => left.eval(entry, prefs) && right.eval(entry, prefs),
+            Expr::Or(left, right) => left.eval(entry, prefs) || right.eval(entry, prefs),
+            Expr::Not(inner) => !inner.eval(entry, prefs),
+            Expr::Flag(flag) => match flag {
+                Flag::Locked => entry.is_locked(),
+                Flag::Sticky => entry.pref_type == PrefType::Sticky,
+                Flag::User => entry.source == Some(PrefSource::User),
+                Flag::Modified => is_modified_in(entry, prefs),
+                Flag::Explained => entry.explanation.is_some(),
+            },
+            Expr::Compare(field, op, operand) => {
+                let (positive, negated) = match op {
+                    CmpOp::Ne => (CmpOp::Eq, true),
+                    CmpOp::NotMatch => (CmpOp::Match, true),
+                    op => (*op, false),
+                };
+                let result = match field {
+                    Field::Value => compare_value(&entry.value, positive, operand),
+                    _ => field_text(entry, *field)
+                        .is_some_and(|text| compare_text(&text, positive, operand)),
+                };
+                result != negated
+            }
+        }
+    }
+}
+
+/// Textual form of a non-value field, `None` when the entry doesn't have it
+fn field_text(entry: &PrefEntry, field: Field) -> Option<String> {
+    match field {
+        Field::Key => Some(entry.key.clone()),
+        Field::Type => Some(entry.value.type_name().to_string()),
+        Field::PrefType => Some(
+            match entry.pref_type {
+                PrefType::User => "user",
+                PrefType::Default => "default",
+                PrefType::Locked => "locked",
+                PrefType::Sticky => "sticky",
+            }
+            .to_string(),
+        ),
+        Field::Source => entry.source.as_ref().map(|source| {
+            match source {
+                PrefSource::BuiltIn => "builtin",
+                PrefSource::GlobalDefault => "global",
+                PrefSource::User => "user",
+                PrefSource::SystemPolicy => "policy",
+            }
+            .to_string()
+        }),
+        Field::SourceFile => entry.source_file.clone(),
+        Field::Explanation => entry.explanation.map(str::to_string),
+        Field::Value => None,
+    }
+}
+
+fn compare_text(text: &str, op: CmpOp, operand: &Operand) -> bool {
+    match operand {
+        Operand::Pattern(matcher) => op == CmpOp::Match && matcher.is_match(text),
+        Operand::Text(other) => ordering_holds(text.cmp(other.as_str()), op),
+        Operand::Number(n) => text
+            .parse::<f64>()
+            .ok()
+            .and_then(|value| value.partial_cmp(n))
+            .is_some_and(|ordering| ordering_holds(ordering, op)),
+        Operand::Bool(_) | Operand::Null => false,
+    }
+}
+
+fn compare_value(value: &PrefValue, op: CmpOp, operand: &Operand) -> bool {
+    match operand {
+        Operand::Pattern(matcher) => {
+            let text = match value {
+                PrefValue::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            op == CmpOp::Match && matcher.is_match(&text)
+        }
+        Operand::Bool(b) => op == CmpOp::Eq && value.as_bool() == Some(*b),
+        Operand::Null => op == CmpOp::Eq && value.is_null(),
+        Operand::Number(n) => value
+            .as_f64()
+            .and_then(|v| v.partial_cmp(n))
+            .is_some_and(|ordering| ordering_holds(ordering, op)),
+        Operand::Text(text) => value
+            .as_str()
+            .is_some_and(|s| ordering_holds(s.cmp(text.as_str()), op)),
+    }
+}
+
+fn ordering_holds(ordering: Ordering, op: CmpOp) -> bool {
+    match op {
+        CmpOp::Eq => ordering == Ordering::Equal,
+        CmpOp::Lt => ordering == Ordering::Less,
+        CmpOp::Le => ordering != Ordering::Greater,
+        CmpOp::Gt => ordering == Ordering::Greater,
+        CmpOp::Ge => ordering != Ordering::Less,
+        CmpOp::Ne | CmpOp::Match | CmpOp::NotMatch => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_prefs_js;
+
+    fn prefs() -> Vec<PrefEntry> {
+        let mut prefs = parse_prefs_js(
+            r#"
+            user_pref("browser.cache.disk.capacity", 1048576);
+            user_pref("browser.startup.homepage", "https://example.com");
+            user_pref("network.proxy.type", 1);
+            pref("general.smoothScroll", true);
+            lock_pref("app.update.auto", false);
+            sticky_pref("browser.zoom.ratio", 1.5);
+            "#,
+        )
+        .unwrap();
+        for pref in &mut prefs {
+            pref.source = Some(match pref.pref_type {
+                PrefType::User => PrefSource::User,
+                _ => PrefSource::BuiltIn,
+            });
+        }
+        prefs[0].explanation = Some("Disk cache size in KB");
+        prefs
+    }
+
+    fn keys(expression: &str) -> Vec<String> {
+        PrefQuery::parse(expression)
+            .unwrap()
+            .filter(&prefs())
+            .into_iter()
+            .map(|entry| entry.key)
+            .collect()
+    }
+
+    #[test]
+    fn test_value_comparisons() {
+        assert_eq!(keys("value > 100"), vec!["browser.cache.disk.capacity"]);
+        assert_eq!(keys("value == true"), vec!["general.smoothScroll"]);
+        assert_eq!(keys("value ~ /^HTTPS:/i"), vec!["browser.startup.homepage"]);
+        assert_eq!(keys("value >= 1 AND value < 2").len(), 2);
+        assert_eq!(keys(r#"value == "https://example.com""#).len(), 1);
+    }
+
+    #[test]
+    fn test_metadata_predicates() {
+        assert_eq!(keys("type == string"), vec!["browser.startup.homepage"]);
+        assert_eq!(keys("locked"), vec!["app.update.auto"]);
+        assert_eq!(keys("pref_type == sticky"), vec!["browser.zoom.ratio"]);
+        assert_eq!(keys("source == builtin").len(), 3);
+        assert_eq!(keys("explanation"), vec!["browser.cache.disk.capacity"]);
+        assert_eq!(keys("user AND NOT explanation").len(), 2);
+    }
+
+    #[test]
+    fn test_modified_compares_with_default() {
+        let mut set: PrefSet = prefs().into_iter().collect();
+        let mut user_pref = |key: &str, value| {
+            let mut entry = set.get(key).unwrap().clone();
+            entry.value = value;
+            entry.pref_type = PrefType::User;
+            entry.source = Some(PrefSource::User);
+            set.insert(entry);
+        };
+        user_pref("general.smoothScroll", PrefValue::Bool(true));
+        user_pref("browser.zoom.ratio", PrefValue::Float(2.0));
+
+        let query = PrefQuery::parse("modified").unwrap();
+        let modified: Vec<String> = query.filter_set(&set).into_iter().map(|e| e.key).collect();
+        // The smoothScroll user value equals its default
+        assert_eq!(
+            modified,
+            [
+                "browser.cache.disk.capacity",
+                "browser.startup.homepage",
+                "network.proxy.type",
+                "browser.zoom.ratio"
+            ]
+        );
+        assert_eq!(PrefQuery::parse("user").unwrap().filter_set(&set).len(), 5);
+    }
+
+    #[test]
+    fn test_boolean_operators_and_precedence() {
+        assert_eq!(
+            keys(r#"key ~ "browser.*" && (locked || value > 100 || sticky)"#),
+            vec!["browser.cache.disk.capacity", "browser.zoom.ratio"]
+        );
+        // AND binds tighter than OR
+        assert_eq!(keys("locked OR sticky AND value == 1").len(), 1);
+        assert_eq!(keys("!(type == Integer) and key !~ /^browser/").len(), 2);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (expression, message) in [
+            ("value >", "expected a value"),
+            ("(locked", "missing ')'"),
+            ("colour == red", "unknown field"),
+            ("type == Text", "unknown value"),
+            ("value == /x/", "need '~'"),
+            ("value ~ /(/", "invalid regular expression"),
+            ("locked sticky", "unexpected input"),
+        ] {
+            let err = PrefQuery::parse(expression).unwrap_err().to_string();
+            assert!(err.contains(message), "{}: {}", expression, err);
+        }
+    }
+}
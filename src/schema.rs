@@ -0,0 +1,380 @@
+//! JSON representation of preferences
+//!
+//! [`PrefValue`] serializes as a plain JSON value and [`PrefEntry`] adds an
+//! explicit `type` next to it, so consumers see the same value shape in
+//! every output:
+//!
+//! ```json
+//! {
+//!   "key": "browser.startup.homepage",
+//!   "value": "https://example.com",
+//!   "type": "String",
+//!   "pref_type": "user",
+//!   "source": "user",
+//!   "source_file": "prefs.js"
+//! }
+//! ```
+//!
+//! The layout is versioned by [`PREF_SCHEMA_VERSION`] and described by the
+//! JSON Schema in [`PREF_JSON_SCHEMA`] (also shipped as
+//! `schema/ffcv-prefs.schema.json`). Serialized [`MergedPreferences`] carry
+//! the version in a `schema_version` field.
+//!
+//! Version 0 (ffcv 1.1 and earlier) serialized values externally tagged
+//! (`"value": {"String": "x"}`) without `type`. Deserialization accepts both
+//! forms, so older dumps still load.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ffcv::{PrefEntry, PrefValue};
+//!
+//! let current: PrefEntry = serde_json::from_str(
+//!     r#"{"key": "a", "value": 1.0, "type": "Float", "pref_type": "user"}"#,
+//! )?;
+//! let legacy: PrefEntry = serde_json::from_str(
+//!     r#"{"key": "a", "value": {"Float": 1.0}, "pref_type": "user"}"#,
+//! )?;
+//! assert_eq!(current.value, PrefValue::Float(1.0));
+//! assert_eq!(legacy.value, current.value);
+//!
+//! let json = serde_json::to_value(&current)?;
+//! assert_eq!(json["value"], 1.0);
+//! assert_eq!(json["type"], "Float");
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+
+use crate::explanations::get_preference_explanation_static;
+use crate::types::{MergedPreferences, PrefEntry, PrefSource, PrefType, PrefValue, PrefValueExt};
+use serde::de::{self, Deserializer, MapAccess, Visitor};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Version of the JSON layout of [`PrefEntry`] and [`MergedPreferences`]
+pub const PREF_SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema (draft 2020-12) for serialized preferences
+///
+/// Covers a single entry, the json-array output (a list of entries), the
+/// json-object output (key to value) and [`MergedPreferences`].
+pub const PREF_JSON_SCHEMA: &str = include_str!("../schema/ffcv-prefs.schema.json");
+
+impl Serialize for PrefValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PrefValue::Bool(b) => serializer.serialize_bool(*b),
+            PrefValue::Integer(i) => serializer.serialize_i64(*i),
+            PrefValue::Float(f) => serializer.serialize_f64(*f),
+            PrefValue::String(s) => serializer.serialize_str(s),
+            PrefValue::Null => serializer.serialize_unit(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PrefValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(PrefValueVisitor)
+    }
+}
+
+/// Accepts plain values and the externally tagged form of version 0
+struct PrefValueVisitor;
+
+impl<'de> Visitor<'de> for PrefValueVisitor {
+    type Value = PrefValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a boolean, number, string or null preference value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<PrefValue, E> {
+        Ok(PrefValue::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<PrefValue, E> {
+        Ok(PrefValue::Integer(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<PrefValue, E> {
+        Ok(i64::try_from(v)
+            .map(PrefValue::Integer)
+            .unwrap_or(PrefValue::Float(v as f64)))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<PrefValue, E> {
+        Ok(PrefValue::Float(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<PrefValue, E> {
+        Ok(PrefValue::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<PrefValue, E> {
+        Ok(PrefValue::String(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<PrefValue, E> {
+        Ok(PrefValue::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<PrefValue, E> {
+        Ok(PrefValue::Null)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<PrefValue, A::Error> {
+        let tag: String = map
+            .next_key()?
+            .ok_or_else(|| de::Error::custom("empty object is not a preference value"))?;
+        let value = match tag.as_str() {
+            "Bool" => PrefValue::Bool(map.next_value()?),
+            "Integer" => PrefValue::Integer(map.next_value()?),
+            "Float" => PrefValue::Float(map.next_value()?),
+            "String" => PrefValue::String(map.next_value()?),
+            "Null" => {
+                map.next_value::<de::IgnoredAny>()?;
+                PrefValue::Null
+            }
+            other => {
+                return Err(de::Error::unknown_variant(
+                    other,
+                    &["Bool", "Integer", "Float", "String", "Null"],
+                ))
+            }
+        };
+        if map.next_key::<de::IgnoredAny>()?.is_some() {
+            return Err(de::Error::custom(
+                "tagged preference value must have exactly one field",
+            ));
+        }
+        Ok(value)
+    }
+}
+
+impl Serialize for PrefEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let len = 4
+            + usize::from(self.explanation.is_some())
+            + usize::from(self.source.is_some())
+            + usize::from(self.source_file.is_some())
+            + usize::from(self.locked.is_some());
+        let mut state = serializer.serialize_struct("PrefEntry", len)?;
+        state.serialize_field("key", &self.key)?;
+        state.serialize_field("value", &self.value)?;
+        state.serialize_field("type", self.value.type_name())?;
+        state.serialize_field("pref_type", &self.pref_type)?;
+        if let Some(explanation) = self.explanation {
+            state.serialize_field("explanation", explanation)?;
+        }
+        if let Some(source) = &self.source {
+            state.serialize_field("source", source)?;
+        }
+        if let Some(source_file) = &self.source_file {
+            state.serialize_field("source_file", source_file)?;
+        }
+        if let Some(locked) = self.locked {
+            state.serialize_field("locked", &locked)?;
+        }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for PrefEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        PrefEntryRepr::deserialize(deserializer)
+            .and_then(|repr| PrefEntry::try_from(repr).map_err(de::Error::custom))
+    }
+}
+
+/// Serialized form of [`PrefEntry`], in either schema version
+#[derive(Deserialize)]
+struct PrefEntryRepr {
+    key: String,
+    value: PrefValue,
+    #[serde(rename = "type")]
+    value_type: Option<String>,
+    pref_type: PrefType,
+    explanation: Option<String>,
+    source: Option<PrefSource>,
+    source_file: Option<String>,
+    locked: Option<bool>,
+}
+
+impl TryFrom<PrefEntryRepr> for PrefEntry {
+    type Error = String;
+
+    fn try_from(repr: PrefEntryRepr) -> Result<Self, Self::Error> {
+        let value = match (repr.value_type.as_deref(), repr.value) {
+            // Version 0 wrote Null as the bare variant name
+            (None, PrefValue::String(s)) if s == "Null" => PrefValue::Null,
+            (None, value) => value,
+            // Whole floats such as 1.0 may have been written as 1
+            (Some("Float"), PrefValue::Integer(i)) => PrefValue::Float(i as f64),
+            (Some(expected), value) if expected == value.type_name() => value,
+            (Some(expected), value) => {
+                return Err(format!(
+                    "preference '{}' has type {} but its value {} is {}",
+                    repr.key,
+                    expected,
+                    value,
+                    value.type_name()
+                ))
+            }
+        };
+
+        // Explanations are static text; take the current one for the key
+        let explanation = repr
+            .explanation
+            .and_then(|_| get_preference_explanation_static(&repr.key));
+
+        Ok(PrefEntry {
+            key: repr.key,
+            value,
+            pref_type: repr.pref_type,
+            explanation,
+            source: repr.source,
+            source_file: repr.source_file,
+            locked: repr.locked,
+        })
+    }
+}
+
+impl Serialize for MergedPreferences {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("MergedPreferences", 6)?;
+        state.serialize_field("schema_version", &PREF_SCHEMA_VERSION)?;
+        state.serialize_field("entries", &self.entries)?;
+        state.serialize_field("install_path", &self.install_path)?;
+        state.serialize_field("profile_path", &self.profile_path)?;
+        state.serialize_field("loaded_sources", &self.loaded_sources)?;
+        state.serialize_field("warnings", &self.warnings)?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values_serialize_untagged_with_type() {
+        let entries = crate::parser::parse_prefs_js(
+            r#"
+            user_pref("a.bool", true);
+            user_pref("a.float", 1.5);
+            user_pref("a.string", "x");
+            "#,
+        )
+        .unwrap();
+        let json = serde_json::to_value(&entries).unwrap();
+        assert_eq!(json[0]["value"], true);
+        assert_eq!(json[0]["type"], "Bool");
+        assert_eq!(json[1]["value"], 1.5);
+        assert_eq!(json[2]["value"], "x");
+        assert_eq!(json[2]["type"], "String");
+
+        let round_trip: Vec<PrefEntry> = serde_json::from_value(json).unwrap();
+        assert_eq!(round_trip[1].value, PrefValue::Float(1.5));
+    }
+
+    #[test]
+    fn test_legacy_dumps_still_load() {
+        let legacy = r#"[
+            {"key": "a", "value": {"String": "x"}, "pref_type": "user", "source": "user"},
+            {"key": "b", "value": {"Integer": 3}, "pref_type": "default"},
+            {"key": "c", "value": "Null", "pref_type": "user"},
+            {"key": "javascript.enabled", "value": {"Bool": false}, "pref_type": "user",
+             "explanation": "old text"}
+        ]"#;
+        let entries: Vec<PrefEntry> = serde_json::from_str(legacy).unwrap();
+        assert_eq!(entries[0].value, PrefValue::String("x".to_string()));
+        assert_eq!(entries[1].value, PrefValue::Integer(3));
+        assert_eq!(entries[2].value, PrefValue::Null);
+        assert!(entries[3].explanation.is_some());
+
+        // In the current form a "Null" string is just a string
+        let current: PrefEntry = serde_json::from_str(
+            r#"{"key": "c", "value": "Null", "type": "String", "pref_type": "user"}"#,
+        )
+        .unwrap();
+        assert_eq!(current.value, PrefValue::String("Null".to_string()));
+
+        let mismatch = serde_json::from_str::<PrefEntry>(
+            r#"{"key": "c", "value": "x", "type": "Bool", "pref_type": "user"}"#,
+        );
+        assert!(mismatch.unwrap_err().to_string().contains("has type Bool"));
+    }
+
+    #[test]
+    fn test_schema_is_valid_json_with_version() {
+        let schema: serde_json::Value = serde_json::from_str(PREF_JSON_SCHEMA).unwrap();
+        assert_eq!(
+            schema["$defs"]["mergedPreferences"]["properties"]["schema_version"]["const"],
+            PREF_SCHEMA_VERSION
+        );
+
+        let merged = MergedPreferences {
+            entries: vec![],
+            install_path: None,
+            profile_path: "/p".into(),
+            loaded_sources: vec![PrefSource::User],
+            warnings: vec![],
+        };
+        let json = serde_json::to_value(&merged).unwrap();
+        assert_eq!(json["schema_version"], PREF_SCHEMA_VERSION);
+        let back: MergedPreferences = serde_json::from_value(json).unwrap();
+        assert_eq!(back.loaded_sources, vec![PrefSource::User]);
+    }
+
+    #[test]
+    fn test_outputs_validate_against_schema() {
+        use crate::formatter::{JsonArrayFormatter, JsonObjectFormatter, PrefFormatter};
+
+        let schema: serde_json::Value = serde_json::from_str(PREF_JSON_SCHEMA).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        let entries = crate::parser::parse_prefs_js(
+            r#"
+            user_pref("a.bool", true);
+            user_pref("a.float", 1.5);
+            user_pref("a.json", "{\"x\":1}");
+            lock_pref("a.int", 3);
+            "#,
+        )
+        .unwrap();
+
+        let format = |formatter: &dyn PrefFormatter| {
+            let mut out = Vec::new();
+            formatter.write_prefs(&entries, &mut out).unwrap();
+            serde_json::from_slice::<serde_json::Value>(&out).unwrap()
+        };
+        let merged = MergedPreferences {
+            entries: entries.clone(),
+            install_path: None,
+            profile_path: "/p".into(),
+            loaded_sources: vec![PrefSource::User],
+            warnings: vec!["w".to_string()],
+        };
+        for (name, json) in [
+            ("entry", serde_json::to_value(&entries[0]).unwrap()),
+            (
+                "json-array",
+                format(&JsonArrayFormatter { decode_json: true }),
+            ),
+            (
+                "json-object",
+                format(&JsonObjectFormatter { decode_json: false }),
+            ),
+            ("merged", serde_json::to_value(&merged).unwrap()),
+        ] {
+            let errors: Vec<String> = validator
+                .iter_errors(&json)
+                .map(|e| e.to_string())
+                .collect();
+            assert!(errors.is_empty(), "{}: {:?}", name, errors);
+        }
+
+        let mismatch =
+            serde_json::json!({"key": "a", "value": "x", "type": "Bool", "pref_type": "user"});
+        assert!(!validator.is_valid(&mismatch));
+    }
+}
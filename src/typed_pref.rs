@@ -0,0 +1,344 @@
+//! Typed preference access
+//!
+//! [`PrefValueExt`](crate::PrefValueExt) returns `Option`s and leaves the
+//! meaning of magic numbers to the caller. This module adds a typed layer:
+//!
+//! - [`FromPrefValue`] converts a [`PrefValue`] into a Rust type, with a
+//!   descriptive error when the value has the wrong type or is out of range
+//! - [`PrefLookup::get_as`](crate::PrefLookup::get_as) and
+//!   [`MergedPreferences::get`](crate::MergedPreferences::get) look a key up
+//!   and convert it, with `_or` variants falling back to a default
+//! - Enums such as [`TrrMode`] and [`CookieBehavior`] decode documented
+//!   preferences; as [`KnownPref`]s they know their key and Firefox's default
+//!
+//! # Example
+//!
+//! ```rust
+//! use ffcv::{parse_prefs_js, CookieBehavior, PrefLookup, StartupPage, TrrMode};
+//!
+//! let prefs = parse_prefs_js(r#"
+//!     user_pref("network.trr.mode", 3);
+//!     user_pref("browser.cache.disk.capacity", 1048576);
+//!     user_pref("browser.startup.page", 7);
+//! "#)?;
+//!
+//! let capacity: i64 = prefs.get_as("browser.cache.disk.capacity")?;
+//! assert_eq!(capacity, 1048576);
+//! assert!(prefs.get_as_or("browser.tabs.warnOnClose", true)?);
+//!
+//! assert_eq!(prefs.get_known::<TrrMode>()?, TrrMode::Only);
+//! // Not set: Firefox's default applies
+//! assert_eq!(prefs.get_known::<CookieBehavior>()?, CookieBehavior::RejectTrackersAndPartitionForeign);
+//!
+//! let err = prefs.get_known::<StartupPage>().unwrap_err();
+//! assert!(err.to_string().contains("7 is not a valid browser.startup.page"));
+//! # Ok::<(), ffcv::Error>(())
+//! ```
+
+use crate::error::{Error, Result};
+use crate::types::{PrefValue, PrefValueExt};
+use std::fmt;
+
+/// Conversion from a preference value
+pub trait FromPrefValue: Sized {
+    /// Convert `value`, the value of preference `key` (used in errors)
+    fn from_pref_value(key: &str, value: &PrefValue) -> Result<Self>;
+}
+
+/// A preference with a fixed key and a Firefox default
+///
+/// Implemented by the enums in this module; see
+/// [`PrefLookup::get_known`](crate::PrefLookup::get_known).
+pub trait KnownPref: FromPrefValue + Default {
+    /// Preference key
+    const KEY: &'static str;
+}
+
+/// Error for a value of the wrong type
+fn type_error(key: &str, expected: &str, value: &PrefValue) -> Error {
+    Error::InvalidPrefValue {
+        key: key.to_string(),
+        message: format!(
+            "expected {}, found {} {}",
+            expected,
+            value.type_name(),
+            value
+        ),
+    }
+}
+
+impl FromPrefValue for PrefValue {
+    fn from_pref_value(_key: &str, value: &PrefValue) -> Result<Self> {
+        Ok(value.clone())
+    }
+}
+
+impl FromPrefValue for bool {
+    fn from_pref_value(key: &str, value: &PrefValue) -> Result<Self> {
+        value
+            .as_bool()
+            .ok_or_else(|| type_error(key, "Bool", value))
+    }
+}
+
+impl FromPrefValue for i64 {
+    fn from_pref_value(key: &str, value: &PrefValue) -> Result<Self> {
+        value
+            .as_i64()
+            .ok_or_else(|| type_error(key, "Integer", value))
+    }
+}
+
+impl FromPrefValue for i32 {
+    fn from_pref_value(key: &str, value: &PrefValue) -> Result<Self> {
+        let wide = i64::from_pref_value(key, value)?;
+        i32::try_from(wide).map_err(|_| Error::InvalidPrefValue {
+            key: key.to_string(),
+            message: format!("{} does not fit in a 32-bit integer", wide),
+        })
+    }
+}
+
+impl FromPrefValue for u32 {
+    fn from_pref_value(key: &str, value: &PrefValue) -> Result<Self> {
+        let wide = i64::from_pref_value(key, value)?;
+        u32::try_from(wide).map_err(|_| Error::InvalidPrefValue {
+            key: key.to_string(),
+            message: format!("{} is not a non-negative 32-bit integer", wide),
+        })
+    }
+}
+
+impl FromPrefValue for f64 {
+    fn from_pref_value(key: &str, value: &PrefValue) -> Result<Self> {
+        value
+            .as_f64()
+            .ok_or_else(|| type_error(key, "Integer or Float", value))
+    }
+}
+
+impl FromPrefValue for String {
+    fn from_pref_value(key: &str, value: &PrefValue) -> Result<Self> {
+        value
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| type_error(key, "String", value))
+    }
+}
+
+/// Decodes JSON-valued strings; other values convert as-is
+impl FromPrefValue for serde_json::Value {
+    fn from_pref_value(_key: &str, value: &PrefValue) -> Result<Self> {
+        Ok(crate::json_pref::json_or_plain(value))
+    }
+}
+
+/// Define an enum for a documented preference
+///
+/// Each variant maps to one literal value; `int` preferences hold Integers,
+/// `str` preferences hold Strings. `default` names Firefox's default.
+macro_rules! pref_enum {
+    (
+        $(#[$meta:meta])*
+        $name:ident, $kind:ident, $key:literal, default $default:ident {
+            $(
+                $(#[$vmeta:meta])*
+                $variant:ident = $value:literal
+            ),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $(
+                $(#[$vmeta])*
+                $variant,
+            )+
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name::$default
+            }
+        }
+
+        impl $name {
+            /// All variants, in value order
+            pub const ALL: &'static [$name] = &[$($name::$variant),+];
+
+            /// Literal value stored in prefs.js
+            pub fn value(&self) -> PrefValue {
+                match self {
+                    $($name::$variant => pref_enum!(@value $kind $value),)+
+                }
+            }
+
+            /// Variant for a literal value, if it is valid
+            pub fn from_value(value: &PrefValue) -> Option<Self> {
+                $(
+                    if *value == pref_enum!(@value $kind $value) {
+                        return Some($name::$variant);
+                    }
+                )+
+                None
+            }
+        }
+
+        impl KnownPref for $name {
+            const KEY: &'static str = $key;
+        }
+
+        impl FromPrefValue for $name {
+            fn from_pref_value(key: &str, value: &PrefValue) -> Result<Self> {
+                pref_enum!(@check $kind key value);
+                $name::from_value(value).ok_or_else(|| Error::InvalidPrefValue {
+                    key: key.to_string(),
+                    message: format!(
+                        "{} is not a valid {} (expected {})",
+                        value,
+                        $key,
+                        [$(format!("{} = {}", pref_enum!(@value $kind $value), stringify!($variant))),+]
+                            .join(", ")
+                    ),
+                })
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.value())
+            }
+        }
+    };
+
+    (@value int $value:literal) => { PrefValue::Integer($value) };
+    (@value str $value:literal) => { PrefValue::String($value.to_string()) };
+    (@check int $key:ident $value:ident) => { i64::from_pref_value($key, $value)?; };
+    (@check str $key:ident $value:ident) => { String::from_pref_value($key, $value)?; };
+}
+
+pref_enum! {
+    /// DNS over HTTPS mode (`network.trr.mode`)
+    TrrMode, int, "network.trr.mode", default Off {
+        /// DoH disabled unless enabled by a rollout (default)
+        Off = 0,
+        /// DoH first, falling back to native DNS
+        First = 2,
+        /// DoH only; resolution fails when DoH fails
+        Only = 3,
+        /// DoH explicitly disabled by the user
+        ExplicitlyOff = 5,
+    }
+}
+
+pref_enum! {
+    /// Third-party cookie policy (`network.cookie.cookieBehavior`)
+    CookieBehavior, int, "network.cookie.cookieBehavior", default RejectTrackersAndPartitionForeign {
+        /// Accept all cookies
+        AcceptAll = 0,
+        /// Reject third-party cookies
+        RejectThirdParty = 1,
+        /// Reject all cookies
+        RejectAll = 2,
+        /// Reject third-party cookies from sites not visited before
+        LimitForeign = 3,
+        /// Reject cookies from known trackers
+        RejectTrackers = 4,
+        /// Reject trackers and partition other third-party cookies
+        /// (Total Cookie Protection, default)
+        RejectTrackersAndPartitionForeign = 5,
+    }
+}
+
+pref_enum! {
+    /// Page shown at startup (`browser.startup.page`)
+    StartupPage, int, "browser.startup.page", default Home {
+        /// Blank page
+        Blank = 0,
+        /// Home page (default)
+        Home = 1,
+        /// Last visited page
+        LastVisited = 2,
+        /// Restore the previous session
+        RestoreSession = 3,
+    }
+}
+
+pref_enum! {
+    /// Enhanced Tracking Protection mode (`browser.contentblocking.category`)
+    ContentBlockingCategory, str, "browser.contentblocking.category", default Standard {
+        /// Balanced protection (default)
+        Standard = "standard",
+        /// Stronger protection that may break some sites
+        Strict = "strict",
+        /// Individually configured protections
+        Custom = "custom",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_prefs_js;
+    use crate::pref_set::PrefLookup;
+
+    #[test]
+    fn test_primitive_conversions() {
+        let prefs = parse_prefs_js(
+            r#"
+            user_pref("a.bool", true);
+            user_pref("a.int", 3000000000);
+            user_pref("a.string", "x");
+            "#,
+        )
+        .unwrap();
+
+        assert!(prefs.get_as::<bool>("a.bool").unwrap());
+        assert_eq!(prefs.get_as::<i64>("a.int").unwrap(), 3_000_000_000);
+        assert_eq!(prefs.get_as::<u32>("a.int").unwrap(), 3_000_000_000);
+        assert!(prefs.get_as::<i32>("a.int").is_err());
+        assert_eq!(prefs.get_as::<f64>("a.int").unwrap(), 3e9);
+        assert_eq!(prefs.get_as_or("missing", 7i64).unwrap(), 7);
+
+        let err = prefs.get_as::<bool>("a.string").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid value for preference 'a.string': expected Bool, found String \"x\""
+        );
+        // A present value of the wrong type is an error even with a default
+        assert!(prefs.get_as_or("a.string", false).is_err());
+        assert!(matches!(
+            prefs.get_as::<bool>("missing"),
+            Err(Error::PrefNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_enum_decoding() {
+        let prefs = parse_prefs_js(
+            r#"
+            user_pref("network.trr.mode", 1);
+            user_pref("browser.contentblocking.category", "strict");
+            user_pref("network.cookie.cookieBehavior", "4");
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            prefs.get_known::<ContentBlockingCategory>().unwrap(),
+            ContentBlockingCategory::Strict
+        );
+        assert_eq!(prefs.get_known::<StartupPage>().unwrap(), StartupPage::Home);
+
+        let err = prefs.get_known::<TrrMode>().unwrap_err().to_string();
+        assert!(err.contains("1 is not a valid network.trr.mode"));
+        assert!(err.contains("0 = Off, 2 = First, 3 = Only, 5 = ExplicitlyOff"));
+
+        let err = prefs.get_known::<CookieBehavior>().unwrap_err().to_string();
+        assert!(err.contains("expected Integer, found String"));
+
+        assert_eq!(TrrMode::Only.value(), PrefValue::Integer(3));
+        assert_eq!(ContentBlockingCategory::Custom.to_string(), "\"custom\"");
+        assert_eq!(TrrMode::ALL.len(), 4);
+    }
+}
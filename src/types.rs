@@ -3,6 +3,8 @@
 //! This module defines the data structures used throughout the ffcv library
 //! for representing Firefox preferences and their metadata.
 
+use crate::pref_set::{PrefLookup, PrefSet};
+use crate::typed_pref::FromPrefValue;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::path::PathBuf;
@@ -26,7 +28,11 @@ use std::path::PathBuf;
 /// assert_eq!(int_val.as_i64(), Some(42));
 /// assert_eq!(string_val.as_str(), Some("example"));
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+///
+/// Values serialize as plain JSON (`true`, `42`, `"text"`, `null`). The
+/// externally tagged form of older versions (`{"String": "text"}`) is still
+/// accepted when deserializing; see [`PREF_SCHEMA_VERSION`](crate::PREF_SCHEMA_VERSION).
+#[derive(Debug, Clone, PartialEq)]
 pub enum PrefValue {
     /// Boolean value (true/false)
     Bool(bool),
@@ -269,7 +275,20 @@ pub enum PrefSource {
 /// assert_eq!(entry.value, PrefValue::Bool(true));
 /// # Ok::<(), ffcv::Error>(())
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// # JSON representation
+///
+/// Entries serialize with a plain `value` and its `type` (`Bool`, `Integer`,
+/// `Float`, `String` or `Null`); fields that are `None` are omitted:
+///
+/// ```json
+/// {"key": "network.trr.mode", "value": 2, "type": "Integer", "pref_type": "user", "source": "user"}
+/// ```
+///
+/// The layout is versioned as [`PREF_SCHEMA_VERSION`](crate::PREF_SCHEMA_VERSION)
+/// and described by [`PREF_JSON_SCHEMA`](crate::PREF_JSON_SCHEMA).
+/// Deserialization also accepts dumps from older versions without `type`.
+#[derive(Debug, Clone)]
 pub struct PrefEntry {
     /// The preference name/key
     pub key: String,
@@ -278,16 +297,12 @@ pub struct PrefEntry {
     /// The type of preference (user, default, locked, sticky)
     pub pref_type: PrefType,
     /// Optional human-readable explanation for the preference
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub explanation: Option<&'static str>,
     /// The source of this preference value
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<PrefSource>,
     /// The origin file for this preference (e.g., "prefs.js", "omni.ja:defaults/pref/browser.js")
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub source_file: Option<String>,
     /// Locked flag from three-argument syntax (Some(true)=locked, Some(false)=unlocked, None=not specified)
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub locked: Option<bool>,
 }
 
@@ -313,8 +328,34 @@ impl PrefEntry {
     /// }
     /// # Ok::<(), ffcv::Error>(())
     /// ```
-    pub fn find_by_key<'a>(prefs: &'a [PrefEntry], key: &str) -> Option<&'a PrefEntry> {
-        prefs.iter().find(|e| e.key == key)
+    ///
+    /// Accepts a slice, a vector or an indexed [`PrefSet`](crate::PrefSet).
+    pub fn find_by_key<'a, P>(prefs: &'a P, key: &str) -> Option<&'a PrefEntry>
+    where
+        P: PrefLookup + ?Sized,
+    {
+        prefs.lookup(key)
+    }
+
+    /// Whether the preference is locked, by `lock_pref()` or a locked
+    /// three-argument `pref()`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ffcv::parse_prefs_js;
+    ///
+    /// let prefs = parse_prefs_js(r#"
+    ///     lock_pref("app.update.auto", false);
+    ///     pref("browser.startup.page", 1, true);
+    ///     pref("network.trr.mode", 2);
+    /// "#)?;
+    /// let locked: Vec<bool> = prefs.iter().map(|entry| entry.is_locked()).collect();
+    /// assert_eq!(locked, vec![true, true, false]);
+    /// # Ok::<(), ffcv::Error>(())
+    /// ```
+    pub fn is_locked(&self) -> bool {
+        self.pref_type == PrefType::Locked || self.locked == Some(true)
     }
 }
 
@@ -369,6 +410,104 @@ pub struct FirefoxInstallation {
     pub has_omni_ja: bool,
 }
 
+impl From<Installation> for FirefoxInstallation {
+    fn from(install: Installation) -> Self {
+        Self {
+            version: install.version,
+            path: install.path,
+            has_greprefs: install.has_greprefs,
+            has_omni_ja: install.has_omni_ja,
+        }
+    }
+}
+
+/// An installation of a Mozilla application found on the system
+///
+/// Extends [`FirefoxInstallation`] with the application, packaging, build
+/// metadata and discovery details. New fields may be added in minor
+/// releases, so it can only be obtained from the locator functions.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ffcv::{find_installation_for_app, Application};
+///
+/// if let Some(install) = find_installation_for_app(&Application::FIREFOX)? {
+///     println!("{} {} ({:?})", install.application, install.version, install.packaging);
+/// }
+/// # Ok::<(), ffcv::Error>(())
+/// ```
+#[derive(Debug, Clone, serde::Serialize)]
+#[non_exhaustive]
+pub struct Installation {
+    /// Firefox version string (e.g., "128.0", "115.5.0esr")
+    pub version: String,
+    /// Identifier of the application this installation belongs to (see [`crate::Application`])
+    pub application: String,
+    /// Path to Firefox installation directory
+    pub path: PathBuf,
+    /// How this installation was packaged
+    pub packaging: Packaging,
+    /// Build metadata (build ID, vendor, update channel, architecture, ...)
+    #[serde(flatten)]
+    pub metadata: crate::InstallationMetadata,
+    /// Name of the discovery probe that found this installation (e.g., "path", "dpkg")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probe: Option<String>,
+    /// Other paths of the same installation (symlinks, resolved path, identical copies)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<PathBuf>,
+    /// Whether greprefs.js exists in this installation
+    pub has_greprefs: bool,
+    /// Whether omni.ja exists in this installation
+    pub has_omni_ja: bool,
+}
+
+impl Installation {
+    /// Record the discovery probe that found this installation
+    pub(crate) fn found_by(mut self, probe: &str) -> Self {
+        self.probe = Some(probe.to_string());
+        self
+    }
+
+    /// Record another path of this installation, ignoring duplicates
+    pub(crate) fn add_alias(&mut self, alias: PathBuf) {
+        if alias != self.path && !self.aliases.contains(&alias) {
+            self.aliases.push(alias);
+        }
+    }
+
+    /// The installation path followed by all alias paths
+    pub fn all_paths(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.path).chain(self.aliases.iter())
+    }
+}
+
+/// How a Firefox installation was packaged
+///
+/// Sandboxed packages (Flatpak, Snap) keep their profiles outside the usual
+/// profile directory, so the packaging type tells where to look for them.
+///
+/// # Example
+///
+/// ```rust
+/// use ffcv::Packaging;
+///
+/// assert_eq!(serde_json::to_string(&Packaging::Flatpak).unwrap(), "\"flatpak\"");
+/// ```
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Packaging {
+    /// Distribution package, tarball or platform installer
+    System,
+    /// Nix store path
+    Nix,
+    /// Flatpak (system-wide or per-user)
+    Flatpak,
+    /// Snap package
+    Snap,
+}
+
 /// Merged preferences from multiple sources
 ///
 /// Represents the result of merging preferences from built-in defaults,
@@ -389,7 +528,10 @@ pub struct FirefoxInstallation {
 ///     warnings: vec![],
 /// };
 /// ```
-#[derive(Debug, Clone, Serialize)]
+///
+/// Serializes with a leading `schema_version` field
+/// ([`PREF_SCHEMA_VERSION`](crate::PREF_SCHEMA_VERSION)).
+#[derive(Debug, Clone, Deserialize)]
 pub struct MergedPreferences {
     /// All merged preference entries (with highest precedence value for each key)
     pub entries: Vec<PrefEntry>,
@@ -402,3 +544,147 @@ pub struct MergedPreferences {
     /// Any warnings or issues encountered during merging
     pub warnings: Vec<String>,
 }
+
+/// Merged preferences with what the merger found along the way
+///
+/// Returned by [`PrefMerger::merge_report`](crate::PrefMerger::merge_report).
+/// More findings may be added in minor releases.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct MergeReport {
+    /// The merged preferences
+    pub merged: MergedPreferences,
+    /// User values ignored because their type differs from the default's
+    pub type_mismatches: Vec<TypeMismatch>,
+    /// Every loaded entry, with the defaults under each user value
+    ///
+    /// Use [`PrefSet::is_modified`] to tell user values that differ from their
+    /// default from those that merely repeat it.
+    #[serde(skip)]
+    pub layers: PrefSet,
+}
+
+/// A user value whose type differs from the default's
+///
+/// Firefox ignores such a user value and keeps the default, so
+/// [`merge_all_preferences`](crate::merge_all_preferences) keeps the default
+/// entry as the effective one and warns; the conflict itself is listed in a
+/// [`MergeReport`]. Firefox stores floats as strings, so a Float matches a
+/// String default.
+///
+/// # Example
+///
+/// ```rust
+/// use ffcv::{parse_prefs_js, TypeMismatch};
+///
+/// let default = parse_prefs_js(r#"pref("network.trr.mode", 0);"#)?.remove(0);
+/// let user = parse_prefs_js(r#"user_pref("network.trr.mode", "2");"#)?.remove(0);
+///
+/// let mismatch = TypeMismatch::check(&user, &default).unwrap();
+/// assert_eq!(
+///     mismatch.to_string(),
+///     "network.trr.mode: user value \"2\" (String) ignored; the default 0 is Integer"
+/// );
+/// # Ok::<(), ffcv::Error>(())
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeMismatch {
+    /// Preference key
+    pub key: String,
+    /// The ignored user entry
+    pub user: PrefEntry,
+    /// The default entry, which stays in effect
+    pub default: PrefEntry,
+}
+
+impl TypeMismatch {
+    /// Type as libpref stores it: Bool, Int or String (floats are strings)
+    fn libpref_type(value: &PrefValue) -> Option<&'static str> {
+        match value {
+            PrefValue::Bool(_) => Some("Bool"),
+            PrefValue::Integer(_) => Some("Int"),
+            PrefValue::Float(_) | PrefValue::String(_) => Some("String"),
+            PrefValue::Null => None,
+        }
+    }
+
+    /// The mismatch between a user entry and its default, if their types differ
+    ///
+    /// Null values are never reported.
+    pub fn check(user: &PrefEntry, default: &PrefEntry) -> Option<Self> {
+        let user_type = Self::libpref_type(&user.value)?;
+        let default_type = Self::libpref_type(&default.value)?;
+        (user_type != default_type).then(|| TypeMismatch {
+            key: user.key.clone(),
+            user: user.clone(),
+            default: default.clone(),
+        })
+    }
+}
+
+impl fmt::Display for TypeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: user value {} ({}) ignored; the default {} is {}",
+            self.key,
+            self.user.value,
+            self.user.value.type_name(),
+            self.default.value,
+            self.default.value.type_name()
+        )
+    }
+}
+
+impl MergedPreferences {
+    /// Index the merged entries for O(1) key lookup and prefix iteration
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ffcv::{find_profile_path, merge_all_preferences, MergeConfig};
+    ///
+    /// let profile = find_profile_path("default-release", None)?;
+    /// let merged = merge_all_preferences(&profile, None, &MergeConfig::default())?;
+    /// let prefs = merged.to_pref_set();
+    ///
+    /// if let Some(entry) = prefs.get("network.trr.mode") {
+    ///     println!("TRR mode: {}", entry.value);
+    /// }
+    /// println!("{} proxy prefs", prefs.prefix("network.proxy.").count());
+    /// # Ok::<(), ffcv::Error>(())
+    /// ```
+    pub fn to_pref_set(&self) -> PrefSet {
+        self.entries.iter().cloned().collect()
+    }
+
+    /// Effective value of `key` converted to `T`
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ffcv::{find_profile_path, merge_all_preferences, MergeConfig, TrrMode};
+    ///
+    /// let profile = find_profile_path("default-release", None)?;
+    /// let merged = merge_all_preferences(&profile, None, &MergeConfig::default())?;
+    ///
+    /// let homepage: String = merged.get("browser.startup.homepage")?;
+    /// let capacity = merged.get_or("browser.cache.disk.capacity", 256_000i64)?;
+    /// let trr: TrrMode = merged.get("network.trr.mode")?;
+    /// # Ok::<(), ffcv::Error>(())
+    /// ```
+    pub fn get<T: FromPrefValue>(&self, key: &str) -> crate::Result<T> {
+        self.entries.get_as(key)
+    }
+
+    /// Effective value of `key` converted to `T`, or `default` when it is not set
+    pub fn get_or<T: FromPrefValue>(&self, key: &str, default: T) -> crate::Result<T> {
+        self.entries.get_as_or(key, default)
+    }
+}
+
+impl PrefLookup for MergedPreferences {
+    fn lookup(&self, key: &str) -> Option<&PrefEntry> {
+        self.entries.lookup(key)
+    }
+}